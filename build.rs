@@ -1,6 +1,18 @@
 // Taken from embassy stm32f3 examples:
 // https://github.com/embassy-rs/embassy/blob/main/examples/stm32f3/build.rs
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
 fn main() {
+    // Put `memory.x` where the linker can find it, rather than letting
+    // embassy-stm32 generate one covering all of flash, so that the
+    // flash log region is kept out of the firmware image
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    fs::copy("memory.x", out.join("memory.x")).unwrap();
+    println!("cargo:rustc-link-search={}", out.display());
+    println!("cargo:rerun-if-changed=memory.x");
+
     println!("cargo:rustc-link-arg-bins=--nmagic");
     println!("cargo:rustc-link-arg-bins=-Tlink.x");
     println!("cargo:rustc-link-arg-bins=-Tdefmt.x");
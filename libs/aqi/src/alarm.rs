@@ -0,0 +1,114 @@
+//! Alarm module
+//!
+//! This module provides a latching alarm for dangerous air quality,
+//! which stays raised until it is acknowledged, even if the AQI
+//! later drops back below the threshold.
+
+/// The AlarmLatch struct raises an alarm once the AQI reaches a
+/// threshold. The alarm stays raised, regardless of later readings,
+/// until it is acknowledged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlarmLatch {
+    threshold: u16,
+    latched: bool,
+}
+
+impl AlarmLatch {
+    /// Create a new AlarmLatch with the alarm not raised.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - AQI at or above which the alarm is raised
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut alarm = AlarmLatch::new(201);
+    /// ```
+    pub const fn new(threshold: u16) -> Self {
+        Self {
+            threshold,
+            latched: false,
+        }
+    }
+
+    /// Record a new AQI reading, raising the alarm if the AQI is at or
+    /// above the threshold.
+    ///
+    /// # Arguments
+    ///
+    /// * `aqi` - The calculated AQI
+    ///
+    /// # Returns
+    ///
+    /// true if the alarm is raised, whether by this reading or an
+    /// earlier one which has not been acknowledged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if alarm.update(report.aqi) {
+    ///     led_controller.set_roles(ALARM_LEDS);
+    /// }
+    /// ```
+    pub fn update(&mut self, aqi: u16) -> bool {
+        if aqi >= self.threshold {
+            self.latched = true;
+        }
+        self.latched
+    }
+
+    /// Clear the alarm. If the AQI is still at or above the threshold,
+    /// the next reading raises it again.
+    pub fn acknowledge(&mut self) {
+        self.latched = false;
+    }
+
+    /// Whether the alarm is currently raised.
+    pub fn is_latched(&self) -> bool {
+        self.latched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latch() {
+        let mut alarm = AlarmLatch::new(201);
+        assert!(!alarm.update(115));
+        assert!(!alarm.update(200));
+        assert!(!alarm.is_latched());
+
+        // Crossing the threshold raises the alarm
+        assert!(alarm.update(201));
+        assert!(alarm.is_latched());
+
+        // The alarm stays raised after the AQI drops
+        assert!(alarm.update(50));
+        assert!(alarm.update(0));
+        assert!(alarm.is_latched());
+    }
+
+    #[test]
+    fn test_acknowledge() {
+        let mut alarm = AlarmLatch::new(201);
+        alarm.update(250);
+        alarm.acknowledge();
+        assert!(!alarm.is_latched());
+        assert!(!alarm.update(100));
+
+        // Acknowledging while still dangerous only clears the alarm
+        // until the next reading
+        alarm.update(300);
+        alarm.acknowledge();
+        assert!(!alarm.is_latched());
+        assert!(alarm.update(300));
+
+        // Acknowledging when no alarm is raised has no effect
+        let mut alarm = AlarmLatch::new(201);
+        alarm.acknowledge();
+        assert!(!alarm.is_latched());
+    }
+}
@@ -0,0 +1,347 @@
+//! Rolling average module
+//!
+//! This module provides a fixed-capacity rolling average over PM2.5
+//! samples, for use when a steadier value is preferred over a single
+//! instantaneous reading, along with an outlier-rejecting average
+//! and agreement measures over a batch of samples, and a mean weighted
+//! by sample confidence.
+
+use crate::calculate_aqi;
+
+/// The RollingAverage struct holds up to `N` of the most recent
+/// samples. Once full, each new sample replaces the oldest one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RollingAverage<const N: usize> {
+    samples: [f32; N],
+    next: usize,
+    len: usize,
+}
+
+impl<const N: usize> RollingAverage<N> {
+    /// Create a new, empty RollingAverage.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut readings: RollingAverage<30> = RollingAverage::new();
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            samples: [0.0; N],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    /// Add a sample, replacing the oldest sample if already full.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample` - A PM2.5 value from the sensor
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// readings.push(data.pm2_5_env as f32);
+    /// ```
+    pub fn push(&mut self, sample: f32) {
+        if N == 0 {
+            return;
+        }
+        self.samples[self.next] = sample;
+        self.next = (self.next + 1) % N;
+        if self.len < N {
+            self.len += 1;
+        }
+    }
+
+    /// The number of samples currently held.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no samples have been added.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove all samples.
+    pub fn clear(&mut self) {
+        self.next = 0;
+        self.len = 0;
+    }
+
+    /// Calculate the mean of the samples currently held.
+    ///
+    /// # Returns
+    ///
+    /// The mean, or None if no samples have been added.
+    pub fn average(&self) -> Option<f32> {
+        if self.is_empty() {
+            return None;
+        }
+        let sum: f32 = self.samples[..self.len].iter().sum();
+        Some(sum / self.len as f32)
+    }
+
+    /// Calculate the AQI of the averaged PM2.5 concentration.
+    ///
+    /// # Returns
+    ///
+    /// The AQI, or None if no samples have been added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if let Some(aqi) = readings.aqi() {
+    ///     hprintln!("Average AQI over {} readings: {}", readings.len(), aqi);
+    /// }
+    /// ```
+    pub fn aqi(&self) -> Option<u16> {
+        self.average().map(calculate_aqi)
+    }
+}
+
+impl<const N: usize> Default for RollingAverage<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Calculates the trimmed mean of a batch of samples, dropping the
+/// single lowest and highest sample before averaging. This rejects an
+/// occasional spike, such as from a single bad read, which would
+/// otherwise skew a plain mean over a few quick reads.
+///
+/// # Arguments
+///
+/// * `samples` - PM2.5 values from the sensor
+///
+/// # Returns
+///
+/// The trimmed mean, or None if fewer than 3 samples are given.
+///
+/// # Examples
+///
+/// ```
+/// let samples = [41.0, 40.0, 250.0, 42.0];
+/// assert_eq!(robust_average(&samples), Some(41.5));
+/// ```
+pub fn robust_average(samples: &[f32]) -> Option<f32> {
+    if samples.len() < 3 {
+        return None;
+    }
+    let mut sum = 0.0;
+    let mut min = f32::INFINITY;
+    let mut max = f32::NEG_INFINITY;
+    for &sample in samples {
+        sum += sample;
+        min = min.min(sample);
+        max = max.max(sample);
+    }
+    Some((sum - min - max) / (samples.len() - 2) as f32)
+}
+
+/// Calculates the mean and population variance of a batch of samples,
+/// e.g. to characterize how stable the sensor output is over a few
+/// quick reads. A high variance indicates a noisy reading which
+/// should be retaken.
+///
+/// # Arguments
+///
+/// * `samples` - PM2.5 values from the sensor
+///
+/// # Returns
+///
+/// A tuple of the mean and variance, both 0.0 if no samples are given.
+///
+/// # Examples
+///
+/// ```
+/// let (mean, variance) = mean_variance(&[40.0, 42.0, 41.0, 41.0]);
+/// assert_eq!(mean, 41.0);
+/// assert_eq!(variance, 0.5);
+/// ```
+pub fn mean_variance(samples: &[f32]) -> (f32, f32) {
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+    let count = samples.len() as f32;
+    let mean = samples.iter().sum::<f32>() / count;
+    let variance = samples
+        .iter()
+        .map(|&sample| (sample - mean) * (sample - mean))
+        .sum::<f32>()
+        / count;
+    (mean, variance)
+}
+
+/// Scores how closely a batch of samples agree, from 0 to 100, e.g. to
+/// show a confidence indicator alongside a reading taken from a few
+/// quick samples. The score is 100 minus the coefficient of variation
+/// (the standard deviation relative to the mean) as a percentage, so a
+/// spread of 10% of the mean scores 90, and a spread as large as the
+/// mean scores 0. A low score suggests the reading should be retaken.
+///
+/// # Arguments
+///
+/// * `samples` - PM2.5 values from the sensor
+///
+/// # Returns
+///
+/// The confidence score, rounded to the nearest whole number. Agreement
+/// cannot be judged from fewer than 2 samples, so they score 0. Samples
+/// which are all 0 agree exactly, so score 100.
+///
+/// # Examples
+///
+/// ```
+/// if confidence(&samples) < 50 {
+///     hprintln!("Low confidence, consider retaking the reading");
+/// }
+/// ```
+pub fn confidence(samples: &[f32]) -> u8 {
+    if samples.len() < 2 {
+        return 0;
+    }
+    let (mean, variance) = mean_variance(samples);
+    if variance == 0.0 {
+        return 100;
+    }
+    if mean <= 0.0 {
+        return 0;
+    }
+    let variation = libm::sqrtf(variance) / mean;
+    libm::roundf(100.0 * (1.0 - variation)).clamp(0.0, 100.0) as u8
+}
+
+/// Calculates the mean of samples of varying quality, weighting each
+/// sample by its confidence so that noisy samples count less, e.g.
+/// combining readings each scored with `confidence`.
+///
+/// # Arguments
+///
+/// * `samples` - `(pm25, confidence)` pairs, with confidence from 0 to
+///   100. Confidences above 100 are treated as 100.
+///
+/// # Returns
+///
+/// The weighted mean, or None if no sample has a nonzero confidence.
+///
+/// # Examples
+///
+/// ```
+/// let samples = [(40.0, 90), (60.0, 10), (500.0, 0)];
+/// assert_eq!(weighted_average(&samples), Some(42.0));
+/// ```
+pub fn weighted_average(samples: &[(f32, u8)]) -> Option<f32> {
+    let mut weighted_sum = 0.0;
+    let mut total_weight = 0u32;
+    for &(sample, confidence) in samples {
+        let weight = confidence.min(100) as u32;
+        weighted_sum += sample * weight as f32;
+        total_weight += weight;
+    }
+    if total_weight == 0 {
+        return None;
+    }
+    Some(weighted_sum / total_weight as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_average() {
+        let mut readings: RollingAverage<4> = RollingAverage::new();
+        assert!(readings.is_empty());
+        assert_eq!(readings.average(), None);
+        assert_eq!(readings.aqi(), None);
+
+        // Synthetic samples collected while the button is held
+        for sample in [40.0, 42.0, 41.0, 41.0] {
+            readings.push(sample);
+        }
+        assert_eq!(readings.len(), 4);
+        assert_eq!(readings.average(), Some(41.0));
+        assert_eq!(readings.aqi(), Some(115));
+
+        // Once full, the oldest sample is replaced
+        readings.push(0.0);
+        assert_eq!(readings.len(), 4);
+        assert_eq!(readings.average(), Some(31.0));
+
+        readings.clear();
+        assert!(readings.is_empty());
+        assert_eq!(readings.average(), None);
+    }
+
+    #[test]
+    fn test_robust_average() {
+        assert_eq!(robust_average(&[]), None);
+        assert_eq!(robust_average(&[41.0, 42.0]), None);
+        assert_eq!(robust_average(&[40.0, 41.0, 42.0]), Some(41.0));
+
+        // A single spike is dropped along with the lowest sample
+        let samples = [41.0, 40.0, 250.0, 42.0, 43.0];
+        assert_eq!(robust_average(&samples), Some(42.0));
+
+        // Only one copy of a repeated extreme is dropped
+        assert_eq!(robust_average(&[5.0, 5.0, 5.0, 9.0]), Some(5.0));
+        assert_eq!(robust_average(&[0.0, 0.0, 10.0, 10.0]), Some(5.0));
+    }
+
+    #[test]
+    fn test_mean_variance() {
+        assert_eq!(mean_variance(&[]), (0.0, 0.0));
+        assert_eq!(mean_variance(&[41.0]), (41.0, 0.0));
+        assert_eq!(mean_variance(&[41.0, 41.0, 41.0]), (41.0, 0.0));
+
+        // Known dataset with a mean of 5 and a standard deviation of 2
+        let samples = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        assert_eq!(mean_variance(&samples), (5.0, 4.0));
+
+        assert_eq!(mean_variance(&[40.0, 42.0, 41.0, 41.0]), (41.0, 0.5));
+    }
+
+    #[test]
+    fn test_confidence() {
+        // Tightly clustered samples, with a spread of about 2% of the mean
+        assert_eq!(confidence(&[40.0, 42.0, 41.0, 41.0]), 98);
+        assert_eq!(confidence(&[41.0, 41.0, 41.0]), 100);
+        assert_eq!(confidence(&[0.0, 0.0, 0.0]), 100);
+
+        // Known dataset with a mean of 5 and a standard deviation of 2
+        let samples = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        assert_eq!(confidence(&samples), 60);
+
+        // Scattered samples, spread further than the mean
+        assert_eq!(confidence(&[2.0, 40.0, 3.0, 1.0]), 0);
+
+        // Too few samples to judge agreement
+        assert_eq!(confidence(&[41.0]), 0);
+        assert_eq!(confidence(&[]), 0);
+    }
+
+    #[test]
+    fn test_weighted_average() {
+        // Equal weights give the plain mean
+        assert_eq!(weighted_average(&[(40.0, 50), (42.0, 50)]), Some(41.0));
+
+        // A confident sample outweighs a noisy one
+        assert_eq!(weighted_average(&[(40.0, 90), (60.0, 10)]), Some(42.0));
+        assert_eq!(weighted_average(&[(40.0, 10), (60.0, 90)]), Some(58.0));
+
+        // Zero-weight samples are ignored entirely
+        assert_eq!(
+            weighted_average(&[(40.0, 90), (60.0, 10), (500.0, 0)]),
+            Some(42.0)
+        );
+        assert_eq!(weighted_average(&[(500.0, 0)]), None);
+        assert_eq!(weighted_average(&[]), None);
+
+        // Confidences above 100 count as 100
+        assert_eq!(weighted_average(&[(40.0, 100), (60.0, 255)]), Some(50.0));
+    }
+}
@@ -0,0 +1,153 @@
+//! Calibration module
+//!
+//! This module provides a simple zero-offset calibration for correcting
+//! sensor drift. A baseline is captured by recording a number of samples
+//! in known-clean air, and the average of those samples is subtracted from
+//! subsequent raw readings.
+
+/// The Calibrator struct accumulates `N` baseline samples taken in
+/// clean air and uses their average as an offset for later readings.
+/// Until all `N` samples have been recorded, no offset is applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibrator<const N: usize> {
+    sum: f32,
+    count: usize,
+    offset: f32,
+}
+
+impl<const N: usize> Calibrator<N> {
+    /// Create a new Calibrator with no baseline recorded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut calibrator: Calibrator<10> = Calibrator::new();
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            sum: 0.0,
+            count: 0,
+            offset: 0.0,
+        }
+    }
+
+    /// Record a baseline sample taken in known-clean air. Once `N`
+    /// samples have been recorded, the offset is computed and any
+    /// further samples are ignored until the Calibrator is reset.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample` - A raw PM2.5 value from the sensor
+    ///
+    /// # Returns
+    ///
+    /// true if the baseline is complete, false if more samples are needed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut calibrator: Calibrator<3> = Calibrator::new();
+    /// calibrator.record(2.0);
+    /// calibrator.record(3.0);
+    /// assert!(calibrator.record(4.0));
+    /// assert_eq!(calibrator.offset(), 3.0);
+    /// ```
+    pub fn record(&mut self, sample: f32) -> bool {
+        if self.is_calibrated() {
+            return true;
+        }
+
+        self.sum += sample;
+        self.count += 1;
+
+        if self.is_calibrated() {
+            self.offset = self.sum / N as f32;
+        }
+        self.is_calibrated()
+    }
+
+    /// Whether all `N` baseline samples have been recorded.
+    pub fn is_calibrated(&self) -> bool {
+        self.count >= N
+    }
+
+    /// The current offset. This is 0.0 until the baseline is complete.
+    pub fn offset(&self) -> f32 {
+        self.offset
+    }
+
+    /// Apply the baseline offset to a raw reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw` - A raw PM2.5 value from the sensor
+    ///
+    /// # Returns
+    ///
+    /// The raw value less the offset, clamped at zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pm25 = calibrator.apply(data.pm2_5_env as f32);
+    /// let aqi = calculate_aqi(pm25);
+    /// ```
+    pub fn apply(&self, raw: f32) -> f32 {
+        let corrected = raw - self.offset;
+        if corrected > 0.0 {
+            corrected
+        } else {
+            0.0
+        }
+    }
+
+    /// Discard the recorded baseline so a new one may be captured.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl<const N: usize> Default for Calibrator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_baseline_computation() {
+        let mut calibrator: Calibrator<4> = Calibrator::new();
+        assert!(!calibrator.record(1.0));
+        assert!(!calibrator.record(2.0));
+        assert!(!calibrator.record(3.0));
+        // No offset is applied before the baseline is complete
+        assert_eq!(calibrator.offset(), 0.0);
+        assert_eq!(calibrator.apply(10.0), 10.0);
+
+        assert!(calibrator.record(6.0));
+        assert!(calibrator.is_calibrated());
+        assert_eq!(calibrator.offset(), 3.0);
+
+        // Additional samples do not change a completed baseline
+        assert!(calibrator.record(100.0));
+        assert_eq!(calibrator.offset(), 3.0);
+
+        calibrator.reset();
+        assert!(!calibrator.is_calibrated());
+        assert_eq!(calibrator.offset(), 0.0);
+    }
+
+    #[test]
+    fn test_offset_application() {
+        let mut calibrator: Calibrator<2> = Calibrator::new();
+        calibrator.record(4.0);
+        calibrator.record(6.0);
+
+        assert_eq!(calibrator.apply(41.0), 36.0);
+        assert_eq!(calibrator.apply(5.0), 0.0);
+        assert_eq!(calibrator.apply(0.0), 0.0);
+    }
+}
@@ -0,0 +1,128 @@
+//! Display module
+//!
+//! This module provides the choice between showing the instantaneous
+//! AQI of each reading or the AQI of the rolling average.
+
+use crate::{calculate_aqi, ReadingReport};
+use core::fmt;
+
+/// DisplayMode enum provides the AQI values which may drive the LEDs.
+/// The Display implementation produces a lowercase name for the mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DisplayMode {
+    /// The AQI of each reading as soon as it is taken, for
+    /// responsiveness to sudden changes
+    Instantaneous,
+    /// The AQI of the rolling average, for a steadier display which
+    /// only changes when the average changes significantly
+    #[default]
+    Averaged,
+}
+
+impl DisplayMode {
+    /// The other mode, for switching modes with a button press.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// display_mode = display_mode.toggle();
+    /// ```
+    pub const fn toggle(self) -> Self {
+        match self {
+            DisplayMode::Instantaneous => DisplayMode::Averaged,
+            DisplayMode::Averaged => DisplayMode::Instantaneous,
+        }
+    }
+
+    /// Number of times the LEDs blink to indicate the mode after it
+    /// is switched: once for Instantaneous, twice for Averaged.
+    pub const fn cue_blinks(self) -> u8 {
+        match self {
+            DisplayMode::Instantaneous => 1,
+            DisplayMode::Averaged => 2,
+        }
+    }
+
+    /// Decides which AQI, if any, the LEDs should show after a reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `pm25` - The PM 2.5 value of the reading just taken
+    /// * `report` - The reported reading, if the change in the rolling
+    ///   average was significant enough to report
+    ///
+    /// # Returns
+    ///
+    /// In Instantaneous mode, the AQI of the reading itself. In Averaged
+    /// mode, the AQI of the average if it was reported, or None if the
+    /// LEDs should be left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let report = monitor.record_significant(pm25_concentration, 5, &mut output);
+    /// if let Some(aqi) = display_mode.led_aqi(pm25_concentration, report.as_ref()) {
+    ///     led_controller.set_color(get_aqi_color(aqi));
+    /// }
+    /// ```
+    pub fn led_aqi(self, pm25: u16, report: Option<&ReadingReport>) -> Option<u16> {
+        match self {
+            DisplayMode::Instantaneous => Some(calculate_aqi(pm25 as f32)),
+            DisplayMode::Averaged => report.map(|report| report.aqi),
+        }
+    }
+}
+
+impl fmt::Display for DisplayMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DisplayMode::Instantaneous => "instantaneous",
+            DisplayMode::Averaged => "averaged",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_aqi_color;
+
+    fn report(pm25: u16, aqi: u16) -> ReadingReport {
+        ReadingReport {
+            sequence: 1,
+            pm25,
+            aqi,
+            delta: 0,
+            color: get_aqi_color(aqi),
+            secondary_aqi: None,
+        }
+    }
+
+    #[test]
+    fn test_toggle() {
+        assert_eq!(DisplayMode::default(), DisplayMode::Averaged);
+        assert_eq!(DisplayMode::Averaged.toggle(), DisplayMode::Instantaneous);
+        assert_eq!(DisplayMode::Instantaneous.toggle(), DisplayMode::Averaged);
+        assert_ne!(
+            DisplayMode::Averaged.cue_blinks(),
+            DisplayMode::Instantaneous.cue_blinks()
+        );
+    }
+
+    #[test]
+    fn test_led_aqi() {
+        // A spike to 41 µg/m³ after a low average of AQI 60
+        let reported = report(41, 60);
+
+        assert_eq!(DisplayMode::Averaged.led_aqi(41, Some(&reported)), Some(60));
+        assert_eq!(
+            DisplayMode::Instantaneous.led_aqi(41, Some(&reported)),
+            Some(115)
+        );
+
+        // When the average has not changed enough to report, only the
+        // instantaneous mode updates the LEDs
+        assert_eq!(DisplayMode::Averaged.led_aqi(41, None), None);
+        assert_eq!(DisplayMode::Instantaneous.led_aqi(41, None), Some(115));
+    }
+}
@@ -0,0 +1,111 @@
+//! Dwell time module
+//!
+//! This module provides tracking of the total time spent in each
+//! AQI category, for exposure summaries such as "2 hours in
+//! Unhealthy air today".
+
+use crate::{get_aqi_color, Color};
+
+/// The DwellTracker struct accumulates the time spent in each
+/// AQI color range. The time between two readings is attributed
+/// to the range of the earlier reading.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DwellTracker {
+    durations_ms: [u32; 6],
+    last: Option<(u32, Color)>,
+}
+
+impl DwellTracker {
+    /// Create a new DwellTracker with no time accumulated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut dwell_tracker = DwellTracker::new();
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            durations_ms: [0; 6],
+            last: None,
+        }
+    }
+
+    /// Record a timestamped AQI reading. Timestamps are expected
+    /// to come from a monotonic millisecond clock, and wraparound
+    /// of the clock is handled.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp_ms` - Time of the reading in milliseconds
+    /// * `aqi` - The calculated AQI
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// dwell_tracker.update(Instant::now().as_millis() as u32, aqi);
+    /// ```
+    pub fn update(&mut self, timestamp_ms: u32, aqi: u16) {
+        if let Some((last_timestamp_ms, last_color)) = self.last {
+            let elapsed_ms = timestamp_ms.wrapping_sub(last_timestamp_ms);
+            let duration_ms = &mut self.durations_ms[last_color as usize];
+            *duration_ms = duration_ms.saturating_add(elapsed_ms);
+        }
+        self.last = Some((timestamp_ms, get_aqi_color(aqi)));
+    }
+
+    /// Total time spent in the given AQI color range.
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The Color corresponding to an AQI range
+    ///
+    /// # Returns
+    ///
+    /// The accumulated duration in milliseconds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let unhealthy_ms = dwell_tracker.duration_in(Color::Red);
+    /// ```
+    pub fn duration_in(&self, color: Color) -> u32 {
+        self.durations_ms[color as usize]
+    }
+
+    /// Clear all accumulated time, e.g. at the start of a new day.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accumulation_across_transitions() {
+        let mut tracker = DwellTracker::new();
+        tracker.update(0, 25); // Green
+        tracker.update(1_000, 30); // Green
+        tracker.update(3_000, 75); // Yellow
+        tracker.update(6_000, 175); // Red
+        tracker.update(10_000, 40); // Green
+
+        assert_eq!(tracker.duration_in(Color::Green), 3_000);
+        assert_eq!(tracker.duration_in(Color::Yellow), 3_000);
+        assert_eq!(tracker.duration_in(Color::Red), 4_000);
+        assert_eq!(tracker.duration_in(Color::Orange), 0);
+
+        tracker.reset();
+        assert_eq!(tracker.duration_in(Color::Green), 0);
+    }
+
+    #[test]
+    fn test_clock_wraparound() {
+        let mut tracker = DwellTracker::new();
+        tracker.update(u32::MAX - 499, 125); // Orange
+        tracker.update(500, 125);
+
+        assert_eq!(tracker.duration_in(Color::Orange), 1_000);
+    }
+}
@@ -0,0 +1,192 @@
+//! Event module
+//!
+//! This module provides a fixed-capacity log of notable events, such
+//! as category changes and alarms, giving a human-readable history of
+//! what happened during a session.
+
+use crate::Category;
+use core::fmt;
+
+/// Event enum provides the notable events recorded by the firmware.
+/// The Display implementation produces a short description of the event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The AQI moved into a different category
+    CategoryChanged { from: Category, to: Category },
+    /// The alarm was raised by a reading at the given AQI
+    AlarmRaised { aqi: u16 },
+    /// The raised alarm was acknowledged
+    AlarmAcknowledged,
+    /// A reading could not be taken from the sensor, after the
+    /// previous reading succeeded
+    SensorFault,
+    /// A reading was taken from the sensor again after a fault
+    SensorRecovered,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::CategoryChanged { from, to } => {
+                write!(f, "Category changed from {} to {}", from, to)
+            }
+            Event::AlarmRaised { aqi } => write!(f, "Alarm raised at AQI {}", aqi),
+            Event::AlarmAcknowledged => f.write_str("Alarm acknowledged"),
+            Event::SensorFault => f.write_str("Sensor reading failed"),
+            Event::SensorRecovered => f.write_str("Sensor reading recovered"),
+        }
+    }
+}
+
+/// The EventLog struct stores up to `N` of the most recent timestamped
+/// events. Once full, each new event replaces the oldest one.
+/// Timestamps are expected to come from a monotonic millisecond clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventLog<const N: usize> {
+    events: [Option<(u32, Event)>; N],
+    next: usize,
+    len: usize,
+}
+
+impl<const N: usize> EventLog<N> {
+    /// Create a new, empty EventLog.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut events: EventLog<32> = EventLog::new();
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            events: [None; N],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    /// Add an event, replacing the oldest event if already full.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp_ms` - Time of the event in milliseconds
+    /// * `event` - The event to record
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// events.push(Instant::now().as_millis() as u32, Event::AlarmAcknowledged);
+    /// ```
+    pub fn push(&mut self, timestamp_ms: u32, event: Event) {
+        if N == 0 {
+            return;
+        }
+        self.events[self.next] = Some((timestamp_ms, event));
+        self.next = (self.next + 1) % N;
+        if self.len < N {
+            self.len += 1;
+        }
+    }
+
+    /// The number of events currently stored.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no events have been stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove all events.
+    pub fn clear(&mut self) {
+        self.next = 0;
+        self.len = 0;
+    }
+
+    /// Iterate over the stored events from oldest to newest,
+    /// as `(timestamp_ms, event)` pairs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// for (timestamp_ms, event) in events.iter() {
+    ///     hprintln!("{} ms: {}", timestamp_ms, event);
+    /// }
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (u32, Event)> + '_ {
+        let start = (self.next + N - self.len) % N.max(1);
+        (0..self.len).filter_map(move |i| self.events[(start + i) % N])
+    }
+}
+
+impl<const N: usize> Default for EventLog<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::ToString;
+
+    #[test]
+    fn test_event_log() {
+        let mut events: EventLog<3> = EventLog::new();
+        assert!(events.is_empty());
+        assert_eq!(events.iter().count(), 0);
+
+        events.push(1, Event::SensorFault);
+        events.push(2, Event::AlarmRaised { aqi: 205 });
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().eq([
+            (1, Event::SensorFault),
+            (2, Event::AlarmRaised { aqi: 205 })
+        ]));
+
+        events.clear();
+        assert!(events.is_empty());
+        assert_eq!(events.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_event_log_overflow() {
+        let mut events: EventLog<3> = EventLog::new();
+        for timestamp_ms in 1..=5 {
+            events.push(timestamp_ms, Event::SensorFault);
+        }
+        // Only the newest three events remain, oldest first
+        assert_eq!(events.len(), 3);
+        assert!(events
+            .iter()
+            .map(|(timestamp_ms, _)| timestamp_ms)
+            .eq(3..=5));
+
+        // A zero capacity log stores nothing
+        let mut empty: EventLog<0> = EventLog::new();
+        empty.push(1, Event::SensorFault);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_event_display() {
+        let changed = Event::CategoryChanged {
+            from: Category::Good,
+            to: Category::UnhealthyForSensitiveGroups,
+        };
+        assert_eq!(
+            changed.to_string(),
+            "Category changed from Good to Unhealthy for Sensitive Groups"
+        );
+        assert_eq!(
+            Event::AlarmRaised { aqi: 205 }.to_string(),
+            "Alarm raised at AQI 205"
+        );
+        assert_eq!(Event::AlarmAcknowledged.to_string(), "Alarm acknowledged");
+        assert_eq!(Event::SensorFault.to_string(), "Sensor reading failed");
+        assert_eq!(
+            Event::SensorRecovered.to_string(),
+            "Sensor reading recovered"
+        );
+    }
+}
@@ -0,0 +1,451 @@
+//! History module
+//!
+//! This module provides a fixed-capacity buffer of timestamped PM2.5
+//! readings, along with calculations over the stored readings.
+
+use crate::{calculate_aqi, get_aqi_category, Category};
+
+const HOUR_MS: u32 = 60 * 60 * 1000;
+const DAY_MS: u32 = 24 * HOUR_MS;
+
+/// Minimum number of hours in a 24-hour period which must have at least
+/// one reading for a daily average to be considered valid. The EPA
+/// requires 75% coverage, i.e. 18 of 24 hours.
+const MIN_DAILY_HOURS: u32 = 18;
+
+/// The History struct stores up to `N` of the most recent timestamped
+/// PM2.5 readings. Once full, each new reading replaces the oldest one.
+/// Timestamps are expected to come from a monotonic millisecond clock.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct History<const N: usize> {
+    readings: [(u32, f32); N],
+    next: usize,
+    len: usize,
+}
+
+impl<const N: usize> History<N> {
+    /// Create a new, empty History.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut history: History<288> = History::new();
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            readings: [(0, 0.0); N],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    /// Add a reading, replacing the oldest reading if already full.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp_ms` - Time of the reading in milliseconds
+    /// * `pm25` - The PM 2.5 value from the sensor
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// history.push(Instant::now().as_millis() as u32, data.pm2_5_env as f32);
+    /// ```
+    pub fn push(&mut self, timestamp_ms: u32, pm25: f32) {
+        if N == 0 {
+            return;
+        }
+        self.readings[self.next] = (timestamp_ms, pm25);
+        self.next = (self.next + 1) % N;
+        if self.len < N {
+            self.len += 1;
+        }
+    }
+
+    /// The number of readings currently stored.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no readings have been stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove all readings.
+    pub fn clear(&mut self) {
+        self.next = 0;
+        self.len = 0;
+    }
+
+    /// Iterate over the stored readings from oldest to newest,
+    /// as `(timestamp_ms, pm25)` pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, f32)> + '_ {
+        let start = (self.next + N - self.len) % N.max(1);
+        (0..self.len).map(move |i| self.readings[(start + i) % N])
+    }
+}
+
+impl<const N: usize> Default for History<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Calculates the daily AQI from the readings stored over the last
+/// 24 hours. Following the EPA approach, readings are first averaged
+/// within each hour, and the daily PM2.5 concentration is the average
+/// of those hourly values.
+///
+/// # Arguments
+///
+/// * `history` - The stored readings
+/// * `now_ms` - The current time in milliseconds, from the same clock
+///   used to timestamp the readings
+///
+/// # Returns
+///
+/// The AQI of the 24-hour average PM2.5 concentration, or None if
+/// fewer than 18 of the last 24 hours contain a reading.
+///
+/// # Examples
+///
+/// ```
+/// if let Some(aqi) = daily_aqi(&history, Instant::now().as_millis() as u32) {
+///     hprintln!("24-hour AQI: {}", aqi);
+/// }
+/// ```
+pub fn daily_aqi<const N: usize>(history: &History<N>, now_ms: u32) -> Option<u16> {
+    let mut hourly_sums = [0.0f32; 24];
+    let mut hourly_counts = [0u32; 24];
+
+    for (timestamp_ms, pm25) in history.iter() {
+        let age_ms = now_ms.wrapping_sub(timestamp_ms);
+        if age_ms < DAY_MS {
+            let hour = (age_ms / HOUR_MS) as usize;
+            hourly_sums[hour] += pm25;
+            hourly_counts[hour] += 1;
+        }
+    }
+
+    let mut covered_hours = 0;
+    let mut sum_of_averages = 0.0;
+    for (sum, count) in hourly_sums.iter().zip(hourly_counts.iter()) {
+        if *count > 0 {
+            covered_hours += 1;
+            sum_of_averages += sum / *count as f32;
+        }
+    }
+
+    if covered_hours < MIN_DAILY_HOURS {
+        return None;
+    }
+    Some(calculate_aqi(sum_of_averages / covered_hours as f32))
+}
+
+/// Calculates an approximate hourly AQI from samples taken every few
+/// minutes, using the time-weighted mean PM2.5 concentration over the
+/// hour up to the newest sample. Samples may be irregularly spaced, as
+/// each interval between consecutive samples is weighted by its length
+/// using the trapezoidal rule. When an older sample precedes the hour,
+/// the concentration at the start of the hour is interpolated from it,
+/// so the part of that interval within the hour is counted too.
+///
+/// # Arguments
+///
+/// * `samples` - `(timestamp_ms, pm25)` pairs ordered from oldest to
+///   newest, such as from `History::iter`. Timestamps are expected to
+///   come from a monotonic millisecond clock, and wraparound of the
+///   clock is handled.
+///
+/// # Returns
+///
+/// The AQI of the time-weighted mean, or None if no samples are given.
+/// If every sample within the hour shares a timestamp, their plain
+/// mean is used.
+///
+/// # Examples
+///
+/// ```
+/// // Samples 5 and then 10 minutes apart
+/// let samples = [(0, 40.0), (300_000, 42.0), (900_000, 41.0)];
+/// assert_eq!(hourly_average_aqi(&samples), Some(115));
+/// ```
+pub fn hourly_average_aqi(samples: &[(u32, f32)]) -> Option<u16> {
+    let &(now_ms, _) = samples.last()?;
+    let start = samples
+        .iter()
+        .position(|&(timestamp_ms, _)| now_ms.wrapping_sub(timestamp_ms) <= HOUR_MS)?;
+    let window = &samples[start..];
+
+    let mut weighted_sum = 0.0;
+    let mut span_ms = 0;
+    if start > 0 {
+        let (before_ms, before_pm25) = samples[start - 1];
+        let (after_ms, after_pm25) = samples[start];
+        let interval_ms = after_ms.wrapping_sub(before_ms);
+        let within_ms = after_ms.wrapping_sub(now_ms.wrapping_sub(HOUR_MS));
+        let hour_start_pm25 =
+            after_pm25 + (before_pm25 - after_pm25) * (within_ms as f32 / interval_ms as f32);
+        weighted_sum += (hour_start_pm25 + after_pm25) / 2.0 * within_ms as f32;
+        span_ms += within_ms;
+    }
+    for pair in window.windows(2) {
+        let ((start_ms, start_pm25), (end_ms, end_pm25)) = (pair[0], pair[1]);
+        let interval_ms = end_ms.wrapping_sub(start_ms);
+        weighted_sum += (start_pm25 + end_pm25) / 2.0 * interval_ms as f32;
+        span_ms += interval_ms;
+    }
+
+    let mean = if span_ms == 0 {
+        window.iter().map(|&(_, pm25)| pm25).sum::<f32>() / window.len() as f32
+    } else {
+        weighted_sum / span_ms as f32
+    };
+    Some(calculate_aqi(mean))
+}
+
+/// Finds the most severe AQI category among the readings within a
+/// recent window, for a conservative indicator which keeps a warning
+/// up briefly after a spike rather than dropping as soon as it passes.
+///
+/// # Arguments
+///
+/// * `history` - The stored readings
+/// * `window_ms` - How far back to look in milliseconds, inclusive
+/// * `now_ms` - The current time in milliseconds, from the same clock
+///   used to timestamp the readings
+///
+/// # Returns
+///
+/// The worst category within the window, or None if no reading falls
+/// within it.
+///
+/// # Examples
+///
+/// ```
+/// let now_ms = Instant::now().as_millis() as u32;
+/// if let Some(category) = worst_recent(&history, 10 * 60 * 1000, now_ms) {
+///     hprintln!("Worst in the last 10 minutes: {}", category);
+/// }
+/// ```
+pub fn worst_recent<const N: usize>(
+    history: &History<N>,
+    window_ms: u32,
+    now_ms: u32,
+) -> Option<Category> {
+    history
+        .iter()
+        .filter(|&(timestamp_ms, _)| now_ms.wrapping_sub(timestamp_ms) <= window_ms)
+        .map(|(_, pm25)| get_aqi_category(calculate_aqi(pm25)))
+        .max()
+}
+
+/// Calculates the fraction of stored readings with an AQI below the
+/// given AQI, for context such as "worse than 80% of recent readings".
+///
+/// # Arguments
+///
+/// * `history` - The stored readings
+/// * `aqi` - The AQI to rank
+///
+/// # Returns
+///
+/// The fraction of readings strictly below `aqi`, from 0.0 to 1.0, or
+/// 0.0 if there are no readings.
+///
+/// # Examples
+///
+/// ```
+/// let rank = percentile_rank(&history, reading.aqi);
+/// hprintln!("Worse than {:.0}% of recent readings", rank * 100.0);
+/// ```
+pub fn percentile_rank<const N: usize>(history: &History<N>, aqi: u16) -> f32 {
+    if history.is_empty() {
+        return 0.0;
+    }
+    let below = history
+        .iter()
+        .filter(|&(_, pm25)| calculate_aqi(pm25) < aqi)
+        .count();
+    below as f32 / history.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    const MINUTE_MS: u32 = 60 * 1000;
+
+    #[test]
+    fn test_history_buffer() {
+        let mut history: History<3> = History::new();
+        assert!(history.is_empty());
+        assert_eq!(history.iter().count(), 0);
+
+        history.push(1, 10.0);
+        history.push(2, 20.0);
+        assert_eq!(history.len(), 2);
+        assert!(history.iter().eq([(1, 10.0), (2, 20.0)]));
+
+        // Once full, the oldest reading is replaced
+        history.push(3, 30.0);
+        history.push(4, 40.0);
+        assert_eq!(history.len(), 3);
+        assert!(history.iter().eq([(2, 20.0), (3, 30.0), (4, 40.0)]));
+
+        history.clear();
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn test_daily_aqi() {
+        // A reading every 10 minutes over a full day
+        let mut history: History<160> = History::new();
+        let start_ms = 1_000;
+        for i in 0..144 {
+            // Alternate readings so each hour averages to 41 µg/m³
+            let pm25 = if i % 2 == 0 { 40.0 } else { 42.0 };
+            history.push(start_ms + i * 10 * MINUTE_MS, pm25);
+        }
+        let now_ms = start_ms + 144 * 10 * MINUTE_MS;
+        assert_eq!(daily_aqi(&history, now_ms), Some(115));
+
+        // Readings older than 24 hours are excluded
+        history.push(now_ms, 500.0);
+        assert_eq!(daily_aqi(&history, now_ms + DAY_MS), None);
+    }
+
+    #[test]
+    fn test_daily_aqi_insufficient_coverage() {
+        // Only 12 hours of readings
+        let mut history: History<160> = History::new();
+        for i in 0..72 {
+            history.push(i * 10 * MINUTE_MS, 41.0);
+        }
+        assert_eq!(daily_aqi(&history, 24 * HOUR_MS - 1), None);
+        assert_eq!(daily_aqi(&History::<4>::new(), 0), None);
+    }
+
+    #[test]
+    fn test_hourly_average_aqi_even() {
+        assert_eq!(hourly_average_aqi(&[]), None);
+        assert_eq!(hourly_average_aqi(&[(0, 41.0)]), Some(115));
+
+        // A sample every 5 minutes, rising steadily from 35 to 47 µg/m³,
+        // so the time-weighted mean is the midpoint of 41 µg/m³
+        let samples: Vec<(u32, f32)> = (0..13)
+            .map(|i| (i * 5 * MINUTE_MS, 35.0 + i as f32))
+            .collect();
+        assert_eq!(hourly_average_aqi(&samples), Some(115));
+    }
+
+    #[test]
+    fn test_hourly_average_aqi_uneven() {
+        // 10 µg/m³ for 40 minutes, then a sample after 20 more minutes
+        // at 40 µg/m³. The trapezoidal mean is (10 * 40 + 25 * 20) / 60
+        // = 15 µg/m³, below the plain mean of 20 µg/m³.
+        let samples = [(0, 10.0), (40 * MINUTE_MS, 10.0), (60 * MINUTE_MS, 40.0)];
+        assert_eq!(hourly_average_aqi(&samples), Some(calculate_aqi(15.0)));
+        assert_ne!(hourly_average_aqi(&samples), Some(calculate_aqi(20.0)));
+
+        // Samples more than an hour before the newest are excluded
+        let samples = [
+            (0, 500.0),
+            (MINUTE_MS, 10.0),
+            (31 * MINUTE_MS, 10.0),
+            (61 * MINUTE_MS, 10.0),
+        ];
+        assert_eq!(hourly_average_aqi(&samples), Some(calculate_aqi(10.0)));
+
+        // The interval crossing the start of the hour is interpolated.
+        // From 20 to 80 minutes the concentration falls from 70 to 10
+        // µg/m³, so it is 50 µg/m³ at the start of the hour at 40
+        // minutes, giving a mean of ((50 + 10) / 2 * 40 + 10 * 20) / 60
+        // = 23.33 µg/m³.
+        let samples = [
+            (20 * MINUTE_MS, 70.0),
+            (80 * MINUTE_MS, 10.0),
+            (100 * MINUTE_MS, 10.0),
+        ];
+        assert_eq!(
+            hourly_average_aqi(&samples),
+            Some(calculate_aqi(70.0 / 3.0))
+        );
+        assert_ne!(hourly_average_aqi(&samples), Some(calculate_aqi(10.0)));
+
+        // Samples sharing a timestamp fall back to a plain mean
+        assert_eq!(
+            hourly_average_aqi(&[(5, 40.0), (5, 42.0)]),
+            Some(calculate_aqi(41.0))
+        );
+
+        // Wraparound of the clock
+        let start_ms = u32::MAX - 20 * MINUTE_MS;
+        let samples = [
+            (start_ms, 10.0),
+            (start_ms.wrapping_add(40 * MINUTE_MS), 10.0),
+            (start_ms.wrapping_add(60 * MINUTE_MS), 40.0),
+        ];
+        assert_eq!(hourly_average_aqi(&samples), Some(calculate_aqi(15.0)));
+    }
+
+    #[test]
+    fn test_worst_recent() {
+        let mut history: History<16> = History::new();
+        assert_eq!(worst_recent(&history, 5 * MINUTE_MS, 0), None);
+
+        // A spike into Unhealthy between Good readings, one per minute
+        for i in 0..10 {
+            let pm25 = if i == 3 { 60.0 } else { 5.0 };
+            history.push(i * MINUTE_MS, pm25);
+        }
+        let now_ms = 9 * MINUTE_MS;
+
+        // Window reaching back to the spike keeps it up
+        assert_eq!(
+            worst_recent(&history, 6 * MINUTE_MS, now_ms),
+            Some(Category::Unhealthy)
+        );
+        // Window ending just after the spike drops it
+        assert_eq!(
+            worst_recent(&history, 6 * MINUTE_MS - 1, now_ms),
+            Some(Category::Good)
+        );
+        // No readings in the window at all
+        assert_eq!(
+            worst_recent(&history, MINUTE_MS, now_ms + 2 * MINUTE_MS),
+            None
+        );
+    }
+
+    #[test]
+    fn test_percentile_rank() {
+        assert_eq!(percentile_rank(&History::<4>::new(), 50), 0.0);
+
+        // Ten readings with AQI 0, 5, 10, ..., 45, 0.9 µg/m³ apart
+        // within the Good range, where AQI = 50 * pm25 / 9
+        let mut history: History<10> = History::new();
+        for i in 0..10 {
+            history.push(i * MINUTE_MS, i as f32 * 0.9);
+        }
+        assert_eq!(percentile_rank(&history, 0), 0.0);
+        assert_eq!(percentile_rank(&history, 1), 0.1);
+        // Readings equal to the AQI are not counted as below it
+        assert_eq!(percentile_rank(&history, 40), 0.8);
+        assert_eq!(percentile_rank(&history, 41), 0.9);
+        assert_eq!(percentile_rank(&history, 500), 1.0);
+    }
+
+    #[test]
+    fn test_daily_aqi_clock_wraparound() {
+        let mut history: History<32> = History::new();
+        let start_ms = u32::MAX - 12 * HOUR_MS;
+        for i in 0..24 {
+            history.push(start_ms.wrapping_add(i * HOUR_MS), 7.0);
+        }
+        let now_ms = start_ms.wrapping_add(24 * HOUR_MS);
+        assert_eq!(daily_aqi(&history, now_ms), Some(39));
+    }
+}
@@ -0,0 +1,207 @@
+//! Input module
+//!
+//! This module provides parsing of PM2.5 concentrations received as
+//! lines of text, such as over serial from a computer relaying a network
+//! API, so that the board can be used as a remote display without a
+//! sensor.
+
+use crate::AqiError;
+
+/// Parses a PM2.5 concentration given as a decimal string in µg/m³,
+/// rounded to the nearest whole µg/m³ to match sensor readings. Leading
+/// and trailing whitespace is ignored.
+///
+/// # Arguments
+///
+/// * `s` - The PM 2.5 concentration, e.g. "41" or "35.4"
+///
+/// # Returns
+///
+/// The concentration, saturating at `u16::MAX`, or an AqiError if the
+/// string is not a valid concentration.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(parse_concentration("35.4"), Ok(35));
+/// assert!(parse_concentration("-1").is_err());
+/// ```
+pub fn parse_concentration(s: &str) -> Result<u16, AqiError> {
+    let pm25: f32 = s.trim().parse().map_err(|_| AqiError::InvalidNumber)?;
+    if !pm25.is_finite() || pm25 < 0.0 {
+        return Err(AqiError::InvalidConcentration);
+    }
+    // Float to integer casts saturate, so very large values become u16::MAX
+    Ok(libm::roundf(pm25) as u16)
+}
+
+/// The LineBuffer struct collects bytes arriving in arbitrary chunks,
+/// such as serial packets, into lines of up to `N` bytes, parsing each
+/// complete line as a concentration. Lines end with LF, CR, or CRLF,
+/// and blank lines are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineBuffer<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    overflowed: bool,
+}
+
+impl<const N: usize> LineBuffer<N> {
+    /// Create a new, empty LineBuffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut line: LineBuffer<16> = LineBuffer::new();
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+            overflowed: false,
+        }
+    }
+
+    /// Add a received byte.
+    ///
+    /// # Arguments
+    ///
+    /// * `byte` - The byte received
+    ///
+    /// # Returns
+    ///
+    /// The parsed concentration if the byte completed a line, an
+    /// AqiError if the completed line was not a valid concentration or
+    /// was longer than `N` bytes, or None if no line was completed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// for &byte in &packet[..len] {
+    ///     match line.push(byte) {
+    ///         Some(Ok(pm25)) => hprintln!("Received {} µg/m³", pm25),
+    ///         Some(Err(e)) => hprintln!("Error parsing input: {}", e),
+    ///         None => {}
+    ///     }
+    /// }
+    /// ```
+    pub fn push(&mut self, byte: u8) -> Option<Result<u16, AqiError>> {
+        if byte != b'\n' && byte != b'\r' {
+            if self.len < N {
+                self.buf[self.len] = byte;
+                self.len += 1;
+            } else {
+                self.overflowed = true;
+            }
+            return None;
+        }
+
+        let line = &self.buf[..self.len];
+        let result = if self.overflowed {
+            Some(Err(AqiError::InvalidNumber))
+        } else if line.iter().all(u8::is_ascii_whitespace) {
+            None
+        } else {
+            Some(
+                core::str::from_utf8(line)
+                    .map_err(|_| AqiError::InvalidNumber)
+                    .and_then(parse_concentration),
+            )
+        };
+        self.len = 0;
+        self.overflowed = false;
+        result
+    }
+}
+
+impl<const N: usize> Default for LineBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color_to_led_roles, LedRole, Monitor, Output, Verbosity};
+    use core::fmt;
+    use std::vec::Vec;
+
+    /// An Output which discards everything reported to it.
+    struct NullOutput;
+
+    impl Output for NullOutput {
+        fn verbosity(&self) -> Verbosity {
+            Verbosity::Normal
+        }
+
+        fn write_line(&mut self, _args: fmt::Arguments) {}
+    }
+
+    fn push_all<const N: usize>(
+        line: &mut LineBuffer<N>,
+        bytes: &[u8],
+    ) -> Vec<Result<u16, AqiError>> {
+        bytes.iter().filter_map(|&byte| line.push(byte)).collect()
+    }
+
+    #[test]
+    fn test_parse_concentration() {
+        assert_eq!(parse_concentration("41"), Ok(41));
+        assert_eq!(parse_concentration(" 35.4\t"), Ok(35));
+        assert_eq!(parse_concentration("35.5"), Ok(36));
+        assert_eq!(parse_concentration("1e9"), Ok(u16::MAX));
+
+        assert_eq!(
+            parse_concentration("forty-one"),
+            Err(AqiError::InvalidNumber)
+        );
+        assert_eq!(
+            parse_concentration("-1"),
+            Err(AqiError::InvalidConcentration)
+        );
+        assert_eq!(
+            parse_concentration("NaN"),
+            Err(AqiError::InvalidConcentration)
+        );
+    }
+
+    #[test]
+    fn test_line_buffer() {
+        let mut line: LineBuffer<8> = LineBuffer::new();
+
+        // Lines may arrive split across packets, with any line ending
+        assert!(push_all(&mut line, b"4").is_empty());
+        assert_eq!(push_all(&mut line, b"1\r\n"), [Ok(41)]);
+        assert_eq!(push_all(&mut line, b"12\n35.4\r"), [Ok(12), Ok(35)]);
+
+        // Blank lines are ignored, but invalid lines are reported
+        assert!(push_all(&mut line, b"\n \r\n").is_empty());
+        assert_eq!(
+            push_all(&mut line, b"abc\n"),
+            [Err(AqiError::InvalidNumber)]
+        );
+
+        // Overlong lines are rejected whole, without affecting the next
+        assert_eq!(
+            push_all(&mut line, b"123456789\n7\n"),
+            [Err(AqiError::InvalidNumber), Ok(7)]
+        );
+    }
+
+    #[test]
+    fn test_display_path() {
+        // A synthetic concentration received as text drives the same
+        // report and LEDs as a sensor reading
+        let mut line: LineBuffer<16> = LineBuffer::new();
+        let mut monitor: Monitor<30> = Monitor::new();
+
+        let pm25 = push_all(&mut line, b"41\r\n")[0].unwrap();
+        let report = monitor.record(pm25, &mut NullOutput);
+        assert_eq!(report.aqi, 115);
+        assert_eq!(
+            color_to_led_roles(report.color),
+            [LedRole::Orange1, LedRole::Orange2]
+        );
+    }
+}
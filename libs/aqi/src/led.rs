@@ -0,0 +1,391 @@
+//! LED module
+//!
+//! This module provides the mapping from AQI colors and values to the
+//! LEDs lit on the STM32F303 Discovery board.
+
+use crate::Color;
+
+/// LedRole enum provides the logical LEDs on the STM32F303 Discovery
+/// board. The board has two LEDs of each color, numbered 1 and 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedRole {
+    Blue1,
+    Red1,
+    Orange1,
+    Green1,
+    Blue2,
+    Red2,
+    Orange2,
+    Green2,
+}
+
+impl LedRole {
+    /// Every LED, in order of its bit in a mask of lit LEDs.
+    pub const ALL: [LedRole; 8] = [
+        LedRole::Blue1,
+        LedRole::Red1,
+        LedRole::Orange1,
+        LedRole::Green1,
+        LedRole::Blue2,
+        LedRole::Red2,
+        LedRole::Orange2,
+        LedRole::Green2,
+    ];
+
+    /// Every LED in clockwise order around the compass on the board,
+    /// starting from the north LED, LD3.
+    pub const CLOCKWISE: [LedRole; 8] = [
+        LedRole::Red1,
+        LedRole::Orange1,
+        LedRole::Green1,
+        LedRole::Blue2,
+        LedRole::Red2,
+        LedRole::Orange2,
+        LedRole::Green2,
+        LedRole::Blue1,
+    ];
+
+    /// The bit representing this LED in a mask of lit LEDs, as
+    /// returned by `led_roles_mask`. Bits are assigned in the order
+    /// the variants are declared, from Blue1 at bit 0 to Green2 at
+    /// bit 7, matching the order of the pins PE8 through PE15.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(LedRole::Blue1.mask(), 0b0000_0001);
+    /// assert_eq!(LedRole::Green2.mask(), 0b1000_0000);
+    /// ```
+    pub const fn mask(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// Whether this LED is on in the given mask of lit LEDs.
+    ///
+    /// # Arguments
+    ///
+    /// * `mask` - A mask of lit LEDs, with bits as given by `mask`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert!(LedRole::Red2.is_lit(0b0010_0000));
+    /// assert!(!LedRole::Red1.is_lit(0b0010_0000));
+    /// ```
+    pub const fn is_lit(self, mask: u8) -> bool {
+        mask & self.mask() != 0
+    }
+}
+
+/// Provides the LEDs to light for a given Color.
+///
+/// The Discovery board does not have LEDs with colors directly
+/// matching the EPA AQI ranges, so some approximations are made.
+/// For example, to represent a dark purple color, both blue LEDs
+/// along with a red LED are lit.
+///
+/// # Arguments
+///
+/// * `color` - The Color corresponding to an AQI range
+///
+/// # Returns
+///
+/// The LEDs which should be on, with all others off.
+///
+/// # Examples
+///
+/// ```
+/// for &role in color_to_led_roles(Color::Yellow) {
+///     led_controller.led(role).set_high();
+/// }
+/// ```
+pub const fn color_to_led_roles(color: Color) -> &'static [LedRole] {
+    match color {
+        Color::Green => &[LedRole::Green1, LedRole::Green2],
+        Color::Yellow => &[LedRole::Green1, LedRole::Orange1],
+        Color::Orange => &[LedRole::Orange1, LedRole::Orange2],
+        Color::Red => &[LedRole::Red1, LedRole::Red2],
+        Color::Purple => &[LedRole::Red1, LedRole::Blue1],
+        Color::DarkPurple => &[LedRole::Red2, LedRole::Blue1, LedRole::Blue2],
+    }
+}
+
+/// Provides the mask of lit LEDs for a set of LEDs, with one bit
+/// per LED as given by `LedRole::mask`.
+///
+/// # Arguments
+///
+/// * `roles` - The LEDs which are on
+///
+/// # Returns
+///
+/// The mask with the bit set for each LED which is on.
+///
+/// # Examples
+///
+/// ```
+/// let lit = led_roles_mask(color_to_led_roles(Color::Green));
+/// assert_eq!(lit, LedRole::Green1.mask() | LedRole::Green2.mask());
+/// ```
+pub const fn led_roles_mask(roles: &[LedRole]) -> u8 {
+    let mut mask = 0;
+    let mut i = 0;
+    while i < roles.len() {
+        mask |= roles[i].mask();
+        i += 1;
+    }
+    mask
+}
+
+/// Provides the mask of lit LEDs showing a value in binary, such as a
+/// PM2.5 concentration, for users who want the raw number rather than
+/// its category. The value is read clockwise from the north LED, most
+/// significant bit first:
+///
+/// | Bit | Weight | LED           |
+/// |-----|--------|---------------|
+/// | 7   | 128    | Red1 (N)      |
+/// | 6   | 64     | Orange1 (NE)  |
+/// | 5   | 32     | Green1 (E)    |
+/// | 4   | 16     | Blue2 (SE)    |
+/// | 3   | 8      | Red2 (S)      |
+/// | 2   | 4      | Orange2 (SW)  |
+/// | 1   | 2      | Green2 (W)    |
+/// | 0   | 1      | Blue1 (NW)    |
+///
+/// # Arguments
+///
+/// * `value` - The value to show
+///
+/// # Returns
+///
+/// The mask with the bit set for each LED which is on, or None if the
+/// value is above 255. Every mask is used by some value, so a larger
+/// value needs a signal other than a fixed set of lit LEDs.
+///
+/// # Examples
+///
+/// ```
+/// if let Some(mask) = binary_led_mask(pm25_concentration) {
+///     led_controller.set_mask(mask);
+/// }
+/// ```
+pub const fn binary_led_mask(value: u16) -> Option<u8> {
+    if value > u8::MAX as u16 {
+        return None;
+    }
+    let value = value as u8;
+    let mut mask = 0;
+    let mut i = 0;
+    while i < LedRole::CLOCKWISE.len() {
+        if value & (0x80 >> i) != 0 {
+            mask |= LedRole::CLOCKWISE[i].mask();
+        }
+        i += 1;
+    }
+    Some(mask)
+}
+
+/// The LedState struct tracks which LEDs are lit, as a mask with one
+/// bit per LED as given by `LedRole::mask`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LedState {
+    lit: u8,
+}
+
+impl LedState {
+    /// Create a new LedState with every LED off.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut state = LedState::new();
+    /// ```
+    pub const fn new() -> Self {
+        Self { lit: 0 }
+    }
+
+    /// Record that exactly the LEDs whose bits are set in the mask are
+    /// lit, with bits as given by `LedRole::mask`.
+    ///
+    /// # Arguments
+    ///
+    /// * `mask` - A mask of lit LEDs
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// state.set_mask(0b0011_0011);
+    /// ```
+    pub fn set_mask(&mut self, mask: u8) {
+        self.lit = mask;
+    }
+
+    /// Record that exactly the given LEDs are lit.
+    ///
+    /// # Arguments
+    ///
+    /// * `roles` - The LEDs which are on
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// state.set_roles(&[LedRole::Blue1, LedRole::Blue2]);
+    /// ```
+    pub fn set_roles(&mut self, roles: &[LedRole]) {
+        self.set_mask(led_roles_mask(roles));
+    }
+
+    /// Record that every LED is off.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// state.off();
+    /// assert!(state.is_off());
+    /// ```
+    pub fn off(&mut self) {
+        self.set_mask(0);
+    }
+
+    /// Get the LEDs which are lit.
+    ///
+    /// # Returns
+    ///
+    /// A mask with one bit set for each LED which is on, as given by
+    /// `LedRole::mask`.
+    pub const fn active_leds(&self) -> u8 {
+        self.lit
+    }
+
+    /// Whether every LED is off.
+    pub const fn is_off(&self) -> bool {
+        self.lit == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_color_to_led_roles() {
+        assert_eq!(
+            color_to_led_roles(Color::Green),
+            [LedRole::Green1, LedRole::Green2]
+        );
+        assert_eq!(
+            color_to_led_roles(Color::Yellow),
+            [LedRole::Green1, LedRole::Orange1]
+        );
+        assert_eq!(
+            color_to_led_roles(Color::Orange),
+            [LedRole::Orange1, LedRole::Orange2]
+        );
+        assert_eq!(
+            color_to_led_roles(Color::Red),
+            [LedRole::Red1, LedRole::Red2]
+        );
+        assert_eq!(
+            color_to_led_roles(Color::Purple),
+            [LedRole::Red1, LedRole::Blue1]
+        );
+        assert_eq!(
+            color_to_led_roles(Color::DarkPurple),
+            [LedRole::Red2, LedRole::Blue1, LedRole::Blue2]
+        );
+    }
+
+    #[test]
+    fn test_led_roles_mask() {
+        assert_eq!(led_roles_mask(&[]), 0);
+        assert_eq!(
+            led_roles_mask(color_to_led_roles(Color::DarkPurple)),
+            0b0011_0001
+        );
+        assert_eq!(
+            led_roles_mask(color_to_led_roles(Color::Yellow)),
+            0b0000_1100
+        );
+        // Listing an LED twice does not change the mask
+        assert_eq!(led_roles_mask(&[LedRole::Red1, LedRole::Red1]), 0b0000_0010);
+    }
+
+    #[test]
+    fn test_binary_led_mask() {
+        assert_eq!(binary_led_mask(0), Some(0));
+        assert_eq!(binary_led_mask(128), Some(LedRole::Red1.mask()));
+        assert_eq!(binary_led_mask(1), Some(LedRole::Blue1.mask()));
+        // 41 is 0b0010_1001
+        assert_eq!(
+            binary_led_mask(41),
+            Some(led_roles_mask(&[
+                LedRole::Green1,
+                LedRole::Red2,
+                LedRole::Blue1
+            ]))
+        );
+        assert_eq!(binary_led_mask(255), Some(u8::MAX));
+
+        // Values beyond 8 bits have no mask, rather than showing as 255
+        assert_eq!(binary_led_mask(256), None);
+        assert_eq!(binary_led_mask(u16::MAX), None);
+
+        // Every value lights a distinct set of LEDs
+        let mut seen = [false; 256];
+        for value in 0..=255 {
+            let mask = binary_led_mask(value).unwrap() as usize;
+            assert!(!seen[mask], "value {}", value);
+            seen[mask] = true;
+        }
+    }
+
+    #[test]
+    fn test_mask_bits() {
+        for (bit, role) in LedRole::ALL.into_iter().enumerate() {
+            assert_eq!(role.mask(), 1 << bit);
+        }
+
+        // A mask lights exactly the LEDs whose bits are set
+        let lit: Vec<LedRole> = LedRole::ALL
+            .into_iter()
+            .filter(|role| role.is_lit(0b1010_0101))
+            .collect();
+        assert_eq!(
+            lit,
+            [
+                LedRole::Blue1,
+                LedRole::Orange1,
+                LedRole::Red2,
+                LedRole::Green2
+            ]
+        );
+        assert!(LedRole::ALL.into_iter().all(|role| !role.is_lit(0)));
+        assert!(LedRole::ALL.into_iter().all(|role| role.is_lit(u8::MAX)));
+
+        // A mask built from a set of LEDs lights exactly those LEDs
+        let roles = color_to_led_roles(Color::Purple);
+        let mask = led_roles_mask(roles);
+        for role in LedRole::ALL {
+            assert_eq!(role.is_lit(mask), roles.contains(&role));
+        }
+    }
+
+    #[test]
+    fn test_led_state() {
+        let mut state = LedState::new();
+        assert!(state.is_off());
+        assert_eq!(state.active_leds(), 0);
+
+        state.set_roles(color_to_led_roles(Color::DarkPurple));
+        assert_eq!(state.active_leds(), 0b0011_0001);
+        assert!(!state.is_off());
+
+        state.set_mask(u8::MAX);
+        state.off();
+        assert!(state.is_off());
+        assert_eq!(state.active_leds(), 0);
+        assert_eq!(state, LedState::default());
+    }
+}
@@ -10,8 +10,68 @@
 #[cfg(feature = "std")]
 extern crate std;
 
-/// Color enum provides colors corresponding to EPA AQI levels
-#[derive(Debug, Clone, Copy, PartialEq)]
+use core::fmt::{self, Write};
+use text::TruncatingWriter;
+
+#[cfg(feature = "std")]
+use std::{format, string::String};
+
+mod alarm;
+mod average;
+mod calibration;
+mod display;
+mod dwell;
+mod event;
+mod history;
+mod input;
+mod led;
+mod monitor;
+mod output;
+mod pattern;
+mod press;
+mod reading;
+mod record;
+#[cfg(feature = "std")]
+mod report;
+mod sequence;
+mod stats;
+mod stuck;
+mod text;
+mod trend;
+mod warmup;
+
+pub use alarm::AlarmLatch;
+pub use average::{confidence, mean_variance, robust_average, weighted_average, RollingAverage};
+pub use calibration::Calibrator;
+pub use display::DisplayMode;
+pub use dwell::DwellTracker;
+pub use event::{Event, EventLog};
+pub use history::{daily_aqi, hourly_average_aqi, percentile_rank, worst_recent, History};
+pub use input::{parse_concentration, LineBuffer};
+pub use led::{binary_led_mask, color_to_led_roles, led_roles_mask, LedRole, LedState};
+pub use monitor::Monitor;
+pub use output::{Output, ReadingReport, Verbosity};
+pub use pattern::BlinkPattern;
+pub use press::{hold_action, DoublePress, HoldAction, LongPress};
+pub use reading::{format_age, is_fresh, Reading};
+pub use record::{LogRecord, RECORD_MARKER, RECORD_SIZE};
+#[cfg(feature = "std")]
+pub use report::{LocationReport, Pollutant, SubIndex};
+pub use sequence::SequenceCounter;
+pub use stats::AqiStats;
+pub use stuck::StuckDetector;
+pub use trend::{
+    change_rate, status_sentence, ChangeRate, RateWindow, Trend, GRADUAL_AQI_PER_MIN,
+    RAPID_AQI_PER_MIN,
+};
+pub use warmup::{measuring_led, MEASURING_SPINNER};
+
+/// Color enum provides colors corresponding to EPA AQI levels.
+///
+/// Variants are ordered by severity, so comparisons like
+/// `Color::DarkPurple > Color::Green` hold and `max()` over
+/// a collection of colors yields the worst reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Color {
     Green,
     Yellow,
@@ -21,6 +81,325 @@ pub enum Color {
     DarkPurple,
 }
 
+impl Color {
+    /// Whether another Color is a neighboring severity level, e.g. to
+    /// decide whether to animate a transition between the two or jump.
+    /// A Color is not adjacent to itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The Color to compare against
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert!(Color::Yellow.is_adjacent(&Color::Orange));
+    /// assert!(!Color::Green.is_adjacent(&Color::Red));
+    /// ```
+    pub fn is_adjacent(&self, other: &Color) -> bool {
+        (*self as u8).abs_diff(*other as u8) == 1
+    }
+
+    /// A 3-bit code for the Color, from 0 for Green to 5 for Dark
+    /// Purple, for compact logs. Codes follow the order of severity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(Color::Orange.to_code(), 2);
+    /// ```
+    pub const fn to_code(&self) -> u8 {
+        *self as u8
+    }
+
+    /// The Color for a code from `to_code`.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - A 3-bit Color code
+    ///
+    /// # Returns
+    ///
+    /// The Color, or None if the code is not in the range 0 to 5.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(Color::from_code(2), Some(Color::Orange));
+    /// assert_eq!(Color::from_code(7), None);
+    /// ```
+    pub const fn from_code(code: u8) -> Option<Color> {
+        match code {
+            0 => Some(Color::Green),
+            1 => Some(Color::Yellow),
+            2 => Some(Color::Orange),
+            3 => Some(Color::Red),
+            4 => Some(Color::Purple),
+            5 => Some(Color::DarkPurple),
+            _ => None,
+        }
+    }
+
+    /// The RGB value for the Color, for driving an RGB LED or strip
+    /// rather than the fixed-color Discovery LEDs. By default, this is
+    /// the EPA's reference value, from `EPA_PALETTE`. With the
+    /// `colorblind` feature, it is instead from `COLORBLIND_PALETTE`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let (red, green, blue) = Color::Orange.to_rgb();
+    /// ```
+    pub const fn to_rgb(&self) -> (u8, u8, u8) {
+        #[cfg(not(feature = "colorblind"))]
+        let palette = EPA_PALETTE;
+        #[cfg(feature = "colorblind")]
+        let palette = COLORBLIND_PALETTE;
+        palette[*self as usize]
+    }
+}
+
+/// The EPA's reference RGB values for each Color, indexed by Color:
+///
+/// | Color       | RGB             |
+/// |-------------|-----------------|
+/// | Green       | (0, 228, 0)     |
+/// | Yellow      | (255, 255, 0)   |
+/// | Orange      | (255, 126, 0)   |
+/// | Red         | (255, 0, 0)     |
+/// | Purple      | (143, 63, 151)  |
+/// | Dark Purple | (126, 0, 35)    |
+pub const EPA_PALETTE: [(u8, u8, u8); 6] = [
+    (0, 228, 0),
+    (255, 255, 0),
+    (255, 126, 0),
+    (255, 0, 0),
+    (143, 63, 151),
+    (126, 0, 35),
+];
+
+/// Colorblind-safe RGB values for each Color, indexed by Color. These
+/// are six evenly spaced steps of the viridis palette, from light to
+/// dark, which stay distinguishable with the common forms of color
+/// blindness and increase in darkness with severity:
+///
+/// | Color       | RGB             |
+/// |-------------|-----------------|
+/// | Green       | (253, 231, 37)  |
+/// | Yellow      | (122, 209, 81)  |
+/// | Orange      | (34, 168, 132)  |
+/// | Red         | (42, 120, 142)  |
+/// | Purple      | (65, 68, 135)   |
+/// | Dark Purple | (68, 1, 84)     |
+pub const COLORBLIND_PALETTE: [(u8, u8, u8); 6] = [
+    (253, 231, 37),
+    (122, 209, 81),
+    (34, 168, 132),
+    (42, 120, 142),
+    (65, 68, 135),
+    (68, 1, 84),
+];
+
+/// Category enum provides the EPA AQI categories. Like Color,
+/// variants are ordered by severity. The Display implementation
+/// produces the official EPA label for each category.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Category {
+    Good,
+    Moderate,
+    UnhealthyForSensitiveGroups,
+    Unhealthy,
+    VeryUnhealthy,
+    Hazardous,
+}
+
+impl Category {
+    /// The RGB value representing the Category, for drawing a swatch
+    /// per category in a legend without going through an AQI. This is
+    /// the `Color::to_rgb` value of the Category's EPA color, so with
+    /// the default palette:
+    ///
+    /// | Category                       | RGB             |
+    /// |--------------------------------|-----------------|
+    /// | Good                           | (0, 228, 0)     |
+    /// | Moderate                       | (255, 255, 0)   |
+    /// | Unhealthy for Sensitive Groups | (255, 126, 0)   |
+    /// | Unhealthy                      | (255, 0, 0)     |
+    /// | Very Unhealthy                 | (143, 63, 151)  |
+    /// | Hazardous                      | (126, 0, 35)    |
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let (red, green, blue) = Category::Moderate.representative_rgb();
+    /// ```
+    pub const fn representative_rgb(&self) -> (u8, u8, u8) {
+        let color = match self {
+            Category::Good => Color::Green,
+            Category::Moderate => Color::Yellow,
+            Category::UnhealthyForSensitiveGroups => Color::Orange,
+            Category::Unhealthy => Color::Red,
+            Category::VeryUnhealthy => Color::Purple,
+            Category::Hazardous => Color::DarkPurple,
+        };
+        color.to_rgb()
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Category::Good => "Good",
+            Category::Moderate => "Moderate",
+            Category::UnhealthyForSensitiveGroups => "Unhealthy for Sensitive Groups",
+            Category::Unhealthy => "Unhealthy",
+            Category::VeryUnhealthy => "Very Unhealthy",
+            Category::Hazardous => "Hazardous",
+        };
+        f.write_str(label)
+    }
+}
+
+/// SimpleLevel enum provides a simplified three level scheme,
+/// collapsing the six EPA categories for indicators with only
+/// green, yellow, and red LEDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SimpleLevel {
+    /// Good or Moderate, AQI up to 100
+    Ok,
+    /// Unhealthy for Sensitive Groups, AQI 101 to 150
+    Caution,
+    /// Unhealthy or worse, AQI above 150
+    Danger,
+}
+
+/// ActivityLevel enum provides outdoor activity guidance for the
+/// general public, following the EPA's activity guidance for particle
+/// pollution. Variants are ordered from least to most restrictive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ActivityLevel {
+    /// Good, Moderate, or Unhealthy for Sensitive Groups, AQI up to
+    /// 150: normal outdoor activity
+    Normal,
+    /// Unhealthy, AQI 151 to 200: reduce prolonged or heavy exertion
+    /// outdoors
+    ReduceProlonged,
+    /// Very Unhealthy, AQI 201 to 300: avoid prolonged or heavy
+    /// exertion outdoors
+    AvoidProlonged,
+    /// Hazardous, AQI above 300: avoid all physical activity outdoors
+    AvoidAll,
+}
+
+/// Recommendation enum provides a simple indoor/outdoor recommendation
+/// for consumer-facing displays, collapsing the EPA's activity guidance
+/// into three bands. Variants are ordered from least to most
+/// restrictive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Recommendation {
+    /// Normal outdoor activity is fine
+    StayOutdoorsOk,
+    /// Limit prolonged or heavy exertion outdoors
+    LimitOutdoor,
+    /// Avoid prolonged or heavy exertion outdoors by staying indoors
+    StayIndoors,
+}
+
+/// Sensitivity enum provides the user setting for whose guidance a
+/// Recommendation follows. The EPA's sensitive groups include people
+/// with heart or lung disease, older adults, children, and teenagers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sensitivity {
+    /// Guidance for the general public
+    #[default]
+    General,
+    /// Guidance for members of sensitive groups, which is one level
+    /// more restrictive than for the general public
+    Sensitive,
+}
+
+/// AqiStandard enum provides the national AQI standards which a
+/// PM2.5 concentration may be reported against. The Display
+/// implementation produces the name of the issuing country.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AqiStandard {
+    /// The US EPA AQI, used throughout this library by default
+    #[default]
+    Us,
+    /// India's National Air Quality Index (NAQI)
+    India,
+}
+
+impl fmt::Display for AqiStandard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AqiStandard::Us => "US",
+            AqiStandard::India => "India",
+        };
+        f.write_str(name)
+    }
+}
+
+/// ConcentrationUnit enum provides the units a PM2.5
+/// concentration may be reported in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ConcentrationUnit {
+    /// Micrograms per cubic meter, as reported by the PMSA003I
+    #[default]
+    MicrogramsPerM3,
+    /// Milligrams per cubic meter
+    MilligramsPerM3,
+}
+
+/// MicrogramsPerM3 struct holds a PM2.5 concentration in µg/m³, so
+/// that the expected unit is documented by the type at call sites
+/// rather than passed as a bare `f32`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub struct MicrogramsPerM3(pub f32);
+
+impl MicrogramsPerM3 {
+    /// Calculate the AQI for this concentration, as with the
+    /// `calculate_aqi` function.
+    ///
+    /// # Returns
+    ///
+    /// The calculated AQI value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pm25 = MicrogramsPerM3(data.pm2_5_env as f32);
+    /// let aqi = pm25.calculate_aqi();
+    /// ```
+    pub fn calculate_aqi(self) -> u16 {
+        calculate_aqi(self.0)
+    }
+}
+
+// AQI breakpoints for PM2.5
+// Updated in 2024, see the following from the EPA:
+// https://www.epa.gov/system/files/documents/2024-02/pm-naaqs-air-quality-index-fact-sheet.pdf
+// https://document.airnow.gov/technical-assistance-document-for-the-reporting-of-daily-air-quailty.pdf
+const PM25_BREAKPOINTS: [(f32, f32); 6] = [
+    (0.0, 9.0),     // Good
+    (9.1, 35.4),    // Moderate
+    (35.5, 55.4),   // Unhealthy for Sensitive Groups
+    (55.5, 125.4),  // Unhealthy
+    (125.5, 225.4), // Very Unhealthy
+    (225.5, 500.0), // Hazardous
+];
+
+/// AQI values corresponding to PM25_BREAKPOINTS
+const PM25_AQI_BREAKPOINTS: [(u16, u16); 6] = [
+    (0, 50),    // Good
+    (51, 100),  // Moderate
+    (101, 150), // Unhealthy for Sensitive Groups
+    (151, 200), // Unhealthy
+    (201, 300), // Very Unhealthy
+    (301, 500), // Hazardous
+];
+
 /// Calulate the AQI for the provided PM2.5 value.
 ///
 /// # Arguments
@@ -32,93 +411,1150 @@ pub enum Color {
 /// The calculated AQI value using breakpoints and a formula
 /// provided by the EPA. These values may be confirmed using
 /// the calculator at https://www.airnow.gov/aqi/aqi-calculator-concentration/
+/// The concentration is rounded to the nearest 0.1 µg/m³ first, so
+/// values between two ranges, such as 9.05, fall into one of them.
+///
+/// # Examples
+///
+/// ```
+/// let pm25_concentration = 41;
+/// let aqi = calculate_aqi(pm25_concentration as f32);
+/// assert_eq!(115, aqi);
+///
+/// let pm25_concentration = 7;
+/// let aqi = calculate_aqi(pm25_concentration as f32);
+/// assert_eq!(39, aqi);
+/// ```
+pub fn calculate_aqi(pm25: f32) -> u16 {
+    // The breakpoints are given to 0.1 µg/m³, leaving a gap between the
+    // top of one range and the bottom of the next, e.g. 9.0 and 9.1.
+    // Rounding to the same precision first keeps the ranges contiguous.
+    let pm25 = libm::roundf(pm25 * 10.0) / 10.0;
+    interpolate(pm25, &PM25_BREAKPOINTS, &PM25_AQI_BREAKPOINTS)
+}
+
+/// Calculate the AQI for the provided PM2.5 value using only integer
+/// arithmetic, for concentrations given in tenths of a µg/m³, which is
+/// the precision the EPA truncates PM2.5 to. Every breakpoint
+/// concentration maps to its documented AQI exactly, with no floating
+/// point rounding involved, and adjacent breakpoint ranges are
+/// contiguous, e.g. 90 (9.0 µg/m³) is AQI 50 and 91 (9.1 µg/m³) is AQI 51.
+///
+/// # Arguments
+///
+/// * `pm25_tenths` - The PM 2.5 value in tenths of a µg/m³
+///
+/// # Returns
+///
+/// The calculated AQI value, matching `calculate_aqi` for the same
+/// concentration, or 500 above the highest breakpoint.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(calculate_aqi_fixed(90), 50);
+/// assert_eq!(calculate_aqi_fixed(354), 100);
+/// assert_eq!(calculate_aqi_fixed(410), 115);
+/// ```
+pub const fn calculate_aqi_fixed(pm25_tenths: u16) -> u16 {
+    // PM25_BREAKPOINTS in tenths of a µg/m³
+    const PM25_TENTHS_BREAKPOINTS: [(u16, u16); 6] = [
+        (0, 90),      // Good
+        (91, 354),    // Moderate
+        (355, 554),   // Unhealthy for Sensitive Groups
+        (555, 1254),  // Unhealthy
+        (1255, 2254), // Very Unhealthy
+        (2255, 5000), // Hazardous
+    ];
+
+    let mut i = 0;
+    while i < PM25_TENTHS_BREAKPOINTS.len() {
+        let (pm_low, pm_high) = PM25_TENTHS_BREAKPOINTS[i];
+        let (aqi_low, aqi_high) = PM25_AQI_BREAKPOINTS[i];
+        if pm25_tenths <= pm_high {
+            // Same interpolation as `interpolate`, rounding half up
+            // using integer division
+            let numerator = (aqi_high - aqi_low) as u32 * (pm25_tenths - pm_low) as u32;
+            let denominator = (pm_high - pm_low) as u32;
+            let offset = (2 * numerator + denominator) / (2 * denominator);
+            return aqi_low + offset as u16;
+        }
+        i += 1;
+    }
+
+    // If the concentration is above the highest range, return the maximum AQI value
+    500
+}
+
+/// PM25_BREAKPOINTS in f64, for `calculate_aqi_f64`. Converting the f32
+/// breakpoints would carry over their representation error, e.g. 9.1
+/// would become 9.100000381.
+#[cfg(feature = "std")]
+const PM25_BREAKPOINTS_F64: [(f64, f64); 6] = [
+    (0.0, 9.0),     // Good
+    (9.1, 35.4),    // Moderate
+    (35.5, 55.4),   // Unhealthy for Sensitive Groups
+    (55.5, 125.4),  // Unhealthy
+    (125.5, 225.4), // Very Unhealthy
+    (225.5, 500.0), // Hazardous
+];
+
+/// Calculate the AQI for the provided PM2.5 value using f64 arithmetic,
+/// to match reference implementations such as the AirNow calculator
+/// exactly, e.g. for validation tooling on the host. Like AirNow, and
+/// unlike `calculate_aqi`, the concentration is truncated to 0.1 µg/m³
+/// rather than rounded, so 12.08 µg/m³ is treated as 12.0 µg/m³. Only
+/// available with the `std` feature; embedded code should use the f32
+/// `calculate_aqi`.
+///
+/// # Arguments
+///
+/// * `pm25` - The PM 2.5 concentration in µg/m³
+///
+/// # Returns
+///
+/// The calculated AQI value, or 500 if the concentration does not
+/// fall in any range.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(calculate_aqi_f64(12.08), 56);
+/// assert_eq!(calculate_aqi(12.08), 57);
+/// ```
+#[cfg(feature = "std")]
+pub fn calculate_aqi_f64(pm25: f64) -> u16 {
+    // Decimal values such as 2.3 are stored slightly low, so a small
+    // allowance keeps truncation from dropping them to the tenth below
+    let pm25 = (pm25 * 10.0 + 1e-9).floor() / 10.0;
+    for (&(pm_low, pm_high), &(aqi_low, aqi_high)) in
+        PM25_BREAKPOINTS_F64.iter().zip(PM25_AQI_BREAKPOINTS.iter())
+    {
+        if pm25 >= pm_low && pm25 <= pm_high {
+            let aqi = ((aqi_high - aqi_low) as f64 / (pm_high - pm_low)) * (pm25 - pm_low)
+                + aqi_low as f64;
+            return aqi.round() as u16;
+        }
+    }
+    500
+}
+
+/// Finds which of the six EPA PM2.5 breakpoint ranges a concentration
+/// falls in, from 0 for Good to 5 for Hazardous, e.g. for diagnostic
+/// tooling. As with `calculate_aqi`, the concentration is rounded to
+/// the nearest 0.1 µg/m³ first.
+///
+/// # Arguments
+///
+/// * `pm25` - The PM 2.5 value from the sensor
+///
+/// # Returns
+///
+/// The index of the breakpoint range, or None if the concentration is
+/// above the top of the Hazardous range or negative.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(breakpoint_index(41.0), Some(2));
+/// assert_eq!(breakpoint_index(600.0), None);
+/// ```
+pub fn breakpoint_index(pm25: f32) -> Option<usize> {
+    let pm25 = libm::roundf(pm25 * 10.0) / 10.0;
+    PM25_BREAKPOINTS
+        .iter()
+        .position(|&(pm_low, pm_high)| pm25 >= pm_low && pm25 <= pm_high)
+}
+
+/// Corrects a PM2.5 concentration from a low-cost laser sensor for
+/// relative humidity, using the US-wide correction the EPA developed
+/// for PurpleAir sensors. Humid air makes particles swell and read
+/// high, so the correction lowers the concentration as RH rises. The
+/// correction expects the CF=1 concentration, e.g. `pm2_5_standard()`
+/// from the PMSA003I.
+///
+/// # Arguments
+///
+/// * `pm25_cf1` - The PM 2.5 CF=1 value from the sensor
+/// * `rh` - Relative humidity in percent
+///
+/// # Returns
+///
+/// The corrected concentration in µg/m³, never below zero.
+///
+/// # Examples
+///
+/// ```
+/// let corrected = correct_pm25(data.pm2_5_standard() as f32, measurement.humidity);
+/// let aqi = calculate_aqi(corrected);
+/// ```
+pub fn correct_pm25(pm25_cf1: f32, rh: f32) -> f32 {
+    (0.524 * pm25_cf1 - 0.0862 * rh + 5.75).max(0.0)
+}
+
+/// Calculates how much the PM2.5 concentration would need to rise
+/// to cross into the next, worse AQI category, giving a sense of the
+/// margin before air quality is reported as worse.
+///
+/// # Arguments
+///
+/// * `pm25` - The PM 2.5 value from the sensor
+///
+/// # Returns
+///
+/// The additional concentration in µg/m³ needed to reach the next
+/// category, or None if already in the Hazardous category. Like
+/// `calculate_aqi`, the concentration is first rounded to 0.1 µg/m³,
+/// so the margin is in steps of 0.1 µg/m³.
+///
+/// # Examples
+///
+/// ```
+/// if let Some(margin) = concentration_to_next_category(41.0) {
+///     hprintln!("{} µg/m³ until the next category", margin);
+/// }
+/// ```
+pub fn concentration_to_next_category(pm25: f32) -> Option<f32> {
+    // Rounded as in calculate_aqi, so a value just below the bottom of
+    // a category, e.g. 9.05, is already counted as in that category
+    let pm25 = libm::roundf(pm25 * 10.0) / 10.0;
+    PM25_BREAKPOINTS
+        .iter()
+        .map(|&(pm_low, _)| pm_low)
+        .find(|&pm_low| pm_low > pm25)
+        .map(|pm_low| pm_low - pm25)
+}
+
+/// Provides the minimum PM2.5 concentration which first produces each
+/// Color, the lower edge of each AQI band, e.g. for calibration or a
+/// legend in a UI.
+///
+/// # Returns
+///
+/// Each Color paired with its lowest concentration in µg/m³, ordered
+/// from Green to Dark Purple.
+///
+/// # Examples
+///
+/// ```
+/// for (color, pm25) in color_thresholds() {
+///     hprintln!("{:?} from {} µg/m³", color, pm25);
+/// }
+/// ```
+pub const fn color_thresholds() -> [(Color, f32); 6] {
+    let mut thresholds = [(Color::Green, 0.0); 6];
+    let mut i = 0;
+    while i < thresholds.len() {
+        let (aqi_low, _) = PM25_AQI_BREAKPOINTS[i];
+        let (pm_low, _) = PM25_BREAKPOINTS[i];
+        thresholds[i] = (get_aqi_color(aqi_low), pm_low);
+        i += 1;
+    }
+    thresholds
+}
+
+/// Calculates an AQI value by finding the breakpoint range containing
+/// the concentration and linearly interpolating within it. Shared by
+/// the per-pollutant AQI calculations.
+///
+/// # Arguments
+///
+/// * `value` - The pollutant concentration
+/// * `pm_bp` - Concentration breakpoint ranges, in increasing order
+/// * `aqi_bp` - AQI ranges corresponding to each concentration range
+///
+/// # Returns
+///
+/// The calculated AQI value, rounded to the nearest integer, or
+/// 500 if the concentration does not fall in any range.
+fn interpolate(value: f32, pm_bp: &[(f32, f32)], aqi_bp: &[(u16, u16)]) -> u16 {
+    // Find the appropriate breakpoint range
+    for (&(pm_low, pm_high), &(aqi_low, aqi_high)) in pm_bp.iter().zip(aqi_bp.iter()) {
+        if value >= pm_low && value <= pm_high {
+            // Linear interpolation formula transcribed from EPA documentation
+            // AQI = ((AQIhigh - AQIlow) / (PMhigh - PMlow)) * (PMactual - PMlow) + AQIlow
+            let aqi = ((aqi_high - aqi_low) as f32 / (pm_high - pm_low)) * (value - pm_low)
+                + aqi_low as f32;
+            return libm::roundf(aqi) as u16;
+        }
+    }
+
+    // If the concentration is above the highest range, return the maximum AQI value
+    500
+}
+
+/// Calculate the AQI for the provided 8-hour average ozone value.
+///
+/// # Arguments
+///
+/// * `ppm` - The 8-hour average ozone concentration in parts per million
+///
+/// # Returns
+///
+/// The calculated AQI value using breakpoints and a formula
+/// provided by the EPA, or None if the concentration is above
+/// 0.200 ppm. The EPA does not define 8-hour ozone AQI values above
+/// 300; those are instead calculated from 1-hour ozone concentrations.
+/// These values may be confirmed using the calculator at
+/// https://www.airnow.gov/aqi/aqi-calculator-concentration/
+///
+/// # Examples
+///
+/// ```
+/// let aqi = calculate_aqi_o3_8hr(0.075);
+/// assert_eq!(Some(115), aqi);
+///
+/// let aqi = calculate_aqi_o3_8hr(0.250);
+/// assert_eq!(None, aqi);
+/// ```
+pub fn calculate_aqi_o3_8hr(ppm: f32) -> Option<u16> {
+    // AQI breakpoints for 8-hour ozone, in ppm
+    // See the EPA technical assistance document:
+    // https://document.airnow.gov/technical-assistance-document-for-the-reporting-of-daily-air-quailty.pdf
+    const O3_8HR_BREAKPOINTS: [(f32, f32); 5] = [
+        (0.000, 0.054), // Good
+        (0.055, 0.070), // Moderate
+        (0.071, 0.085), // Unhealthy for Sensitive Groups
+        (0.086, 0.105), // Unhealthy
+        (0.106, 0.200), // Very Unhealthy
+    ];
+
+    // AQI values corresponding to breakpoints
+    const AQI_BREAKPOINTS: [(u16, u16); 5] = [
+        (0, 50),    // Good
+        (51, 100),  // Moderate
+        (101, 150), // Unhealthy for Sensitive Groups
+        (151, 200), // Unhealthy
+        (201, 300), // Very Unhealthy
+    ];
+
+    // The EPA truncates ozone concentrations to 3 decimal places. The small
+    // offset keeps values such as 0.055, which are not exactly representable
+    // as an f32, from being truncated down to the previous thousandth.
+    let o3 = libm::floorf(ppm * 1000.0 + 0.001) / 1000.0;
+
+    // 8-hour ozone does not define AQI values above 300
+    let (_, o3_max) = O3_8HR_BREAKPOINTS[O3_8HR_BREAKPOINTS.len() - 1];
+    if o3 > o3_max {
+        return None;
+    }
+
+    Some(interpolate(o3, &O3_8HR_BREAKPOINTS, &AQI_BREAKPOINTS))
+}
+
+/// Calculate the AQI for the provided 8-hour average carbon monoxide value.
+///
+/// # Arguments
+///
+/// * `ppm` - The 8-hour average CO concentration in parts per million
+///
+/// # Returns
+///
+/// The calculated AQI value using breakpoints and a formula
+/// provided by the EPA. These values may be confirmed using
+/// the calculator at https://www.airnow.gov/aqi/aqi-calculator-concentration/
+///
+/// # Examples
+///
+/// ```
+/// let aqi = calculate_aqi_co(7.0);
+/// assert_eq!(76, aqi);
+/// ```
+pub fn calculate_aqi_co(ppm: f32) -> u16 {
+    // AQI breakpoints for 8-hour CO, in ppm
+    // See the EPA technical assistance document:
+    // https://document.airnow.gov/technical-assistance-document-for-the-reporting-of-daily-air-quailty.pdf
+    const CO_BREAKPOINTS: [(f32, f32); 6] = [
+        (0.0, 4.4),   // Good
+        (4.5, 9.4),   // Moderate
+        (9.5, 12.4),  // Unhealthy for Sensitive Groups
+        (12.5, 15.4), // Unhealthy
+        (15.5, 30.4), // Very Unhealthy
+        (30.5, 50.4), // Hazardous
+    ];
+
+    // AQI values corresponding to breakpoints
+    const AQI_BREAKPOINTS: [(u16, u16); 6] = [
+        (0, 50),    // Good
+        (51, 100),  // Moderate
+        (101, 150), // Unhealthy for Sensitive Groups
+        (151, 200), // Unhealthy
+        (201, 300), // Very Unhealthy
+        (301, 500), // Hazardous
+    ];
+
+    // The EPA truncates CO concentrations to 1 decimal place
+    let co = libm::floorf(ppm * 10.0 + 0.001) / 10.0;
+
+    interpolate(co, &CO_BREAKPOINTS, &AQI_BREAKPOINTS)
+}
+
+/// Calculate the AQI for the provided PM10 value.
+///
+/// # Arguments
+///
+/// * `pm10` - The PM 10 value from the sensor, in µg/m³
+///
+/// # Returns
+///
+/// The calculated AQI value using breakpoints and a formula
+/// provided by the EPA. These values may be confirmed using
+/// the calculator at https://www.airnow.gov/aqi/aqi-calculator-concentration/
+///
+/// # Examples
+///
+/// ```
+/// let aqi = calculate_aqi_pm10(100.0);
+/// assert_eq!(73, aqi);
+/// ```
+pub fn calculate_aqi_pm10(pm10: f32) -> u16 {
+    // AQI breakpoints for PM10, in µg/m³
+    // Updated in 2024, see the following from the EPA:
+    // https://www.epa.gov/system/files/documents/2024-02/pm-naaqs-air-quality-index-fact-sheet.pdf
+    // https://document.airnow.gov/technical-assistance-document-for-the-reporting-of-daily-air-quailty.pdf
+    const PM10_BREAKPOINTS: [(f32, f32); 6] = [
+        (0.0, 54.0),    // Good
+        (55.0, 154.0),  // Moderate
+        (155.0, 254.0), // Unhealthy for Sensitive Groups
+        (255.0, 354.0), // Unhealthy
+        (355.0, 424.0), // Very Unhealthy
+        (425.0, 604.0), // Hazardous
+    ];
+
+    // AQI values corresponding to breakpoints
+    const AQI_BREAKPOINTS: [(u16, u16); 6] = [
+        (0, 50),    // Good
+        (51, 100),  // Moderate
+        (101, 150), // Unhealthy for Sensitive Groups
+        (151, 200), // Unhealthy
+        (201, 300), // Very Unhealthy
+        (301, 500), // Hazardous
+    ];
+
+    // The EPA truncates PM10 concentrations to whole numbers
+    let pm10 = libm::floorf(pm10);
+
+    interpolate(pm10, &PM10_BREAKPOINTS, &AQI_BREAKPOINTS)
+}
+
+/// Calculates the overall AQI from the PM2.5 AQI and, if available,
+/// the PM10 AQI. As with official reporting, the overall AQI is that
+/// of the worse of the two pollutants.
+///
+/// # Arguments
+///
+/// * `pm25_aqi` - The PM2.5 AQI
+/// * `pm10_aqi` - The PM10 AQI, or None to use PM2.5 alone
+///
+/// # Returns
+///
+/// The overall AQI.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(overall_aqi(115, Some(73)), 115);
+/// assert_eq!(overall_aqi(53, Some(73)), 73);
+/// assert_eq!(overall_aqi(53, None), 53);
+/// ```
+pub fn overall_aqi(pm25_aqi: u16, pm10_aqi: Option<u16>) -> u16 {
+    match pm10_aqi {
+        Some(pm10_aqi) => pm25_aqi.max(pm10_aqi),
+        None => pm25_aqi,
+    }
+}
+
+/// Calculate the AQI for the provided PM2.5 value using the
+/// specified national standard.
+///
+/// # Arguments
+///
+/// * `pm25` - The PM 2.5 value from the sensor
+/// * `standard` - The AQI standard to calculate against
+///
+/// # Returns
+///
+/// The calculated AQI value. For `AqiStandard::Us`, this is the same
+/// as `calculate_aqi`. For `AqiStandard::India`, this uses the PM2.5
+/// sub-index breakpoints published by India's Central Pollution
+/// Control Board, with concentrations truncated to whole numbers.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(calculate_aqi_for(41.0, AqiStandard::Us), 115);
+/// assert_eq!(calculate_aqi_for(41.0, AqiStandard::India), 68);
+/// ```
+pub fn calculate_aqi_for(pm25: f32, standard: AqiStandard) -> u16 {
+    // AQI breakpoints for PM2.5 under India's NAQI, in µg/m³
+    // See the CPCB National Air Quality Index report:
+    // https://cpcb.nic.in/displaypdf.php?id=bmF0aW9uYWwtYWlyLXF1YWxpdHktaW5kZXgvQWJvdXRfQVFJLnBkZg==
+    const INDIA_PM25_BREAKPOINTS: [(f32, f32); 6] = [
+        (0.0, 30.0),    // Good
+        (31.0, 60.0),   // Satisfactory
+        (61.0, 90.0),   // Moderate
+        (91.0, 120.0),  // Poor
+        (121.0, 250.0), // Very Poor
+        (251.0, 500.0), // Severe
+    ];
+
+    // AQI values corresponding to breakpoints
+    const INDIA_AQI_BREAKPOINTS: [(u16, u16); 6] = [
+        (0, 50),    // Good
+        (51, 100),  // Satisfactory
+        (101, 200), // Moderate
+        (201, 300), // Poor
+        (301, 400), // Very Poor
+        (401, 500), // Severe
+    ];
+
+    match standard {
+        AqiStandard::Us => calculate_aqi(pm25),
+        AqiStandard::India => interpolate(
+            libm::floorf(pm25),
+            &INDIA_PM25_BREAKPOINTS,
+            &INDIA_AQI_BREAKPOINTS,
+        ),
+    }
+}
+
+/// Calculate the AQI for the provided PM2.5 value under two standards
+/// at once, e.g. for travelers comparing the local standard with the
+/// one they are used to.
+///
+/// # Arguments
+///
+/// * `pm25` - The PM 2.5 value from the sensor
+/// * `first` - The first AQI standard to calculate against
+/// * `second` - The second AQI standard to calculate against
+///
+/// # Returns
+///
+/// A tuple of the AQI under the first and second standards.
+///
+/// # Examples
+///
+/// ```
+/// let (us, india) = dual_aqi(41.0, AqiStandard::Us, AqiStandard::India);
+/// hprintln!("US AQI {} / India AQI {}", us, india);
+/// ```
+pub fn dual_aqi(pm25: f32, first: AqiStandard, second: AqiStandard) -> (u16, u16) {
+    (
+        calculate_aqi_for(pm25, first),
+        calculate_aqi_for(pm25, second),
+    )
+}
+
+/// Calculate the AQI for a PM2.5 value given in the specified unit.
+/// The value is converted to µg/m³ before calculating the AQI.
+///
+/// # Arguments
+///
+/// * `value` - The PM 2.5 concentration
+/// * `unit` - The unit the concentration is reported in
+///
+/// # Returns
+///
+/// The calculated AQI value, as with `calculate_aqi`.
+///
+/// # Examples
+///
+/// ```
+/// let aqi = calculate_aqi_from(0.041, ConcentrationUnit::MilligramsPerM3);
+/// assert_eq!(115, aqi);
+/// ```
+pub fn calculate_aqi_from(value: f32, unit: ConcentrationUnit) -> u16 {
+    let pm25 = match unit {
+        ConcentrationUnit::MicrogramsPerM3 => value,
+        ConcentrationUnit::MilligramsPerM3 => value * 1000.0,
+    };
+    calculate_aqi(pm25)
+}
+
+/// AqiError enum provides the reasons an AQI may not be
+/// calculated from the given input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AqiError {
+    /// The input could not be parsed as a decimal number
+    InvalidNumber,
+    /// The concentration was negative, infinite, or NaN
+    InvalidConcentration,
+}
+
+impl fmt::Display for AqiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            AqiError::InvalidNumber => "Could not parse concentration as a number",
+            AqiError::InvalidConcentration => "Concentration must be a non-negative finite number",
+        };
+        f.write_str(message)
+    }
+}
+
+/// Calculate the AQI for a PM2.5 value given as a decimal string in
+/// µg/m³, such as from a command line argument. Leading and trailing
+/// whitespace is ignored. Parsing uses `core`, so this is available
+/// without the `std` feature.
+///
+/// # Arguments
+///
+/// * `s` - The PM 2.5 concentration, e.g. "41" or "35.4"
+///
+/// # Returns
+///
+/// The calculated AQI value, as with `calculate_aqi`, or an AqiError
+/// if the string is not a valid concentration.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(calculate_aqi_from_str("41"), Ok(115));
+/// assert!(calculate_aqi_from_str("forty-one").is_err());
+/// ```
+pub fn calculate_aqi_from_str(s: &str) -> Result<u16, AqiError> {
+    let pm25: f32 = s.trim().parse().map_err(|_| AqiError::InvalidNumber)?;
+    if !pm25.is_finite() || pm25 < 0.0 {
+        return Err(AqiError::InvalidConcentration);
+    }
+    Ok(calculate_aqi(pm25))
+}
+
+/// Provides a Color enum variant value based on the
+/// specified AQI value. Uses the ranges provided by the
+/// EPA for mapping AQI to color.
+///
+/// # Arguments
+///
+/// * `aqi` - The calculated AQI
+///
+/// # Returns
+///
+/// A Color enum variant.
+///
+/// # Examples
+///
+/// ```
+/// let data = parse_data(&sensor_data).unwrap_or_else(|err| {
+///     hprintln!("Error parsing data: {}", err);
+///     Pmsa003iData::default()
+/// });
+///
+/// let pm25_concentration = data.pm2_5_env;
+/// let aqi = calculate_aqi(pm25_concentration as f32);
+/// let color = get_aqi_color(aqi);
+/// ```
+pub const fn get_aqi_color(aqi: u16) -> Color {
+    match aqi {
+        0..=50 => Color::Green,
+        51..=100 => Color::Yellow,
+        101..=150 => Color::Orange,
+        151..=200 => Color::Red,
+        201..=300 => Color::Purple,
+        _ => Color::DarkPurple,
+    }
+}
+
+/// Provides a Category enum variant value based on the
+/// specified AQI value, using the same EPA ranges as
+/// get_aqi_color.
+///
+/// # Arguments
+///
+/// * `aqi` - The calculated AQI
+///
+/// # Returns
+///
+/// A Category enum variant.
+///
+/// # Examples
+///
+/// ```
+/// let category = get_aqi_category(115);
+/// hprintln!("Air quality is {}", category);
+/// ```
+pub const fn get_aqi_category(aqi: u16) -> Category {
+    match aqi {
+        0..=50 => Category::Good,
+        51..=100 => Category::Moderate,
+        101..=150 => Category::UnhealthyForSensitiveGroups,
+        151..=200 => Category::Unhealthy,
+        201..=300 => Category::VeryUnhealthy,
+        _ => Category::Hazardous,
+    }
+}
+
+/// Get the simplified three level scheme value for a given AQI.
+///
+/// # Arguments
+///
+/// * `aqi` - The calculated AQI
+///
+/// # Returns
+///
+/// A SimpleLevel enum variant.
+///
+/// # Examples
+///
+/// ```
+/// if simple_level(aqi) == SimpleLevel::Danger {
+///     red_led.set_high();
+/// }
+/// ```
+pub const fn simple_level(aqi: u16) -> SimpleLevel {
+    match aqi {
+        0..=100 => SimpleLevel::Ok,
+        101..=150 => SimpleLevel::Caution,
+        _ => SimpleLevel::Danger,
+    }
+}
+
+/// Get the recommended outdoor activity level for the general public
+/// for a given AQI. Above an AQI of 100, people in sensitive groups
+/// should follow the guidance for the next more restrictive level.
+///
+/// # Arguments
+///
+/// * `aqi` - The calculated AQI
+///
+/// # Returns
+///
+/// An ActivityLevel enum variant.
+///
+/// # Examples
+///
+/// ```
+/// if activity_guidance(aqi) >= ActivityLevel::AvoidProlonged {
+///     hprintln!("Consider exercising indoors today");
+/// }
+/// ```
+pub const fn activity_guidance(aqi: u16) -> ActivityLevel {
+    match aqi {
+        0..=150 => ActivityLevel::Normal,
+        151..=200 => ActivityLevel::ReduceProlonged,
+        201..=300 => ActivityLevel::AvoidProlonged,
+        _ => ActivityLevel::AvoidAll,
+    }
+}
+
+/// Get the indoor/outdoor recommendation for a given AQI, following
+/// the same EPA bands as `activity_guidance`. For the general public,
+/// outdoor activity is fine up to 150, limited up to 200, and avoided
+/// above that. Sensitive groups are held to the next more restrictive
+/// level, so are limited from 101 and kept indoors from 151.
+///
+/// # Arguments
+///
+/// * `aqi` - The calculated AQI
+/// * `sensitivity` - Whether to follow the guidance for sensitive groups
+///
+/// # Returns
+///
+/// A Recommendation enum variant.
+///
+/// # Examples
+///
+/// ```
+/// if recommendation(aqi, Sensitivity::Sensitive) == Recommendation::StayIndoors {
+///     hprintln!("Stay indoors and keep windows closed");
+/// }
+/// ```
+pub const fn recommendation(aqi: u16, sensitivity: Sensitivity) -> Recommendation {
+    match (sensitivity, aqi) {
+        (Sensitivity::General, 0..=150) | (Sensitivity::Sensitive, 0..=100) => {
+            Recommendation::StayOutdoorsOk
+        }
+        (Sensitivity::General, 151..=200) | (Sensitivity::Sensitive, 101..=150) => {
+            Recommendation::LimitOutdoor
+        }
+        _ => Recommendation::StayIndoors,
+    }
+}
+
+/// Calculates the signed change in AQI from the previous reading,
+/// for display alongside a new reading (e.g. "+12 since last").
+///
+/// # Arguments
+///
+/// * `prev` - The previous AQI, or None if this is the first reading
+/// * `current` - The current AQI
+///
+/// # Returns
+///
+/// The difference between the current and previous AQI, or 0 if
+/// there is no previous reading.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(aqi_delta(None, 115), 0);
+/// assert_eq!(aqi_delta(Some(103), 115), 12);
+/// assert_eq!(aqi_delta(Some(115), 96), -19);
+/// ```
+pub fn aqi_delta(prev: Option<u16>, current: u16) -> i32 {
+    match prev {
+        Some(prev) => current as i32 - prev as i32,
+        None => 0,
+    }
+}
+
+/// Decides whether a new AQI is worth reporting, for continuous reading
+/// where reporting every reading would be too noisy. A reading is
+/// reported if nothing has been reported yet, if the AQI has moved by
+/// more than `min_delta` since the last reported value, or if it has
+/// crossed into a different category.
+///
+/// # Arguments
+///
+/// * `prev` - The last reported AQI, or None if nothing has been reported
+/// * `current` - The current AQI
+/// * `min_delta` - The change in AQI which must be exceeded to report
+///   a reading within the same category
+///
+/// # Returns
+///
+/// Whether the current AQI should be reported.
+///
+/// # Examples
+///
+/// ```
+/// assert!(should_report(None, 42, 5));
+/// assert!(!should_report(Some(40), 42, 5));
+/// assert!(should_report(Some(50), 51, 5));
+/// ```
+pub fn should_report(prev: Option<u16>, current: u16, min_delta: u16) -> bool {
+    match prev {
+        Some(prev) => {
+            prev.abs_diff(current) > min_delta
+                || get_aqi_category(prev) != get_aqi_category(current)
+        }
+        None => true,
+    }
+}
+
+/// Finds which milestone, if any, the AQI has just risen past, for
+/// one-time alerts such as "AQI crossed 100". A milestone is crossed
+/// when the previous AQI was below it and the current AQI is at or
+/// above it, so holding above a milestone, or falling back below it,
+/// does not alert again.
+///
+/// # Arguments
+///
+/// * `prev` - The previous AQI
+/// * `current` - The current AQI
+/// * `milestones` - The AQI values to alert on, in any order
+///
+/// # Returns
+///
+/// The highest milestone crossed upward, or None if none were crossed.
+///
+/// # Examples
+///
+/// ```
+/// if let Some(milestone) = crossed_milestone(prev_aqi, aqi, &[100, 150, 200]) {
+///     hprintln!("AQI crossed {}", milestone);
+/// }
+/// ```
+pub fn crossed_milestone(prev: u16, current: u16, milestones: &[u16]) -> Option<u16> {
+    milestones
+        .iter()
+        .copied()
+        .filter(|&milestone| prev < milestone && milestone <= current)
+        .max()
+}
+
+/// Finds the worst reading in a batch of PM2.5 concentrations, such
+/// as a logged session, for summarizing it. If several readings share
+/// the highest AQI, the earliest is returned.
+///
+/// # Arguments
+///
+/// * `concentrations` - PM2.5 concentrations in µg/m³
+///
+/// # Returns
+///
+/// The index and AQI of the reading with the highest AQI, or None if
+/// the batch is empty.
+///
+/// # Examples
+///
+/// ```
+/// if let Some((index, aqi)) = worst_in_batch(&session) {
+///     hprintln!("Worst reading was #{} at AQI {}", index, aqi);
+/// }
+/// ```
+pub fn worst_in_batch(concentrations: &[f32]) -> Option<(usize, u16)> {
+    let mut worst: Option<(usize, u16)> = None;
+    for (index, &pm25) in concentrations.iter().enumerate() {
+        let aqi = calculate_aqi(pm25);
+        if worst.is_none_or(|(_, worst_aqi)| aqi > worst_aqi) {
+            worst = Some((index, aqi));
+        }
+    }
+    worst
+}
+
+/// AQI at and above which the health score is 0.
+const HEALTH_SCORE_ZERO_AQI: u16 = 300;
+
+/// Maps an AQI to a 0–100 "health score", where higher is better,
+/// for consumer-friendly displays. The score falls linearly from 100
+/// at AQI 0 to 0 at AQI 300, rounding to the nearest whole point, so
+/// each 3 AQI points cost 1 point of score. AQI values of 300 and
+/// above, which fall in the Hazardous category, all score 0.
+///
+/// # Arguments
+///
+/// * `aqi` - The calculated AQI
+///
+/// # Returns
+///
+/// The health score from 0 to 100.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(health_score(0), 100);
+/// assert_eq!(health_score(150), 50);
+/// assert_eq!(health_score(350), 0);
+/// ```
+pub const fn health_score(aqi: u16) -> u8 {
+    if aqi >= HEALTH_SCORE_ZERO_AQI {
+        return 0;
+    }
+    let remaining = (HEALTH_SCORE_ZERO_AQI - aqi) as u32;
+    let half = HEALTH_SCORE_ZERO_AQI as u32 / 2;
+    ((remaining * 100 + half) / HEALTH_SCORE_ZERO_AQI as u32) as u8
+}
+
+/// Counts how many category levels above Good an AQI is, for a simple
+/// progress indicator toward clean air.
+///
+/// # Arguments
+///
+/// * `aqi` - The calculated AQI
+///
+/// # Returns
+///
+/// The number of categories between the AQI's category and Good, from
+/// 0 for Good to 5 for Hazardous.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(steps_to_good(42), 0);
+/// assert_eq!(steps_to_good(115), 2);
+/// ```
+pub const fn steps_to_good(aqi: u16) -> u8 {
+    get_aqi_category(aqi) as u8 - Category::Good as u8
+}
+
+/// Character used for the filled part of an AQI bar.
+const BAR_FILLED: char = '#';
+
+/// Character used for the empty part of an AQI bar.
+const BAR_EMPTY: char = '-';
+
+/// Renders a proportional bar representing an AQI from 0 to 500, for
+/// a quick visual on character displays. The bar is made of `#` for
+/// the filled part and `-` for the rest, so it is plain ASCII and
+/// displays on any character LCD. The filled length is rounded to the
+/// nearest character, and AQI values above 500 show a full bar.
+///
+/// # Arguments
+///
+/// * `aqi` - The calculated AQI
+/// * `width` - The width of the bar in characters
+/// * `buf` - Buffer to render the bar into. If shorter than `width`,
+///   the bar is shrunk to fit.
+///
+/// # Returns
+///
+/// The rendered bar, borrowed from `buf`.
+///
+/// # Examples
+///
+/// ```
+/// let mut buf = [0u8; 16];
+/// assert_eq!(aqi_bar(250, 10, &mut buf), "#####-----");
+/// ```
+pub fn aqi_bar(aqi: u16, width: usize, buf: &mut [u8]) -> &str {
+    let width = width.min(buf.len());
+    let filled = (aqi.min(500) as usize * width + 250) / 500;
+    let mut writer = TruncatingWriter::new(buf);
+    for i in 0..width {
+        // Writing never fails, as the text is truncated instead
+        let _ = writer.write_char(if i < filled { BAR_FILLED } else { BAR_EMPTY });
+    }
+    writer.into_str()
+}
+
+/// Lower AQI bound of each Color's band, in order of severity.
+const COLOR_BAND_STARTS: [(u16, Color); 6] = [
+    (0, Color::Green),
+    (51, Color::Yellow),
+    (101, Color::Orange),
+    (151, Color::Red),
+    (201, Color::Purple),
+    (301, Color::DarkPurple),
+];
+
+/// Maps an AQI to an RGB value which shifts gradually between
+/// categories rather than snapping, for a continuous gradient on
+/// an RGB strip. Within each band, the color is linearly blended
+/// from the band's own Color toward the next Color, based on the
+/// AQI's position within the band, so the end of one band meets the
+/// start of the next. The first AQI of each band matches
+/// `Color::to_rgb` exactly. The Dark Purple band has no next Color,
+/// so it is shown as solid Dark Purple.
+///
+/// # Arguments
+///
+/// * `aqi` - The calculated AQI
+///
+/// # Returns
+///
+/// The blended RGB value as a `(red, green, blue)` tuple.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(aqi_to_rgb_smooth(51), Color::Yellow.to_rgb());
+/// let (red, green, blue) = aqi_to_rgb_smooth(115);
+/// ```
+pub const fn aqi_to_rgb_smooth(aqi: u16) -> (u8, u8, u8) {
+    let mut i = 0;
+    while i + 1 < COLOR_BAND_STARTS.len() {
+        let (start, color) = COLOR_BAND_STARTS[i];
+        let (next_start, next_color) = COLOR_BAND_STARTS[i + 1];
+        if aqi < next_start {
+            let position = (aqi - start) as i32;
+            let width = (next_start - start) as i32;
+            let (r0, g0, b0) = color.to_rgb();
+            let (r1, g1, b1) = next_color.to_rgb();
+            return (
+                blend(r0, r1, position, width),
+                blend(g0, g1, position, width),
+                blend(b0, b1, position, width),
+            );
+        }
+        i += 1;
+    }
+    Color::DarkPurple.to_rgb()
+}
+
+/// Linearly blends one color component from `from` toward `to`,
+/// `position` steps out of `width`, rounding toward `from` so that
+/// the result never passes the exact blend.
+const fn blend(from: u8, to: u8, position: i32, width: i32) -> u8 {
+    let delta = (to as i32 - from as i32) * position / width;
+    (from as i32 + delta) as u8
+}
+
+/// Packs the RGB value of an AQI's Color into a single `0x00RRGGBB`
+/// value, for display APIs which take packed colors. The components
+/// are those of `Color::to_rgb`, so follow the `colorblind` feature.
+///
+/// # Arguments
+///
+/// * `aqi` - The calculated AQI
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(aqi_to_packed_rgb(160), 0x00FF0000);
+/// ```
+pub const fn aqi_to_packed_rgb(aqi: u16) -> u32 {
+    let (red, green, blue) = get_aqi_color(aqi).to_rgb();
+    (red as u32) << 16 | (green as u32) << 8 | blue as u32
+}
+
+/// Lowest tone frequency, for an AQI of 0.
+const TONE_MIN_HZ: u16 = 200;
+
+/// Highest tone frequency, for an AQI of 500 and above.
+const TONE_MAX_HZ: u16 = 2000;
+
+/// Maps an AQI to a tone frequency for a piezo buzzer driven by PWM,
+/// as non-visual feedback. The frequency rises linearly from 200 Hz at
+/// an AQI of 0 to 2000 Hz at 500, so higher AQI gives a higher pitch.
+/// AQI values above 500 give the top frequency.
+///
+/// # Arguments
+///
+/// * `aqi` - The calculated AQI
+///
+/// # Returns
+///
+/// The tone frequency in Hz, rounded to the nearest Hz.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(aqi_to_frequency(250), 1100);
+/// pwm.set_frequency(Hertz(aqi_to_frequency(aqi) as u32));
+/// ```
+pub const fn aqi_to_frequency(aqi: u16) -> u16 {
+    let aqi = if aqi > 500 { 500 } else { aqi } as u32;
+    let span = (TONE_MAX_HZ - TONE_MIN_HZ) as u32;
+    TONE_MIN_HZ + ((aqi * span + 250) / 500) as u16
+}
+
+/// Lookup table mapping every AQI value from 0 to 500 to its Color,
+/// allowing a branchless lookup with `AQI_COLOR_TABLE[aqi as usize]`.
+/// Values above 500 must be clamped by the caller before indexing.
 ///
 /// # Examples
 ///
 /// ```
-/// let pm25_concentration = 41;
-/// let aqi = calculate_aqi(pm25_concentration as f32);
-/// assert_eq!(115, aqi);
-///
-/// let pm25_concentration = 7;
 /// let aqi = calculate_aqi(pm25_concentration as f32);
-/// assert_eq!(39, aqi);
+/// let color = AQI_COLOR_TABLE[aqi.min(500) as usize];
 /// ```
-pub fn calculate_aqi(pm25: f32) -> u16 {
-    // AQI breakpoints for PM2.5
-    // Updated in 2024, see the following from the EPA:
-    // https://www.epa.gov/system/files/documents/2024-02/pm-naaqs-air-quality-index-fact-sheet.pdf
-    // https://document.airnow.gov/technical-assistance-document-for-the-reporting-of-daily-air-quailty.pdf
-    const PM25_BREAKPOINTS: [(f32, f32); 6] = [
-        (0.0, 9.0),     // Good
-        (9.1, 35.4),    // Moderate
-        (35.5, 55.4),   // Unhealthy for Sensitive Groups
-        (55.5, 125.4),  // Unhealthy
-        (125.5, 225.4), // Very Unhealthy
-        (225.5, 500.0), // Hazardous
-    ];
-
-    // AQI values corresponding to breakpoints
-    const AQI_BREAKPOINTS: [(u16, u16); 6] = [
-        (0, 50),    // Good
-        (51, 100),  // Moderate
-        (101, 150), // Unhealthy for Sensitive Groups
-        (151, 200), // Unhealthy
-        (201, 300), // Very Unhealthy
-        (301, 500), // Hazardous
-    ];
-
-    // Find the appropriate breakpoint range
-    for i in 0..PM25_BREAKPOINTS.len() {
-        let (pm_low, pm_high) = PM25_BREAKPOINTS[i];
-        if pm25 >= pm_low && pm25 <= pm_high {
-            let (aqi_low, aqi_high) = AQI_BREAKPOINTS[i];
+pub const AQI_COLOR_TABLE: [Color; 501] = build_aqi_color_table();
 
-            // Linear interpolation formula transcribed from EPA documentation
-            // AQI = ((AQIhigh - AQIlow) / (PMhigh - PMlow)) * (PMactual - PMlow) + AQIlow
-            let aqi = ((aqi_high - aqi_low) as f32 / (pm_high - pm_low)) * (pm25 - pm_low)
-                + aqi_low as f32;
-            return libm::roundf(aqi) as u16;
-        }
+/// Builds the AQI_COLOR_TABLE at compile time using get_aqi_color,
+/// so the table and function cannot disagree.
+const fn build_aqi_color_table() -> [Color; 501] {
+    let mut table = [Color::Green; 501];
+    let mut aqi = 0;
+    while aqi < table.len() {
+        table[aqi] = get_aqi_color(aqi as u16);
+        aqi += 1;
     }
-
-    // If PM2.5 is above 500, return the maximum AQI value
-    500
+    table
 }
 
-/// Provides a Color enum variant value based on the
-/// specified AQI value. Uses the ranges provided by the
-/// EPA for mapping AQI to color.
+/// Formats a reading as Prometheus exposition text, suitable
+/// for serving from a host-side gateway's metrics endpoint.
+/// Both values are exposed as gauges. Only available with the
+/// `std` feature.
 ///
 /// # Arguments
 ///
-/// * `aqi` - The calculated AQI
+/// * `pm25` - The PM 2.5 value from the sensor
 ///
 /// # Returns
 ///
-/// A Color enum variant.
+/// A String with the PM2.5 concentration and its calculated AQI.
 ///
 /// # Examples
 ///
 /// ```
-/// let data = parse_data(&sensor_data).unwrap_or_else(|err| {
-///     hprintln!("Error parsing data: {}", err);
-///     Pmsa003iData::default()
-/// });
-///
-/// let pm25_concentration = data.pm2_5_env;
-/// let aqi = calculate_aqi(pm25_concentration as f32);
-/// let color = get_aqi_color(aqi);
+/// let metrics = prometheus_metrics(41);
+/// assert!(metrics.contains("pm25_ugm3 41\n"));
+/// assert!(metrics.contains("aqi 115\n"));
 /// ```
-pub fn get_aqi_color(aqi: u16) -> Color {
-    match aqi {
-        0..=50 => Color::Green,
-        51..=100 => Color::Yellow,
-        101..=150 => Color::Orange,
-        151..=200 => Color::Red,
-        201..=300 => Color::Purple,
-        _ => Color::DarkPurple,
-    }
+#[cfg(feature = "std")]
+pub fn prometheus_metrics(pm25: u16) -> String {
+    let aqi = calculate_aqi(pm25 as f32);
+    format!(
+        "# HELP pm25_ugm3 PM2.5 concentration in micrograms per cubic meter.\n\
+         # TYPE pm25_ugm3 gauge\n\
+         pm25_ugm3 {}\n\
+         # HELP aqi Air Quality Index calculated from PM2.5.\n\
+         # TYPE aqi gauge\n\
+         aqi {}\n",
+        pm25, aqi
+    )
 }
 
 #[cfg(test)]
@@ -145,6 +1581,522 @@ mod tests {
         assert_eq!(calculate_aqi(500.0), 500);
     }
 
+    #[test]
+    fn test_breakpoint_boundaries() {
+        // Each breakpoint concentration maps to its documented AQI
+        let boundaries = [
+            (0, 0),
+            (90, 50),
+            (91, 51),
+            (354, 100),
+            (355, 101),
+            (554, 150),
+            (555, 151),
+            (1254, 200),
+            (1255, 201),
+            (2254, 300),
+            (2255, 301),
+            (5000, 500),
+        ];
+        for (pm25_tenths, aqi) in boundaries {
+            assert_eq!(calculate_aqi_fixed(pm25_tenths), aqi);
+            assert_eq!(calculate_aqi(pm25_tenths as f32 / 10.0), aqi);
+        }
+        assert_eq!(calculate_aqi_fixed(5001), 500);
+        assert_eq!(calculate_aqi_fixed(u16::MAX), 500);
+    }
+
+    #[test]
+    fn test_breakpoint_seams() {
+        // Concentrations between the top of one range and the bottom
+        // of the next round into one of the two ranges
+        let seams = [
+            (9.04, 50),
+            (9.06, 51),
+            (35.44, 100),
+            (35.46, 101),
+            (55.44, 150),
+            (55.46, 151),
+            (125.44, 200),
+            (125.46, 201),
+            (225.44, 300),
+            (225.46, 301),
+        ];
+        for (pm25, aqi) in seams {
+            assert_eq!(calculate_aqi(pm25), aqi, "{} µg/m³", pm25);
+        }
+        assert_eq!(calculate_aqi(9.05), 51);
+    }
+
+    #[test]
+    fn test_calculate_aqi_fixed_matches_float() {
+        for pm25_tenths in 0..=5000 {
+            assert_eq!(
+                calculate_aqi_fixed(pm25_tenths),
+                calculate_aqi(pm25_tenths as f32 / 10.0),
+                "{} tenths of a µg/m³",
+                pm25_tenths
+            );
+        }
+    }
+
+    #[test]
+    fn test_calculate_aqi_f64() {
+        // Expected values follow the AirNow method of truncating to 0.1 µg/m³
+        let airnow = [
+            (0.0, 0),
+            (2.3, 13),
+            (9.0, 50),
+            (9.05, 50),
+            (9.1, 51),
+            (12.08, 56),
+            (35.4, 100),
+            (35.45, 100),
+            (35.5, 101),
+            (41.0, 115),
+            (55.45, 150),
+            (225.4, 300),
+            (500.0, 500),
+            (600.0, 500),
+        ];
+        for (pm25, aqi) in airnow {
+            assert_eq!(calculate_aqi_f64(pm25), aqi, "{} µg/m³", pm25);
+        }
+
+        // The f32 path rounds rather than truncates, so differs here
+        assert_eq!(calculate_aqi(9.05), 51);
+        assert_eq!(calculate_aqi(12.08), 57);
+        assert_eq!(calculate_aqi(35.45), 101);
+
+        // Both agree on every concentration already at 0.1 µg/m³
+        for pm25_tenths in 0..=5000 {
+            assert_eq!(
+                calculate_aqi_f64(pm25_tenths as f64 / 10.0),
+                calculate_aqi(pm25_tenths as f32 / 10.0),
+                "{} tenths of a µg/m³",
+                pm25_tenths
+            );
+        }
+    }
+
+    #[test]
+    fn test_correct_pm25() {
+        assert!((correct_pm25(20.0, 50.0) - 11.92).abs() < 0.001);
+        // Higher humidity lowers the corrected concentration
+        assert!(correct_pm25(20.0, 90.0) < correct_pm25(20.0, 30.0));
+        // Clean, humid air would correct below zero
+        assert_eq!(correct_pm25(0.0, 90.0), 0.0);
+    }
+
+    #[test]
+    fn test_breakpoint_index() {
+        let segments = [
+            (0.0, 0),
+            (9.0, 0),
+            (9.1, 1),
+            (35.4, 1),
+            (35.5, 2),
+            (55.4, 2),
+            (55.5, 3),
+            (125.4, 3),
+            (125.5, 4),
+            (225.4, 4),
+            (225.5, 5),
+            (500.0, 5),
+        ];
+        for (pm25, index) in segments {
+            assert_eq!(breakpoint_index(pm25), Some(index), "{} µg/m³", pm25);
+        }
+        // Concentrations between ranges round into one of them
+        assert_eq!(breakpoint_index(9.06), Some(1));
+
+        assert_eq!(breakpoint_index(500.1), None);
+        assert_eq!(breakpoint_index(f32::MAX), None);
+        assert_eq!(breakpoint_index(-1.0), None);
+    }
+
+    #[test]
+    fn test_concentration_to_next_category() {
+        assert_eq!(concentration_to_next_category(0.0), Some(9.1));
+        assert_eq!(concentration_to_next_category(30.0), Some(5.5));
+        assert_eq!(concentration_to_next_category(41.0), Some(14.5));
+        assert_eq!(concentration_to_next_category(100.0), Some(25.5));
+        assert_eq!(concentration_to_next_category(200.0), Some(25.5));
+
+        // The margin shrinks to just above zero at the top of a category
+        let margin = concentration_to_next_category(35.4).unwrap();
+        assert!(margin > 0.0 && margin < 0.11);
+
+        // Just below the bottom of each category, the value rounds up
+        // into it, so the margin is to the category after
+        for (pm25, next_pm25) in [(9.05, 35.5), (35.45, 55.5), (55.45, 125.5), (125.45, 225.5)] {
+            let margin = concentration_to_next_category(pm25).unwrap();
+            assert!((margin - (next_pm25 - pm25 - 0.05)).abs() < 0.001);
+            assert!(
+                get_aqi_category(calculate_aqi(pm25 + margin))
+                    > get_aqi_category(calculate_aqi(pm25))
+            );
+        }
+        assert_eq!(concentration_to_next_category(225.45), None);
+
+        // Hazardous is the top category
+        assert_eq!(concentration_to_next_category(225.5), None);
+        assert_eq!(concentration_to_next_category(600.0), None);
+    }
+
+    #[test]
+    fn test_color_thresholds() {
+        let thresholds = color_thresholds();
+        assert_eq!(
+            thresholds,
+            [
+                (Color::Green, 0.0),
+                (Color::Yellow, 9.1),
+                (Color::Orange, 35.5),
+                (Color::Red, 55.5),
+                (Color::Purple, 125.5),
+                (Color::DarkPurple, 225.5),
+            ]
+        );
+
+        for (i, &(color, pm25)) in thresholds.iter().enumerate() {
+            // Each threshold produces its color through the full pipeline
+            assert_eq!(get_aqi_color(calculate_aqi(pm25)), color);
+            // Just below the threshold, the previous color is produced
+            if i > 0 {
+                assert_eq!(
+                    get_aqi_color(calculate_aqi(pm25 - 0.1)),
+                    thresholds[i - 1].0
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_interpolate() {
+        const VALUE_BREAKPOINTS: [(f32, f32); 2] = [(0.0, 10.0), (11.0, 20.0)];
+        const AQI_BREAKPOINTS: [(u16, u16); 2] = [(0, 100), (101, 200)];
+
+        assert_eq!(interpolate(0.0, &VALUE_BREAKPOINTS, &AQI_BREAKPOINTS), 0);
+        assert_eq!(interpolate(5.0, &VALUE_BREAKPOINTS, &AQI_BREAKPOINTS), 50);
+        assert_eq!(interpolate(10.0, &VALUE_BREAKPOINTS, &AQI_BREAKPOINTS), 100);
+        assert_eq!(interpolate(11.0, &VALUE_BREAKPOINTS, &AQI_BREAKPOINTS), 101);
+        // 15.5 interpolates to 150.5, which rounds up
+        assert_eq!(interpolate(15.5, &VALUE_BREAKPOINTS, &AQI_BREAKPOINTS), 151);
+        assert_eq!(interpolate(20.0, &VALUE_BREAKPOINTS, &AQI_BREAKPOINTS), 200);
+        // Out of range values return the maximum
+        assert_eq!(interpolate(25.0, &VALUE_BREAKPOINTS, &AQI_BREAKPOINTS), 500);
+    }
+
+    #[test]
+    fn test_calculate_aqi_o3_8hr() {
+        // These expected values were confirmed using
+        // https://www.airnow.gov/aqi/aqi-calculator-concentration/
+        assert_eq!(calculate_aqi_o3_8hr(0.0), Some(0));
+        assert_eq!(calculate_aqi_o3_8hr(0.030), Some(28));
+        assert_eq!(calculate_aqi_o3_8hr(0.054), Some(50));
+        assert_eq!(calculate_aqi_o3_8hr(0.0549), Some(50));
+        assert_eq!(calculate_aqi_o3_8hr(0.055), Some(51));
+        assert_eq!(calculate_aqi_o3_8hr(0.060), Some(67));
+        assert_eq!(calculate_aqi_o3_8hr(0.075), Some(115));
+        assert_eq!(calculate_aqi_o3_8hr(0.100), Some(187));
+        assert_eq!(calculate_aqi_o3_8hr(0.150), Some(247));
+        assert_eq!(calculate_aqi_o3_8hr(0.200), Some(300));
+
+        // 8-hour ozone is undefined above 0.200 ppm
+        assert_eq!(calculate_aqi_o3_8hr(0.201), None);
+        assert_eq!(calculate_aqi_o3_8hr(0.400), None);
+    }
+
+    #[test]
+    fn test_calculate_aqi_co() {
+        // These expected values were confirmed using
+        // https://www.airnow.gov/aqi/aqi-calculator-concentration/
+        assert_eq!(calculate_aqi_co(0.0), 0);
+        assert_eq!(calculate_aqi_co(2.0), 23);
+        assert_eq!(calculate_aqi_co(4.4), 50);
+        assert_eq!(calculate_aqi_co(4.45), 50);
+        assert_eq!(calculate_aqi_co(4.5), 51);
+        assert_eq!(calculate_aqi_co(7.0), 76);
+        assert_eq!(calculate_aqi_co(11.0), 126);
+        assert_eq!(calculate_aqi_co(14.0), 176);
+        assert_eq!(calculate_aqi_co(20.0), 231);
+        assert_eq!(calculate_aqi_co(40.0), 396);
+        assert_eq!(calculate_aqi_co(50.4), 500);
+        assert_eq!(calculate_aqi_co(60.0), 500);
+    }
+
+    #[test]
+    fn test_calculate_aqi_pm10() {
+        // These expected values were confirmed using
+        // https://www.airnow.gov/aqi/aqi-calculator-concentration/
+        assert_eq!(calculate_aqi_pm10(0.0), 0);
+        assert_eq!(calculate_aqi_pm10(54.0), 50);
+        assert_eq!(calculate_aqi_pm10(54.9), 50);
+        assert_eq!(calculate_aqi_pm10(55.0), 51);
+        assert_eq!(calculate_aqi_pm10(100.0), 73);
+        assert_eq!(calculate_aqi_pm10(200.0), 123);
+        assert_eq!(calculate_aqi_pm10(300.0), 173);
+        assert_eq!(calculate_aqi_pm10(400.0), 266);
+        assert_eq!(calculate_aqi_pm10(500.0), 384);
+        assert_eq!(calculate_aqi_pm10(604.0), 500);
+        assert_eq!(calculate_aqi_pm10(700.0), 500);
+    }
+
+    #[test]
+    fn test_overall_aqi() {
+        // PM2.5 alone when PM10 is not included
+        assert_eq!(overall_aqi(115, None), 115);
+        // Otherwise the worse of the two
+        assert_eq!(overall_aqi(115, Some(73)), 115);
+        assert_eq!(overall_aqi(53, Some(173)), 173);
+        assert_eq!(overall_aqi(73, Some(73)), 73);
+    }
+
+    #[test]
+    fn test_color_to_rgb() {
+        #[cfg(not(feature = "colorblind"))]
+        let palette = EPA_PALETTE;
+        #[cfg(feature = "colorblind")]
+        let palette = COLORBLIND_PALETTE;
+
+        let colors = [
+            Color::Green,
+            Color::Yellow,
+            Color::Orange,
+            Color::Red,
+            Color::Purple,
+            Color::DarkPurple,
+        ];
+        for (color, rgb) in colors.into_iter().zip(palette) {
+            assert_eq!(color.to_rgb(), rgb);
+        }
+    }
+
+    #[test]
+    fn test_category_representative_rgb() {
+        // Each category matches the Color of an AQI within it
+        let categories = [
+            (Category::Good, 0),
+            (Category::Moderate, 51),
+            (Category::UnhealthyForSensitiveGroups, 101),
+            (Category::Unhealthy, 151),
+            (Category::VeryUnhealthy, 201),
+            (Category::Hazardous, 301),
+        ];
+        for (category, aqi) in categories {
+            assert_eq!(get_aqi_category(aqi), category);
+            assert_eq!(category.representative_rgb(), get_aqi_color(aqi).to_rgb());
+        }
+
+        #[cfg(not(feature = "colorblind"))]
+        {
+            assert_eq!(Category::Good.representative_rgb(), (0, 228, 0));
+            assert_eq!(Category::Moderate.representative_rgb(), (255, 255, 0));
+            assert_eq!(
+                Category::UnhealthyForSensitiveGroups.representative_rgb(),
+                (255, 126, 0)
+            );
+            assert_eq!(Category::Unhealthy.representative_rgb(), (255, 0, 0));
+            assert_eq!(Category::VeryUnhealthy.representative_rgb(), (143, 63, 151));
+            assert_eq!(Category::Hazardous.representative_rgb(), (126, 0, 35));
+        }
+    }
+
+    #[test]
+    fn test_palettes() {
+        assert_eq!(EPA_PALETTE[Color::Green as usize], (0, 228, 0));
+        assert_eq!(EPA_PALETTE[Color::Orange as usize], (255, 126, 0));
+        assert_eq!(EPA_PALETTE[Color::DarkPurple as usize], (126, 0, 35));
+        assert_eq!(COLORBLIND_PALETTE[Color::Green as usize], (253, 231, 37));
+        assert_eq!(COLORBLIND_PALETTE[Color::Orange as usize], (34, 168, 132));
+        assert_eq!(COLORBLIND_PALETTE[Color::DarkPurple as usize], (68, 1, 84));
+
+        // Each palette gives every Color a distinct value
+        for palette in [EPA_PALETTE, COLORBLIND_PALETTE] {
+            for (i, rgb) in palette.iter().enumerate() {
+                assert!(!palette[i + 1..].contains(rgb));
+            }
+        }
+        // The palettes differ for every Color
+        for (epa, colorblind) in EPA_PALETTE.iter().zip(COLORBLIND_PALETTE.iter()) {
+            assert_ne!(epa, colorblind);
+        }
+    }
+
+    #[test]
+    fn test_aqi_to_packed_rgb() {
+        for aqi in [0, 50, 75, 101, 160, 250, 301, 500, 600] {
+            let (red, green, blue) = get_aqi_color(aqi).to_rgb();
+            let packed = aqi_to_packed_rgb(aqi);
+            assert_eq!(packed >> 24, 0, "AQI {}", aqi);
+            assert_eq!((packed >> 16) as u8, red, "AQI {}", aqi);
+            assert_eq!((packed >> 8) as u8, green, "AQI {}", aqi);
+            assert_eq!(packed as u8, blue, "AQI {}", aqi);
+        }
+
+        #[cfg(not(feature = "colorblind"))]
+        {
+            assert_eq!(aqi_to_packed_rgb(0), 0x0000E400);
+            assert_eq!(aqi_to_packed_rgb(120), 0x00FF7E00);
+            assert_eq!(aqi_to_packed_rgb(250), 0x008F3F97);
+        }
+    }
+
+    #[test]
+    fn test_aqi_to_frequency() {
+        // Endpoints
+        assert_eq!(aqi_to_frequency(0), 200);
+        assert_eq!(aqi_to_frequency(500), 2000);
+        assert_eq!(aqi_to_frequency(u16::MAX), 2000);
+
+        // Linear, so the midpoint AQI gives the midpoint frequency
+        assert_eq!(aqi_to_frequency(250), 1100);
+        assert_eq!(aqi_to_frequency(125), 650);
+        assert_eq!(aqi_to_frequency(1), 204);
+
+        // Higher AQI always gives a higher pitch
+        for aqi in 0..500 {
+            assert!(aqi_to_frequency(aqi + 1) > aqi_to_frequency(aqi));
+        }
+    }
+
+    #[test]
+    fn test_aqi_to_rgb_smooth() {
+        // The first AQI of each band matches the discrete color
+        for (start, color) in COLOR_BAND_STARTS {
+            assert_eq!(aqi_to_rgb_smooth(start), color.to_rgb());
+        }
+
+        // Midpoints are blended halfway to the next color
+        #[cfg(not(feature = "colorblind"))]
+        {
+            assert_eq!(aqi_to_rgb_smooth(75), (255, 194, 0));
+            assert_eq!(aqi_to_rgb_smooth(126), (255, 63, 0));
+            assert_eq!(aqi_to_rgb_smooth(251), (135, 32, 93));
+        }
+
+        // Consecutive AQIs differ by a small step, including across
+        // the boundary between bands
+        let step = |(r0, g0, b0): (u8, u8, u8), (r1, g1, b1): (u8, u8, u8)| {
+            (r0.abs_diff(r1)).max(g0.abs_diff(g1)).max(b0.abs_diff(b1))
+        };
+        for aqi in 0..400 {
+            let (rgb, next_rgb) = (aqi_to_rgb_smooth(aqi), aqi_to_rgb_smooth(aqi + 1));
+            assert!(
+                step(rgb, next_rgb) <= 5,
+                "AQI {} to {} jumps from {:?} to {:?}",
+                aqi,
+                aqi + 1,
+                rgb,
+                next_rgb
+            );
+        }
+
+        // Dark Purple has no next color to blend toward
+        assert_eq!(aqi_to_rgb_smooth(400), Color::DarkPurple.to_rgb());
+        assert_eq!(aqi_to_rgb_smooth(u16::MAX), Color::DarkPurple.to_rgb());
+    }
+
+    #[test]
+    fn test_calculate_aqi_from_str() {
+        assert_eq!(calculate_aqi_from_str("41"), Ok(115));
+        assert_eq!(calculate_aqi_from_str("0"), Ok(0));
+        assert_eq!(calculate_aqi_from_str("41.0"), Ok(115));
+        assert_eq!(calculate_aqi_from_str("35.4"), Ok(100));
+        assert_eq!(calculate_aqi_from_str(" 4.5\n"), Ok(25));
+        assert_eq!(calculate_aqi_from_str(".5"), Ok(3));
+
+        assert_eq!(calculate_aqi_from_str(""), Err(AqiError::InvalidNumber));
+        assert_eq!(
+            calculate_aqi_from_str("forty-one"),
+            Err(AqiError::InvalidNumber)
+        );
+        assert_eq!(
+            calculate_aqi_from_str("41 µg/m³"),
+            Err(AqiError::InvalidNumber)
+        );
+        assert_eq!(
+            calculate_aqi_from_str("4.1.5"),
+            Err(AqiError::InvalidNumber)
+        );
+        assert_eq!(
+            calculate_aqi_from_str("-1"),
+            Err(AqiError::InvalidConcentration)
+        );
+        assert_eq!(
+            calculate_aqi_from_str("NaN"),
+            Err(AqiError::InvalidConcentration)
+        );
+        assert_eq!(
+            calculate_aqi_from_str("inf"),
+            Err(AqiError::InvalidConcentration)
+        );
+    }
+
+    #[test]
+    fn test_calculate_aqi_for() {
+        assert_eq!(
+            calculate_aqi_for(41.0, AqiStandard::Us),
+            calculate_aqi(41.0)
+        );
+
+        assert_eq!(calculate_aqi_for(0.0, AqiStandard::India), 0);
+        assert_eq!(calculate_aqi_for(30.0, AqiStandard::India), 50);
+        assert_eq!(calculate_aqi_for(30.9, AqiStandard::India), 50);
+        assert_eq!(calculate_aqi_for(31.0, AqiStandard::India), 51);
+        assert_eq!(calculate_aqi_for(41.0, AqiStandard::India), 68);
+        assert_eq!(calculate_aqi_for(75.0, AqiStandard::India), 149);
+        assert_eq!(calculate_aqi_for(100.0, AqiStandard::India), 232);
+        assert_eq!(calculate_aqi_for(200.0, AqiStandard::India), 362);
+        assert_eq!(calculate_aqi_for(251.0, AqiStandard::India), 401);
+        assert_eq!(calculate_aqi_for(600.0, AqiStandard::India), 500);
+    }
+
+    #[test]
+    fn test_dual_aqi() {
+        assert_eq!(
+            dual_aqi(41.0, AqiStandard::Us, AqiStandard::India),
+            (115, 68)
+        );
+        assert_eq!(
+            dual_aqi(41.0, AqiStandard::India, AqiStandard::Us),
+            (68, 115)
+        );
+        assert_eq!(dual_aqi(7.0, AqiStandard::Us, AqiStandard::Us), (39, 39));
+    }
+
+    #[test]
+    fn test_aqi_standard_display() {
+        assert_eq!(format!("{}", AqiStandard::Us), "US");
+        assert_eq!(format!("{}", AqiStandard::India), "India");
+    }
+
+    #[test]
+    fn test_micrograms_per_m3() {
+        for pm25 in [0.0, 4.5, 9.0, 9.05, 35.4, 41.0, 225.5, 500.0, 600.0] {
+            assert_eq!(MicrogramsPerM3(pm25).calculate_aqi(), calculate_aqi(pm25));
+        }
+        assert_eq!(MicrogramsPerM3(41.0).calculate_aqi(), 115);
+    }
+
+    #[test]
+    fn test_calculate_aqi_from() {
+        assert_eq!(
+            calculate_aqi_from(41.0, ConcentrationUnit::MicrogramsPerM3),
+            115
+        );
+        assert_eq!(
+            calculate_aqi_from(0.041, ConcentrationUnit::MilligramsPerM3),
+            calculate_aqi(41.0)
+        );
+        assert_eq!(
+            calculate_aqi_from(0.0045, ConcentrationUnit::MilligramsPerM3),
+            25
+        );
+        assert_eq!(calculate_aqi_from(7.0, ConcentrationUnit::default()), 39);
+    }
+
     #[test]
     fn test_get_aqi_color() {
         assert_eq!(get_aqi_color(0), Color::Green);
@@ -167,4 +2119,315 @@ mod tests {
         assert_eq!(get_aqi_color(500), Color::DarkPurple);
         assert_eq!(get_aqi_color(999), Color::DarkPurple);
     }
+
+    #[test]
+    fn test_get_aqi_category() {
+        assert_eq!(get_aqi_category(0), Category::Good);
+        assert_eq!(get_aqi_category(50), Category::Good);
+        assert_eq!(get_aqi_category(51), Category::Moderate);
+        assert_eq!(get_aqi_category(100), Category::Moderate);
+        assert_eq!(get_aqi_category(101), Category::UnhealthyForSensitiveGroups);
+        assert_eq!(get_aqi_category(150), Category::UnhealthyForSensitiveGroups);
+        assert_eq!(get_aqi_category(151), Category::Unhealthy);
+        assert_eq!(get_aqi_category(200), Category::Unhealthy);
+        assert_eq!(get_aqi_category(201), Category::VeryUnhealthy);
+        assert_eq!(get_aqi_category(300), Category::VeryUnhealthy);
+        assert_eq!(get_aqi_category(301), Category::Hazardous);
+        assert_eq!(get_aqi_category(999), Category::Hazardous);
+    }
+
+    #[test]
+    fn test_simple_level() {
+        assert_eq!(simple_level(0), SimpleLevel::Ok);
+        assert_eq!(simple_level(100), SimpleLevel::Ok);
+        assert_eq!(simple_level(101), SimpleLevel::Caution);
+        assert_eq!(simple_level(150), SimpleLevel::Caution);
+        assert_eq!(simple_level(151), SimpleLevel::Danger);
+        assert_eq!(simple_level(500), SimpleLevel::Danger);
+        assert_eq!(simple_level(u16::MAX), SimpleLevel::Danger);
+    }
+
+    #[test]
+    fn test_activity_guidance() {
+        assert_eq!(activity_guidance(0), ActivityLevel::Normal);
+        assert_eq!(activity_guidance(50), ActivityLevel::Normal);
+        assert_eq!(activity_guidance(51), ActivityLevel::Normal);
+        assert_eq!(activity_guidance(100), ActivityLevel::Normal);
+        assert_eq!(activity_guidance(101), ActivityLevel::Normal);
+        assert_eq!(activity_guidance(150), ActivityLevel::Normal);
+        assert_eq!(activity_guidance(151), ActivityLevel::ReduceProlonged);
+        assert_eq!(activity_guidance(200), ActivityLevel::ReduceProlonged);
+        assert_eq!(activity_guidance(201), ActivityLevel::AvoidProlonged);
+        assert_eq!(activity_guidance(300), ActivityLevel::AvoidProlonged);
+        assert_eq!(activity_guidance(301), ActivityLevel::AvoidAll);
+        assert_eq!(activity_guidance(u16::MAX), ActivityLevel::AvoidAll);
+    }
+
+    #[test]
+    fn test_recommendation() {
+        use Recommendation::*;
+
+        // Each row is the AQI, then the recommendation for the general
+        // public and for sensitive groups
+        let cases = [
+            (0, StayOutdoorsOk, StayOutdoorsOk),
+            (100, StayOutdoorsOk, StayOutdoorsOk),
+            (101, StayOutdoorsOk, LimitOutdoor),
+            (150, StayOutdoorsOk, LimitOutdoor),
+            (151, LimitOutdoor, StayIndoors),
+            (200, LimitOutdoor, StayIndoors),
+            (201, StayIndoors, StayIndoors),
+            (u16::MAX, StayIndoors, StayIndoors),
+        ];
+        for (aqi, general, sensitive) in cases {
+            assert_eq!(
+                recommendation(aqi, Sensitivity::General),
+                general,
+                "AQI {}",
+                aqi
+            );
+            assert_eq!(
+                recommendation(aqi, Sensitivity::Sensitive),
+                sensitive,
+                "AQI {}",
+                aqi
+            );
+        }
+    }
+
+    #[test]
+    fn test_category_display() {
+        use std::string::ToString;
+
+        assert_eq!(Category::Good.to_string(), "Good");
+        assert_eq!(Category::Moderate.to_string(), "Moderate");
+        assert_eq!(
+            Category::UnhealthyForSensitiveGroups.to_string(),
+            "Unhealthy for Sensitive Groups"
+        );
+        assert_eq!(Category::Unhealthy.to_string(), "Unhealthy");
+        assert_eq!(Category::VeryUnhealthy.to_string(), "Very Unhealthy");
+        assert_eq!(Category::Hazardous.to_string(), "Hazardous");
+        assert_eq!(
+            format!("AQI 115: {}", get_aqi_category(115)),
+            "AQI 115: Unhealthy for Sensitive Groups"
+        );
+    }
+
+    #[test]
+    fn test_category_ordering() {
+        assert!(Category::Hazardous > Category::Good);
+        assert!(Category::Good < Category::Moderate);
+        assert!(Category::Moderate < Category::UnhealthyForSensitiveGroups);
+        assert!(Category::UnhealthyForSensitiveGroups < Category::Unhealthy);
+        assert!(Category::Unhealthy < Category::VeryUnhealthy);
+        assert!(Category::VeryUnhealthy < Category::Hazardous);
+    }
+
+    #[test]
+    fn test_aqi_delta() {
+        assert_eq!(aqi_delta(None, 0), 0);
+        assert_eq!(aqi_delta(None, 115), 0);
+        assert_eq!(aqi_delta(Some(103), 115), 12);
+        assert_eq!(aqi_delta(Some(115), 96), -19);
+        assert_eq!(aqi_delta(Some(96), 96), 0);
+        assert_eq!(aqi_delta(Some(0), 500), 500);
+        assert_eq!(aqi_delta(Some(500), 0), -500);
+    }
+
+    #[test]
+    fn test_crossed_milestone() {
+        let milestones = [150, 100, 200];
+
+        // Upward crossings, including landing exactly on a milestone
+        assert_eq!(crossed_milestone(95, 105, &milestones), Some(100));
+        assert_eq!(crossed_milestone(99, 100, &milestones), Some(100));
+        // Crossing several at once reports the highest
+        assert_eq!(crossed_milestone(90, 210, &milestones), Some(200));
+
+        // No crossing
+        assert_eq!(crossed_milestone(101, 140, &milestones), None);
+        assert_eq!(crossed_milestone(100, 100, &milestones), None);
+        assert_eq!(crossed_milestone(50, 60, &[]), None);
+
+        // Downward movement never crosses
+        assert_eq!(crossed_milestone(105, 95, &milestones), None);
+        assert_eq!(crossed_milestone(210, 90, &milestones), None);
+    }
+
+    #[test]
+    fn test_worst_in_batch() {
+        // A clear maximum in the middle of the batch
+        assert_eq!(worst_in_batch(&[4.0, 41.0, 12.0]), Some((1, 115)));
+        // Ties report the earliest reading
+        assert_eq!(worst_in_batch(&[12.0, 41.0, 41.0]), Some((1, 115)));
+        assert_eq!(worst_in_batch(&[4.0]), Some((0, 22)));
+        assert_eq!(worst_in_batch(&[]), None);
+    }
+
+    #[test]
+    fn test_should_report() {
+        // The first reading is always reported
+        assert!(should_report(None, 0, 5));
+        assert!(should_report(None, 42, u16::MAX));
+
+        // Changes within a category must exceed the threshold
+        assert!(!should_report(Some(40), 40, 5));
+        assert!(!should_report(Some(40), 45, 5));
+        assert!(!should_report(Some(45), 40, 5));
+        assert!(should_report(Some(40), 46, 5));
+        assert!(should_report(Some(46), 40, 5));
+
+        // Crossing a category boundary is always reported
+        assert!(should_report(Some(50), 51, 5));
+        assert!(should_report(Some(101), 100, 5));
+        assert!(should_report(Some(150), 151, u16::MAX));
+    }
+
+    #[test]
+    fn test_health_score() {
+        assert_eq!(health_score(0), 100);
+        assert_eq!(health_score(1), 100);
+        assert_eq!(health_score(2), 99);
+        assert_eq!(health_score(150), 50);
+        assert_eq!(health_score(299), 0);
+        assert_eq!(health_score(300), 0);
+        assert_eq!(health_score(u16::MAX), 0);
+    }
+
+    #[test]
+    fn test_steps_to_good() {
+        let steps = [
+            (0, 0),
+            (50, 0),
+            (51, 1),
+            (100, 1),
+            (101, 2),
+            (150, 2),
+            (151, 3),
+            (200, 3),
+            (201, 4),
+            (300, 4),
+            (301, 5),
+            (500, 5),
+            (u16::MAX, 5),
+        ];
+        for (aqi, expected) in steps {
+            assert_eq!(steps_to_good(aqi), expected, "AQI {}", aqi);
+        }
+    }
+
+    #[test]
+    fn test_aqi_bar() {
+        let mut buf = [0u8; 20];
+        assert_eq!(aqi_bar(0, 10, &mut buf), "----------");
+        assert_eq!(aqi_bar(115, 10, &mut buf), "##--------");
+        assert_eq!(aqi_bar(250, 10, &mut buf), "#####-----");
+        assert_eq!(aqi_bar(500, 10, &mut buf), "##########");
+        assert_eq!(aqi_bar(u16::MAX, 10, &mut buf), "##########");
+
+        let fill_count = |aqi, width, buf: &mut [u8]| {
+            aqi_bar(aqi, width, buf)
+                .chars()
+                .filter(|&c| c == BAR_FILLED)
+                .count()
+        };
+        assert_eq!(fill_count(100, 16, &mut buf), 3);
+        assert_eq!(fill_count(150, 20, &mut buf), 6);
+        assert_eq!(fill_count(24, 20, &mut buf), 1);
+        assert_eq!(fill_count(300, 0, &mut buf), 0);
+
+        // The bar shrinks to fit a short buffer
+        let mut short = [0u8; 4];
+        assert_eq!(aqi_bar(250, 10, &mut short), "##--");
+    }
+
+    #[test]
+    fn test_aqi_color_table() {
+        for (aqi, color) in AQI_COLOR_TABLE.iter().enumerate() {
+            assert_eq!(*color, get_aqi_color(aqi as u16));
+        }
+    }
+
+    #[test]
+    fn test_color_as_map_key() {
+        use std::collections::HashMap;
+
+        let colors = [
+            Color::Green,
+            Color::Yellow,
+            Color::Orange,
+            Color::Red,
+            Color::Purple,
+            Color::DarkPurple,
+        ];
+
+        let mut counts: HashMap<Color, u32> = HashMap::new();
+        for color in colors {
+            *counts.entry(color).or_insert(0) += 1;
+        }
+        // Inserting a duplicate key should increment the existing entry
+        *counts.entry(Color::Green).or_insert(0) += 1;
+
+        assert_eq!(counts.len(), 6);
+        assert_eq!(counts[&Color::Green], 2);
+        assert_eq!(counts[&Color::DarkPurple], 1);
+    }
+
+    #[test]
+    fn test_color_is_adjacent() {
+        assert!(Color::Green.is_adjacent(&Color::Yellow));
+        assert!(Color::Yellow.is_adjacent(&Color::Green));
+        assert!(Color::Purple.is_adjacent(&Color::DarkPurple));
+
+        assert!(!Color::Green.is_adjacent(&Color::Orange));
+        assert!(!Color::Green.is_adjacent(&Color::DarkPurple));
+        assert!(!Color::Red.is_adjacent(&Color::Red));
+    }
+
+    #[test]
+    fn test_color_code() {
+        let colors = [
+            Color::Green,
+            Color::Yellow,
+            Color::Orange,
+            Color::Red,
+            Color::Purple,
+            Color::DarkPurple,
+        ];
+        for (code, color) in colors.into_iter().enumerate() {
+            assert_eq!(color.to_code(), code as u8);
+            assert_eq!(Color::from_code(color.to_code()), Some(color));
+        }
+
+        // Codes fit in 3 bits, but only six of the eight are used
+        assert_eq!(Color::from_code(6), None);
+        assert_eq!(Color::from_code(7), None);
+        assert_eq!(Color::from_code(u8::MAX), None);
+    }
+
+    #[test]
+    fn test_color_ordering() {
+        assert!(Color::DarkPurple > Color::Green);
+        assert!(Color::Green < Color::Yellow);
+        assert!(Color::Yellow < Color::Orange);
+        assert!(Color::Orange < Color::Red);
+        assert!(Color::Red < Color::Purple);
+        assert!(Color::Purple < Color::DarkPurple);
+
+        let readings = [Color::Yellow, Color::Red, Color::Green, Color::Orange];
+        assert_eq!(readings.iter().max(), Some(&Color::Red));
+        assert_eq!(readings.iter().min(), Some(&Color::Green));
+    }
+
+    #[test]
+    fn test_prometheus_metrics() {
+        let expected = "# HELP pm25_ugm3 PM2.5 concentration in micrograms per cubic meter.\n\
+                        # TYPE pm25_ugm3 gauge\n\
+                        pm25_ugm3 41\n\
+                        # HELP aqi Air Quality Index calculated from PM2.5.\n\
+                        # TYPE aqi gauge\n\
+                        aqi 115\n";
+        assert_eq!(prometheus_metrics(41), expected);
+    }
 }
@@ -0,0 +1,306 @@
+//! Monitor module
+//!
+//! This module provides the measurement loop logic, reporting each
+//! reading and averaging the readings taken while the button is held.
+
+use crate::{
+    aqi_delta, calculate_aqi_for, get_aqi_color, should_report, AqiStandard, Output, ReadingReport,
+    RollingAverage, SequenceCounter,
+};
+
+/// The Monitor struct holds state carried across readings, and
+/// averages up to `N` readings while the button is held.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Monitor<const N: usize> {
+    /// Most recently reported AQI, used to show the change between readings
+    previous_aqi: Option<u16>,
+    /// Sequence number source, used to tag each reported reading
+    sequence: SequenceCounter,
+    /// Readings taken during the current hold
+    readings: RollingAverage<N>,
+    /// Standard to additionally report each AQI under, if any
+    secondary_standard: Option<AqiStandard>,
+}
+
+impl<const N: usize> Monitor<N> {
+    /// Create a new Monitor with no previous readings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut monitor: Monitor<30> = Monitor::new();
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            previous_aqi: None,
+            sequence: SequenceCounter::new(),
+            readings: RollingAverage::new(),
+            secondary_standard: None,
+        }
+    }
+
+    /// Set a second standard under which each reported AQI is also
+    /// calculated, so both can be printed side by side.
+    ///
+    /// # Arguments
+    ///
+    /// * `standard` - The second AQI standard, or None to report the
+    ///   US AQI alone
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut monitor: Monitor<30> =
+    ///     Monitor::new().with_secondary_standard(Some(AqiStandard::India));
+    /// ```
+    pub const fn with_secondary_standard(mut self, standard: Option<AqiStandard>) -> Self {
+        self.secondary_standard = standard;
+        self
+    }
+
+    /// Record a reading taken while the button is held, and report it
+    /// with the AQI of the average across the hold so far.
+    ///
+    /// # Arguments
+    ///
+    /// * `pm25` - The PM 2.5 value from the sensor
+    /// * `output` - The Output to report the reading to
+    ///
+    /// # Returns
+    ///
+    /// The reported reading, including the AQI and Color of the average
+    /// across the hold so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if let Some((pm25_concentration, _)) = read_pm(&mut i2c).await {
+    ///     let report = monitor.record(pm25_concentration, &mut output);
+    ///     led_controller.set_color(report.color);
+    /// }
+    /// ```
+    pub fn record<O: Output>(&mut self, pm25: u16, output: &mut O) -> ReadingReport {
+        let aqi = self.push(pm25);
+        self.report(pm25, aqi, output)
+    }
+
+    /// Record a reading taken while reading continuously, and report it
+    /// only if the AQI of the average has changed significantly since
+    /// the last reported reading, as decided by `should_report`.
+    ///
+    /// # Arguments
+    ///
+    /// * `pm25` - The PM 2.5 value from the sensor
+    /// * `min_delta` - The change in AQI which must be exceeded to report
+    ///   a reading within the same category
+    /// * `output` - The Output to report the reading to
+    ///
+    /// # Returns
+    ///
+    /// The reported reading, or None if the reading was not reported
+    /// and the display should be left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if let Some(report) = monitor.record_significant(pm25_concentration, 5, &mut output) {
+    ///     led_controller.set_color(report.color);
+    /// }
+    /// ```
+    pub fn record_significant<O: Output>(
+        &mut self,
+        pm25: u16,
+        min_delta: u16,
+        output: &mut O,
+    ) -> Option<ReadingReport> {
+        let aqi = self.push(pm25);
+        if !should_report(self.previous_aqi, aqi, min_delta) {
+            return None;
+        }
+        Some(self.report(pm25, aqi, output))
+    }
+
+    /// End the current hold, reporting a summary and clearing the
+    /// readings so the next hold starts fresh.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The Output to report the summary to
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// monitor.finish_hold(&mut output);
+    /// led_controller.all_off();
+    /// ```
+    pub fn finish_hold<O: Output>(&mut self, output: &mut O) {
+        let average_aqi = if self.readings.len() > 1 {
+            self.readings.aqi()
+        } else {
+            None
+        };
+        output.log_hold_summary(self.readings.len(), average_aqi);
+        self.readings.clear();
+    }
+}
+
+impl<const N: usize> Monitor<N> {
+    /// Add a reading to the average, returning the AQI of the average.
+    fn push(&mut self, pm25: u16) -> u16 {
+        self.readings.push(pm25 as f32);
+        // At least one reading was just pushed, so the average always exists
+        self.readings.aqi().unwrap_or_default()
+    }
+
+    /// Calculate the AQI of the average under the secondary standard.
+    fn secondary_aqi(&self) -> Option<(AqiStandard, u16)> {
+        let standard = self.secondary_standard?;
+        let average = self.readings.average()?;
+        Some((standard, calculate_aqi_for(average, standard)))
+    }
+
+    /// Report a reading, returning what was reported.
+    fn report<O: Output>(&mut self, pm25: u16, aqi: u16, output: &mut O) -> ReadingReport {
+        let report = ReadingReport {
+            sequence: self.sequence.next(),
+            pm25,
+            aqi,
+            delta: aqi_delta(self.previous_aqi, aqi),
+            color: get_aqi_color(aqi),
+            secondary_aqi: self.secondary_aqi(),
+        };
+        output.log_reading(&report);
+        self.previous_aqi = Some(aqi);
+        report
+    }
+}
+
+impl<const N: usize> Default for Monitor<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Verbosity};
+    use core::fmt;
+    use std::vec::Vec;
+
+    /// A mock Output that records everything reported to it.
+    #[derive(Default)]
+    struct MockOutput {
+        readings: Vec<ReadingReport>,
+        summaries: Vec<(usize, Option<u16>)>,
+    }
+
+    impl Output for MockOutput {
+        fn verbosity(&self) -> Verbosity {
+            Verbosity::Normal
+        }
+
+        fn write_line(&mut self, _args: fmt::Arguments) {}
+
+        fn log_reading(&mut self, reading: &ReadingReport) {
+            self.readings.push(*reading);
+        }
+
+        fn log_hold_summary(&mut self, readings: usize, average_aqi: Option<u16>) {
+            self.summaries.push((readings, average_aqi));
+        }
+    }
+
+    #[test]
+    fn test_hold_reporting() {
+        let mut monitor: Monitor<30> = Monitor::new();
+        let mut output = MockOutput::default();
+
+        // A single reading reports no average
+        assert_eq!(monitor.record(41, &mut output).color, Color::Orange);
+        monitor.finish_hold(&mut output);
+
+        // The AQI reported during a hold is the running average
+        assert_eq!(monitor.record(33, &mut output).color, Color::Yellow);
+        assert_eq!(monitor.record(35, &mut output).color, Color::Yellow);
+        monitor.finish_hold(&mut output);
+
+        assert_eq!(
+            output.readings,
+            [
+                ReadingReport {
+                    sequence: 0,
+                    pm25: 41,
+                    aqi: 115,
+                    delta: 0,
+                    color: Color::Orange,
+                    secondary_aqi: None,
+                },
+                ReadingReport {
+                    sequence: 1,
+                    pm25: 33,
+                    aqi: 96,
+                    delta: -19,
+                    color: Color::Yellow,
+                    secondary_aqi: None,
+                },
+                ReadingReport {
+                    sequence: 2,
+                    pm25: 35,
+                    aqi: 97,
+                    delta: 1,
+                    color: Color::Yellow,
+                    secondary_aqi: None,
+                },
+            ]
+        );
+        assert_eq!(output.summaries, [(1, None), (2, Some(97))]);
+    }
+
+    #[test]
+    fn test_significant_reporting() {
+        // Average over a single reading, so each AQI is easy to follow
+        let mut monitor: Monitor<1> = Monitor::new();
+        let mut output = MockOutput::default();
+
+        let color = |report: Option<ReadingReport>| report.map(|report| report.color);
+        assert_eq!(
+            color(monitor.record_significant(10, 5, &mut output)),
+            Some(Color::Yellow)
+        );
+        // AQI 53 -> 55 is too small a change to report
+        assert_eq!(monitor.record_significant(11, 5, &mut output), None);
+        // AQI 53 -> 60 exceeds the threshold
+        assert_eq!(
+            color(monitor.record_significant(14, 5, &mut output)),
+            Some(Color::Yellow)
+        );
+        // AQI 60 -> 50 crosses into Good
+        assert_eq!(
+            color(monitor.record_significant(9, 20, &mut output)),
+            Some(Color::Green)
+        );
+
+        let reported: Vec<(u32, u16, i32)> = output
+            .readings
+            .iter()
+            .map(|reading| (reading.sequence, reading.aqi, reading.delta))
+            .collect();
+        assert_eq!(reported, [(0, 53, 0), (1, 60, 7), (2, 50, -10)]);
+    }
+
+    #[test]
+    fn test_secondary_standard() {
+        let mut monitor: Monitor<30> =
+            Monitor::new().with_secondary_standard(Some(AqiStandard::India));
+        let mut output = MockOutput::default();
+
+        let report = monitor.record(41, &mut output);
+        assert_eq!(report.aqi, 115);
+        assert_eq!(report.secondary_aqi, Some((AqiStandard::India, 68)));
+
+        // The secondary AQI follows the same running average
+        let report = monitor.record(31, &mut output);
+        assert_eq!(report.secondary_aqi, Some((AqiStandard::India, 59)));
+    }
+}
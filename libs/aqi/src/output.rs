@@ -0,0 +1,240 @@
+//! Output module
+//!
+//! This module provides the Output trait through which readings are
+//! reported, so that the measurement loop does not depend on a
+//! particular logging mechanism such as semihosting or RTT.
+
+use crate::{AqiStandard, Color};
+use core::fmt;
+
+/// The ReadingReport struct holds everything reported for a single
+/// reading taken while the button is held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadingReport {
+    /// Sequence number of the reading, for spotting dropped readings
+    pub sequence: u32,
+    /// The PM2.5 concentration in µg/m³
+    pub pm25: u16,
+    /// The AQI of the rolling average across the current hold
+    pub aqi: u16,
+    /// The change in AQI since the previously reported reading
+    pub delta: i32,
+    /// The Color corresponding to `aqi`
+    pub color: Color,
+    /// The AQI of the same average under a second standard, if one
+    /// was configured, for printing alongside the US AQI
+    pub secondary_aqi: Option<(AqiStandard, u16)>,
+}
+
+/// Verbosity enum provides how much is printed for each reading, from
+/// only the AQI, for clean logs in production, up to a dump of every
+/// sensor register, for debugging. Each message has a level, and is
+/// printed if the configured verbosity allows that level.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Only the AQI, along with warnings and errors
+    Quiet,
+    /// The full reading
+    #[default]
+    Normal,
+    /// The full reading and a dump of the sensor registers
+    Verbose,
+}
+
+impl Verbosity {
+    /// Decides whether a message is printed at this verbosity.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The level of the message, the lowest verbosity at
+    ///   which it is printed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if output.verbosity().allows(Verbosity::Verbose) {
+    ///     output.write_line(format_args!("Register 0x00: 0x{:02X}", sensor_data[0]));
+    /// }
+    /// ```
+    pub const fn allows(self, level: Verbosity) -> bool {
+        self as u8 >= level as u8
+    }
+}
+
+/// The Output trait is implemented by each logging backend. A backend
+/// only provides how a line is written and its verbosity; which
+/// messages are printed at which verbosity is decided here, so that
+/// every backend prints the same messages.
+pub trait Output {
+    /// The verbosity of this Output, deciding which messages it prints.
+    fn verbosity(&self) -> Verbosity;
+
+    /// Write a single line, regardless of verbosity.
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The formatted line, without a line ending
+    fn write_line(&mut self, args: fmt::Arguments);
+
+    /// Write a line if the verbosity of this Output allows messages at
+    /// `level`. Warnings and errors should use `Verbosity::Quiet`, so
+    /// that they are always printed.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The level of the message
+    /// * `args` - The formatted line, without a line ending
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// output.log(Verbosity::Normal, format_args!("RH: {:.1}%", rh));
+    /// ```
+    fn log(&mut self, level: Verbosity, args: fmt::Arguments) {
+        if self.verbosity().allows(level) {
+            self.write_line(args);
+        }
+    }
+
+    /// Report a single reading. Only the AQI is printed at
+    /// `Verbosity::Quiet`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reading` - The reading to report
+    fn log_reading(&mut self, reading: &ReadingReport) {
+        self.log(
+            Verbosity::Normal,
+            format_args!(
+                "#{} PM2.5 concentration: {} µg/m³",
+                reading.sequence, reading.pm25
+            ),
+        );
+        self.log(
+            Verbosity::Quiet,
+            format_args!(
+                "Calculated AQI: {} ({:+} since last), Color: {:?}",
+                reading.aqi, reading.delta, reading.color
+            ),
+        );
+        if let Some((standard, secondary_aqi)) = reading.secondary_aqi {
+            self.log(
+                Verbosity::Normal,
+                format_args!(
+                    "US AQI {} / {} AQI {}",
+                    reading.aqi, standard, secondary_aqi
+                ),
+            );
+        }
+    }
+
+    /// Report the end of a button hold. The average is printed at
+    /// `Verbosity::Quiet`, as the AQI of each reading is.
+    ///
+    /// # Arguments
+    ///
+    /// * `readings` - The number of readings taken during the hold
+    /// * `average_aqi` - The AQI of the average across the hold, if
+    ///   more than one reading was taken
+    fn log_hold_summary(&mut self, readings: usize, average_aqi: Option<u16>) {
+        if let Some(aqi) = average_aqi {
+            self.log(
+                Verbosity::Quiet,
+                format_args!("Average AQI over {} readings: {}", readings, aqi),
+            );
+        }
+        // Blank line to separate output between holds
+        self.log(Verbosity::Normal, format_args!(""));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::{String, ToString};
+    use std::vec::Vec;
+
+    #[test]
+    fn test_allows() {
+        assert!(Verbosity::Quiet.allows(Verbosity::Quiet));
+        assert!(!Verbosity::Quiet.allows(Verbosity::Normal));
+        assert!(!Verbosity::Quiet.allows(Verbosity::Verbose));
+
+        assert!(Verbosity::Normal.allows(Verbosity::Quiet));
+        assert!(Verbosity::Normal.allows(Verbosity::Normal));
+        assert!(!Verbosity::Normal.allows(Verbosity::Verbose));
+
+        assert!(Verbosity::Verbose.allows(Verbosity::Quiet));
+        assert!(Verbosity::Verbose.allows(Verbosity::Normal));
+        assert!(Verbosity::Verbose.allows(Verbosity::Verbose));
+
+        assert_eq!(Verbosity::default(), Verbosity::Normal);
+    }
+
+    /// An Output which records each line written to it.
+    struct LineOutput {
+        verbosity: Verbosity,
+        lines: Vec<String>,
+    }
+
+    impl LineOutput {
+        fn new(verbosity: Verbosity) -> Self {
+            Self {
+                verbosity,
+                lines: Vec::new(),
+            }
+        }
+    }
+
+    impl Output for LineOutput {
+        fn verbosity(&self) -> Verbosity {
+            self.verbosity
+        }
+
+        fn write_line(&mut self, args: fmt::Arguments) {
+            self.lines.push(args.to_string());
+        }
+    }
+
+    #[test]
+    fn test_log_levels() {
+        let reading = ReadingReport {
+            sequence: 3,
+            pm25: 41,
+            aqi: 115,
+            delta: -4,
+            color: Color::Orange,
+            secondary_aqi: Some((AqiStandard::India, 68)),
+        };
+
+        // Only the AQI and the average are printed when quiet
+        let mut quiet = LineOutput::new(Verbosity::Quiet);
+        quiet.log_reading(&reading);
+        quiet.log_hold_summary(2, Some(115));
+        assert_eq!(
+            quiet.lines,
+            [
+                "Calculated AQI: 115 (-4 since last), Color: Orange",
+                "Average AQI over 2 readings: 115",
+            ]
+        );
+
+        let mut normal = LineOutput::new(Verbosity::Normal);
+        normal.log_reading(&reading);
+        normal.log_hold_summary(1, None);
+        assert_eq!(
+            normal.lines,
+            [
+                "#3 PM2.5 concentration: 41 µg/m³",
+                "Calculated AQI: 115 (-4 since last), Color: Orange",
+                "US AQI 115 / India AQI 68",
+                "",
+            ]
+        );
+
+        normal.lines.clear();
+        normal.log(Verbosity::Verbose, format_args!("Register dump"));
+        normal.log(Verbosity::Quiet, format_args!("Error"));
+        assert_eq!(normal.lines, ["Error"]);
+    }
+}
@@ -0,0 +1,137 @@
+//! Pattern module
+//!
+//! This module provides timed LED blink patterns, such as the SOS shown
+//! after the firmware has faulted, which can be played back without an
+//! executor, such as from a panic handler.
+
+/// The BlinkPattern struct describes a repeating pattern of flashes as
+/// alternating on and off durations, starting with the LEDs on. Each
+/// duration is a whole number of timing units, as in Morse code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlinkPattern {
+    /// Alternating on and off durations in units, starting on
+    pub steps: &'static [u8],
+    /// Length of one unit in milliseconds
+    pub unit_ms: u32,
+}
+
+impl BlinkPattern {
+    /// SOS in Morse code, three short, three long, and three short
+    /// flashes. Flashes are separated by one unit within a letter and
+    /// three between letters, with a gap of seven before repeating.
+    pub const SOS: BlinkPattern = BlinkPattern {
+        steps: &[
+            1, 1, 1, 1, 1, 3, // S
+            3, 1, 3, 1, 3, 3, // O
+            1, 1, 1, 1, 1, 7, // S
+        ],
+        unit_ms: 200,
+    };
+
+    /// The length of one repetition of the pattern in milliseconds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(BlinkPattern::SOS.period_ms(), 6800);
+    /// ```
+    pub fn period_ms(&self) -> u32 {
+        self.steps.iter().map(|&units| units as u32).sum::<u32>() * self.unit_ms
+    }
+
+    /// Iterate over one repetition of the pattern, as `(lit, duration_ms)`
+    /// pairs, for playing the pattern back with blocking delays.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// for (lit, duration_ms) in BlinkPattern::SOS.steps() {
+    ///     led_controller.set_mask(if lit { u8::MAX } else { 0 });
+    ///     cortex_m::asm::delay(duration_ms * CYCLES_PER_MS);
+    /// }
+    /// ```
+    pub fn steps(&self) -> impl Iterator<Item = (bool, u32)> + '_ {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(i, &units)| (i % 2 == 0, units as u32 * self.unit_ms))
+    }
+
+    /// Decides whether the LEDs are on at a given time, repeating the
+    /// pattern indefinitely.
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed_ms` - Time since the pattern started in milliseconds
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let elapsed_ms = Instant::now().as_millis() as u32 - start_ms;
+    /// led_controller.set_mask(if BlinkPattern::SOS.is_lit(elapsed_ms) { u8::MAX } else { 0 });
+    /// ```
+    pub fn is_lit(&self, elapsed_ms: u32) -> bool {
+        let period_ms = self.period_ms();
+        if period_ms == 0 {
+            return false;
+        }
+        let mut remaining_ms = elapsed_ms % period_ms;
+        for (lit, duration_ms) in self.steps() {
+            if remaining_ms < duration_ms {
+                return lit;
+            }
+            remaining_ms -= duration_ms;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sos() {
+        let sos = BlinkPattern::SOS;
+        assert_eq!(sos.period_ms(), 34 * 200);
+
+        // The first S is three short flashes
+        assert!(sos.is_lit(0));
+        assert!(sos.is_lit(199));
+        assert!(!sos.is_lit(200));
+        assert!(sos.is_lit(400));
+        assert!(sos.is_lit(800));
+        assert!(!sos.is_lit(1000));
+
+        // The O is three long flashes, starting after a letter gap
+        assert!(!sos.is_lit(1599));
+        assert!(sos.is_lit(1600));
+        assert!(sos.is_lit(2199));
+        assert!(!sos.is_lit(2200));
+
+        // The gap before repeating is dark throughout
+        assert!(sos.is_lit(5399));
+        assert!(!sos.is_lit(5400));
+        assert!(!sos.is_lit(6799));
+        assert!(sos.is_lit(6800));
+
+        // Nine flashes per repetition, with on-time of 3 + 9 + 3 units
+        assert_eq!(sos.steps().filter(|&(lit, _)| lit).count(), 9);
+        let on_ms: u32 = sos
+            .steps()
+            .filter(|&(lit, _)| lit)
+            .map(|(_, duration_ms)| duration_ms)
+            .sum();
+        assert_eq!(on_ms, 15 * 200);
+    }
+
+    #[test]
+    fn test_empty_pattern() {
+        let pattern = BlinkPattern {
+            steps: &[],
+            unit_ms: 200,
+        };
+        assert_eq!(pattern.period_ms(), 0);
+        assert!(!pattern.is_lit(0));
+    }
+}
@@ -0,0 +1,257 @@
+//! Press module
+//!
+//! This module provides detection of long presses and double presses
+//! of a button, and the action taken for a hold of a given duration.
+
+/// The LongPress struct detects when a button has been held for at
+/// least a given duration. Each press is reported at most once, no
+/// matter how long the button is held after reaching the duration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LongPress {
+    duration_ms: u32,
+    pressed_since_ms: Option<u32>,
+    triggered: bool,
+}
+
+impl LongPress {
+    /// Create a new LongPress with the button released.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration_ms` - How long the button must be held to count
+    ///   as a long press
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut long_press = LongPress::new(3000);
+    /// ```
+    pub const fn new(duration_ms: u32) -> Self {
+        Self {
+            duration_ms,
+            pressed_since_ms: None,
+            triggered: false,
+        }
+    }
+
+    /// Record the state of the button. Timestamps are expected to come
+    /// from a monotonic millisecond clock, and wraparound of the clock
+    /// is handled.
+    ///
+    /// # Arguments
+    ///
+    /// * `pressed` - Whether the button is currently pressed
+    /// * `timestamp_ms` - Time the button state was read in milliseconds
+    ///
+    /// # Returns
+    ///
+    /// True only on the first update at which the current press has
+    /// lasted at least the configured duration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if long_press.update(button.is_high(), Instant::now().as_millis() as u32) {
+    ///     hprintln!("Long press detected");
+    /// }
+    /// ```
+    pub fn update(&mut self, pressed: bool, timestamp_ms: u32) -> bool {
+        if !pressed {
+            self.pressed_since_ms = None;
+            self.triggered = false;
+            return false;
+        }
+
+        let pressed_since_ms = *self.pressed_since_ms.get_or_insert(timestamp_ms);
+        if self.triggered || timestamp_ms.wrapping_sub(pressed_since_ms) < self.duration_ms {
+            return false;
+        }
+        self.triggered = true;
+        true
+    }
+}
+
+/// The DoublePress struct detects two presses of a button in quick
+/// succession. A third quick press starts a new pair, rather than
+/// counting as a second double press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DoublePress {
+    window_ms: u32,
+    last_press_ms: Option<u32>,
+}
+
+impl DoublePress {
+    /// Create a new DoublePress with no previous press.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_ms` - Longest time between two presses for them to
+    ///   count as a double press
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut double_press = DoublePress::new(1500);
+    /// ```
+    pub const fn new(window_ms: u32) -> Self {
+        Self {
+            window_ms,
+            last_press_ms: None,
+        }
+    }
+
+    /// Record a press of the button. Timestamps are expected to come
+    /// from a monotonic millisecond clock, and wraparound of the clock
+    /// is handled.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp_ms` - Time the button was pressed in milliseconds
+    ///
+    /// # Returns
+    ///
+    /// True if this press completes a double press.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// button.wait_for_rising_edge().await;
+    /// if double_press.press(Instant::now().as_millis() as u32) {
+    ///     stats.reset();
+    /// }
+    /// ```
+    pub fn press(&mut self, timestamp_ms: u32) -> bool {
+        match self.last_press_ms {
+            Some(last_press_ms) if timestamp_ms.wrapping_sub(last_press_ms) <= self.window_ms => {
+                self.last_press_ms = None;
+                true
+            }
+            _ => {
+                self.last_press_ms = Some(timestamp_ms);
+                false
+            }
+        }
+    }
+}
+
+/// HoldAction enum provides what holding the button does, depending
+/// on how long it has been held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HoldAction {
+    /// Keep taking readings for as long as the button is held
+    Read,
+    /// Stop taking readings, turn everything off, and wait for a
+    /// press to resume
+    Park,
+}
+
+/// Decides what a hold of the button does, by how long it has lasted.
+///
+/// # Arguments
+///
+/// * `held_ms` - How long the button has been held in milliseconds
+/// * `park_ms` - How long the button must be held to park
+///
+/// # Returns
+///
+/// `HoldAction::Park` once the hold has lasted at least `park_ms`,
+/// and `HoldAction::Read` before that.
+///
+/// # Examples
+///
+/// ```
+/// let held_ms = now_ms.wrapping_sub(pressed_ms);
+/// if hold_action(held_ms, 10_000) == HoldAction::Park {
+///     break;
+/// }
+/// ```
+pub const fn hold_action(held_ms: u32, park_ms: u32) -> HoldAction {
+    if held_ms >= park_ms {
+        HoldAction::Park
+    } else {
+        HoldAction::Read
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_press() {
+        let mut long_press = LongPress::new(3000);
+        assert!(!long_press.update(true, 0));
+        assert!(!long_press.update(true, 2999));
+        assert!(!long_press.update(false, 3000));
+        // Releasing restarts the timing for the next press
+        assert!(!long_press.update(true, 4000));
+        assert!(!long_press.update(true, 6999));
+    }
+
+    #[test]
+    fn test_long_press() {
+        let mut long_press = LongPress::new(3000);
+        assert!(!long_press.update(true, 1000));
+        assert!(long_press.update(true, 4000));
+        // Holding longer does not report the same press again
+        assert!(!long_press.update(true, 8000));
+        assert!(!long_press.update(false, 9000));
+        assert!(!long_press.update(true, 10000));
+        assert!(long_press.update(true, 13000));
+    }
+
+    #[test]
+    fn test_wraparound() {
+        let mut long_press = LongPress::new(3000);
+        assert!(!long_press.update(true, u32::MAX - 1000));
+        assert!(!long_press.update(true, 1000));
+        assert!(long_press.update(true, 1999));
+    }
+
+    #[test]
+    fn test_hold_action() {
+        assert_eq!(hold_action(0, 10_000), HoldAction::Read);
+        assert_eq!(hold_action(9_999, 10_000), HoldAction::Read);
+        assert_eq!(hold_action(10_000, 10_000), HoldAction::Park);
+        assert_eq!(hold_action(u32::MAX, 10_000), HoldAction::Park);
+    }
+
+    #[test]
+    fn test_double_press() {
+        let mut double_press = DoublePress::new(1500);
+        assert!(!double_press.press(0));
+        assert!(double_press.press(1500));
+
+        // A third quick press starts a new pair
+        assert!(!double_press.press(2000));
+        assert!(double_press.press(2500));
+
+        // Presses too far apart are separate single presses
+        assert!(!double_press.press(10_000));
+        assert!(!double_press.press(11_501));
+        assert!(double_press.press(12_000));
+    }
+
+    #[test]
+    fn test_double_press_during_hold() {
+        // A press which takes a reading, with readings 1000 ms apart, is
+        // released and pressed again at 700 ms, before the next reading
+        let sample_interval_ms = 1000;
+        let mut double_press = DoublePress::new(1500);
+        assert!(!double_press.press(0));
+        assert!(double_press.press(700));
+
+        // Seen only once the wait for the next reading and the 3000 ms
+        // binary display had ended, the same press would be too late
+        let mut double_press = DoublePress::new(1500);
+        assert!(!double_press.press(0));
+        assert!(!double_press.press(sample_interval_ms + 3000));
+    }
+
+    #[test]
+    fn test_double_press_wraparound() {
+        let mut double_press = DoublePress::new(1500);
+        assert!(!double_press.press(u32::MAX - 499));
+        assert!(double_press.press(1000));
+    }
+}
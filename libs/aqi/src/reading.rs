@@ -0,0 +1,187 @@
+//! Reading module
+//!
+//! This module provides a timestamped reading combining the sensor
+//! values with the AQI calculated from them, as a single unit for
+//! logging and analysis.
+
+use crate::text::TruncatingWriter;
+use crate::{calculate_aqi, get_aqi_category, Category};
+use core::fmt::Write;
+use pmsa003i::Pmsa003iData;
+
+/// The Reading struct holds a single timestamped sensor reading,
+/// along with the PM2.5 AQI and Category calculated from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reading {
+    /// Time of the reading in milliseconds, from a monotonic clock
+    pub timestamp_ms: u32,
+    /// The PM2.5 concentration in µg/m³, in environmental units
+    pub pm25: u16,
+    /// The PM10 concentration in µg/m³, in environmental units
+    pub pm10: u16,
+    /// The AQI calculated from `pm25`
+    pub aqi: u16,
+    /// The Category corresponding to `aqi`
+    pub category: Category,
+}
+
+impl Reading {
+    /// Create a Reading from parsed sensor data.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Parsed data from the sensor
+    /// * `timestamp_ms` - Time of the reading in milliseconds
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let data = pmsa003i::parse_data(&sensor_data)?;
+    /// let reading = Reading::from_data(&data, Instant::now().as_millis() as u32);
+    /// hprintln!("AQI: {} ({})", reading.aqi, reading.category);
+    /// ```
+    pub fn from_data(data: &Pmsa003iData, timestamp_ms: u32) -> Self {
+        let (_, pm25, pm10) = data.mass_concentrations();
+        let aqi = calculate_aqi(pm25 as f32);
+        Self {
+            timestamp_ms,
+            pm25,
+            pm10,
+            aqi,
+            category: get_aqi_category(aqi),
+        }
+    }
+}
+
+/// Whether a reading is recent enough to display as current, e.g. so
+/// that a display can gray out or warn about stale data. Timestamps are
+/// expected to come from a monotonic millisecond clock, and wraparound
+/// of the clock is handled, so a reading taken just before the clock
+/// wrapped is still fresh just after it. A timestamp later than `now_ms`
+/// is treated as a very old reading from before a wrap, so is stale.
+///
+/// # Arguments
+///
+/// * `reading_ts_ms` - Time of the reading in milliseconds
+/// * `now_ms` - The current time in milliseconds
+/// * `max_age_ms` - The oldest a reading may be while still fresh
+///
+/// # Returns
+///
+/// true if the reading is at most `max_age_ms` old.
+///
+/// # Examples
+///
+/// ```
+/// let now_ms = Instant::now().as_millis() as u32;
+/// if !is_fresh(reading.timestamp_ms, now_ms, 60_000) {
+///     hprintln!("Reading is more than a minute old");
+/// }
+/// ```
+pub fn is_fresh(reading_ts_ms: u32, now_ms: u32, max_age_ms: u32) -> bool {
+    now_ms.wrapping_sub(reading_ts_ms) <= max_age_ms
+}
+
+/// Formats how long ago a reading was taken, such as "3s ago", "2m ago"
+/// or "1h ago", to give context on a display. The age is shown in whole
+/// seconds under a minute, whole minutes under an hour, and whole hours
+/// beyond that, always rounding down.
+///
+/// # Arguments
+///
+/// * `age_ms` - Age of the reading in milliseconds
+/// * `buf` - Buffer to format the age into. 9 bytes fits any age, and
+///   the text is truncated if the buffer is shorter.
+///
+/// # Returns
+///
+/// The formatted age, borrowed from `buf`.
+///
+/// # Examples
+///
+/// ```
+/// let mut buf = [0u8; 9];
+/// assert_eq!(format_age(150_000, &mut buf), "2m ago");
+/// ```
+pub fn format_age(age_ms: u32, buf: &mut [u8]) -> &str {
+    let seconds = age_ms / 1000;
+    let (value, unit) = match seconds {
+        0..60 => (seconds, 's'),
+        60..3600 => (seconds / 60, 'm'),
+        _ => (seconds / 3600, 'h'),
+    };
+
+    let mut writer = TruncatingWriter::new(buf);
+    // Writing never fails, as the text is truncated instead
+    let _ = write!(writer, "{}{} ago", value, unit);
+    writer.into_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_data() {
+        let mut frame = [0u8; pmsa003i::TOTAL_REGISTERS];
+        frame[12..14].copy_from_slice(&41u16.to_be_bytes());
+        frame[14..16].copy_from_slice(&60u16.to_be_bytes());
+        let data = pmsa003i::parse_data(&frame).unwrap();
+
+        let reading = Reading::from_data(&data, 1_000);
+        assert_eq!(
+            reading,
+            Reading {
+                timestamp_ms: 1_000,
+                pm25: 41,
+                pm10: 60,
+                aqi: 115,
+                category: Category::UnhealthyForSensitiveGroups,
+            }
+        );
+        // The AQI and Category are consistent with the PM2.5 value
+        assert_eq!(reading.aqi, calculate_aqi(reading.pm25 as f32));
+        assert_eq!(reading.category, get_aqi_category(reading.aqi));
+    }
+
+    #[test]
+    fn test_is_fresh() {
+        assert!(is_fresh(1_000, 1_000, 0));
+        assert!(is_fresh(1_000, 5_000, 5_000));
+
+        // A reading exactly max_age_ms old is still fresh
+        assert!(is_fresh(1_000, 6_000, 5_000));
+        assert!(!is_fresh(1_000, 6_001, 5_000));
+
+        // The clock wrapping between the reading and now is handled
+        assert!(is_fresh(u32::MAX - 999, 1_000, 2_000));
+        assert!(is_fresh(u32::MAX, 0, 1));
+        assert!(!is_fresh(u32::MAX - 999, 1_001, 2_000));
+
+        // A reading from the future is treated as stale
+        assert!(!is_fresh(2_000, 1_000, 5_000));
+    }
+
+    #[test]
+    fn test_format_age() {
+        let mut buf = [0u8; 9];
+        let ages = [
+            (0, "0s ago"),
+            (3_500, "3s ago"),
+            (59_999, "59s ago"),
+            (60_000, "1m ago"),
+            (150_000, "2m ago"),
+            (3_599_999, "59m ago"),
+            (3_600_000, "1h ago"),
+            (26 * 3_600_000, "26h ago"),
+            (u32::MAX, "1193h ago"),
+        ];
+        for (age_ms, text) in ages {
+            assert_eq!(format_age(age_ms, &mut buf), text, "age {} ms", age_ms);
+        }
+
+        // A short buffer truncates the text
+        assert_eq!(format_age(3_000, &mut [0u8; 4]), "3s a");
+        assert_eq!(format_age(3_000, &mut []), "");
+    }
+}
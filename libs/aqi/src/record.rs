@@ -0,0 +1,126 @@
+//! Record module
+//!
+//! This module provides the fixed-size binary format used to persist
+//! readings, such as to a circular log in flash memory.
+//!
+//! Each record is 16 bytes, with multi-byte values in little endian:
+//!
+//! | Offset | Size | Field                                        |
+//! |--------|------|----------------------------------------------|
+//! | 0      | 1    | Marker, always `RECORD_MARKER`               |
+//! | 1      | 1    | Checksum, wrapping sum of bytes 2 to 15      |
+//! | 2      | 2    | Reserved, always 0                           |
+//! | 4      | 4    | Sequence number, increasing across reboots   |
+//! | 8      | 4    | Timestamp in milliseconds since boot         |
+//! | 12     | 2    | PM2.5 concentration in µg/m³                 |
+//! | 14     | 2    | AQI                                          |
+//!
+//! Erased flash reads as 0xFF, which never matches the marker, so empty
+//! slots are distinguished from records. The checksum catches records
+//! which were only partially written, e.g. due to a power loss.
+
+/// Size in bytes of an encoded record.
+pub const RECORD_SIZE: usize = 16;
+
+/// First byte of every encoded record.
+pub const RECORD_MARKER: u8 = 0xA5;
+
+/// The LogRecord struct holds a single persisted reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogRecord {
+    pub sequence: u32,
+    pub timestamp_ms: u32,
+    pub pm25: u16,
+    pub aqi: u16,
+}
+
+impl LogRecord {
+    /// Encode the record into its binary format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let bytes = record.encode();
+    /// flash.write(offset, &bytes)?;
+    /// ```
+    pub fn encode(&self) -> [u8; RECORD_SIZE] {
+        let mut bytes = [0u8; RECORD_SIZE];
+        bytes[0] = RECORD_MARKER;
+        bytes[4..8].copy_from_slice(&self.sequence.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.timestamp_ms.to_le_bytes());
+        bytes[12..14].copy_from_slice(&self.pm25.to_le_bytes());
+        bytes[14..16].copy_from_slice(&self.aqi.to_le_bytes());
+        bytes[1] = checksum(&bytes);
+        bytes
+    }
+
+    /// Decode a record from its binary format.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - An encoded record
+    ///
+    /// # Returns
+    ///
+    /// The LogRecord, or None if the bytes are erased or corrupt.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut bytes = [0u8; RECORD_SIZE];
+    /// flash.read(offset, &mut bytes)?;
+    /// if let Some(record) = LogRecord::decode(&bytes) {
+    ///     hprintln!("PM2.5: {}, AQI: {}", record.pm25, record.aqi);
+    /// }
+    /// ```
+    pub fn decode(bytes: &[u8; RECORD_SIZE]) -> Option<Self> {
+        if bytes[0] != RECORD_MARKER || bytes[1] != checksum(bytes) || bytes[2..4] != [0, 0] {
+            return None;
+        }
+        Some(Self {
+            sequence: u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+            timestamp_ms: u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]),
+            pm25: u16::from_le_bytes([bytes[12], bytes[13]]),
+            aqi: u16::from_le_bytes([bytes[14], bytes[15]]),
+        })
+    }
+}
+
+/// Wrapping sum of every byte following the checksum.
+fn checksum(bytes: &[u8; RECORD_SIZE]) -> u8 {
+    bytes[2..]
+        .iter()
+        .fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECORD: LogRecord = LogRecord {
+        sequence: 0x0102_0304,
+        timestamp_ms: 3_600_000,
+        pm25: 41,
+        aqi: 115,
+    };
+
+    #[test]
+    fn test_round_trip() {
+        let bytes = RECORD.encode();
+        assert_eq!(bytes[0], RECORD_MARKER);
+        assert_eq!(bytes[4..8], [0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(LogRecord::decode(&bytes), Some(RECORD));
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid() {
+        // Erased flash
+        assert_eq!(LogRecord::decode(&[0xFF; RECORD_SIZE]), None);
+
+        // A partially written record fails the checksum
+        let mut bytes = RECORD.encode();
+        bytes[14] = 0xFF;
+        bytes[15] = 0xFF;
+        assert_eq!(LogRecord::decode(&bytes), None);
+    }
+}
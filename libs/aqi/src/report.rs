@@ -0,0 +1,164 @@
+//! Report module
+//!
+//! This module provides a full AirNow-style report of the air quality
+//! at a location, combining the AQI of each pollutant measured, for
+//! gateways passing readings on to a display or API. It requires the
+//! `std` feature, and with the `serde` feature the report can be
+//! serialized, e.g. to JSON.
+
+use crate::{calculate_aqi, calculate_aqi_pm10, get_aqi_category, Category};
+use core::fmt;
+use pmsa003i::Pmsa003iData;
+use std::vec::Vec;
+
+/// Pollutant enum provides the pollutants measured by the PMSA003I
+/// which have an AQI. The Display implementation produces the usual
+/// name of the pollutant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Pollutant {
+    /// Fine particulate matter, 2.5 µm and smaller
+    Pm25,
+    /// Coarse particulate matter, 10 µm and smaller
+    Pm10,
+}
+
+impl fmt::Display for Pollutant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Pollutant::Pm25 => "PM2.5",
+            Pollutant::Pm10 => "PM10",
+        })
+    }
+}
+
+/// The SubIndex struct holds the AQI of a single pollutant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SubIndex {
+    /// The pollutant measured
+    pub pollutant: Pollutant,
+    /// The concentration in µg/m³, in environmental units
+    pub concentration: u16,
+    /// The AQI calculated from `concentration`
+    pub aqi: u16,
+}
+
+/// The LocationReport struct holds the air quality at a location as
+/// AirNow reports it: the overall AQI, which is that of the worst
+/// pollutant, along with the AQI of each pollutant measured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LocationReport {
+    /// The overall AQI, the highest of the sub-indices
+    pub aqi: u16,
+    /// The pollutant with the highest AQI
+    pub dominant_pollutant: Pollutant,
+    /// The Category corresponding to `aqi`
+    pub category: Category,
+    /// The AQI of each pollutant measured
+    pub sub_indices: Vec<SubIndex>,
+}
+
+impl LocationReport {
+    /// Create a LocationReport from parsed sensor data. If pollutants
+    /// share the highest AQI, PM2.5 is reported as dominant.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Parsed data from the sensor
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let data = pmsa003i::parse_data(&sensor_data)?;
+    /// let report = LocationReport::from_data(&data);
+    /// println!("{}", serde_json::to_string(&report)?);
+    /// ```
+    pub fn from_data(data: &Pmsa003iData) -> Self {
+        let (_, pm25, pm10) = data.mass_concentrations();
+        let sub_indices = std::vec![
+            SubIndex {
+                pollutant: Pollutant::Pm25,
+                concentration: pm25,
+                aqi: calculate_aqi(pm25 as f32),
+            },
+            SubIndex {
+                pollutant: Pollutant::Pm10,
+                concentration: pm10,
+                aqi: calculate_aqi_pm10(pm10 as f32),
+            },
+        ];
+
+        let mut dominant = sub_indices[0];
+        for sub_index in &sub_indices[1..] {
+            if sub_index.aqi > dominant.aqi {
+                dominant = *sub_index;
+            }
+        }
+
+        Self {
+            aqi: dominant.aqi,
+            dominant_pollutant: dominant.pollutant,
+            category: get_aqi_category(dominant.aqi),
+            sub_indices,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(pm25: u16, pm10: u16) -> Pmsa003iData {
+        let mut frame = [0u8; pmsa003i::TOTAL_REGISTERS];
+        frame[12..14].copy_from_slice(&pm25.to_be_bytes());
+        frame[14..16].copy_from_slice(&pm10.to_be_bytes());
+        pmsa003i::parse_data(&frame).unwrap()
+    }
+
+    #[test]
+    fn test_from_data() {
+        let report = LocationReport::from_data(&data(41, 60));
+        assert_eq!(
+            report,
+            LocationReport {
+                aqi: 115,
+                dominant_pollutant: Pollutant::Pm25,
+                category: Category::UnhealthyForSensitiveGroups,
+                sub_indices: std::vec![
+                    SubIndex {
+                        pollutant: Pollutant::Pm25,
+                        concentration: 41,
+                        aqi: 115,
+                    },
+                    SubIndex {
+                        pollutant: Pollutant::Pm10,
+                        concentration: 60,
+                        aqi: 53,
+                    },
+                ],
+            }
+        );
+
+        // Coarse dust can make PM10 the dominant pollutant
+        let report = LocationReport::from_data(&data(5, 200));
+        assert_eq!(report.dominant_pollutant, Pollutant::Pm10);
+        assert_eq!(report.aqi, calculate_aqi_pm10(200.0));
+        assert_eq!(report.category, Category::UnhealthyForSensitiveGroups);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let report = LocationReport::from_data(&data(41, 60));
+        assert_eq!(
+            serde_json::to_string(&report).unwrap(),
+            "{\"aqi\":115,\"dominant_pollutant\":\"Pm25\",\
+             \"category\":\"UnhealthyForSensitiveGroups\",\
+             \"sub_indices\":[\
+             {\"pollutant\":\"Pm25\",\"concentration\":41,\"aqi\":115},\
+             {\"pollutant\":\"Pm10\",\"concentration\":60,\"aqi\":53}]}"
+        );
+    }
+}
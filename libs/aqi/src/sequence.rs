@@ -0,0 +1,68 @@
+//! Sequence module
+//!
+//! This module provides monotonic sequence numbers for tagging readings,
+//! so that readings can be ordered and dropped readings detected
+//! downstream of a lossy link.
+
+/// The SequenceCounter struct hands out incrementing sequence numbers.
+/// After `u32::MAX`, the counter wraps around to 0, so consumers should
+/// compare sequence numbers using wrapping arithmetic.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceCounter {
+    next: u32,
+}
+
+impl SequenceCounter {
+    /// Create a new SequenceCounter starting at 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sequence = SequenceCounter::new();
+    /// ```
+    pub const fn new() -> Self {
+        Self { next: 0 }
+    }
+
+    /// Get the sequence number for a new reading and advance the counter.
+    ///
+    /// # Returns
+    ///
+    /// The sequence number to attach to the reading.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut sequence = SequenceCounter::new();
+    /// assert_eq!(sequence.next(), 0);
+    /// assert_eq!(sequence.next(), 1);
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> u32 {
+        let current = self.next;
+        self.next = self.next.wrapping_add(1);
+        current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_increment() {
+        let mut sequence = SequenceCounter::new();
+        assert_eq!(sequence.next(), 0);
+        assert_eq!(sequence.next(), 1);
+        assert_eq!(sequence.next(), 2);
+    }
+
+    #[test]
+    fn test_wrap() {
+        let mut sequence = SequenceCounter { next: u32::MAX - 1 };
+        assert_eq!(sequence.next(), u32::MAX - 1);
+        assert_eq!(sequence.next(), u32::MAX);
+        assert_eq!(sequence.next(), 0);
+        assert_eq!(sequence.next(), 1);
+    }
+}
@@ -0,0 +1,130 @@
+//! Session statistics module
+//!
+//! This module provides running statistics over the AQI readings
+//! taken during a monitoring session, without storing the readings.
+
+/// The AqiStats struct tracks the minimum, maximum, and average AQI
+/// across all readings since it was created or last reset.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AqiStats {
+    count: u32,
+    sum: u64,
+    min: u16,
+    max: u16,
+}
+
+impl AqiStats {
+    /// Create a new AqiStats with no readings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut stats = AqiStats::new();
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            count: 0,
+            sum: 0,
+            min: 0,
+            max: 0,
+        }
+    }
+
+    /// Add an AQI reading to the statistics.
+    ///
+    /// # Arguments
+    ///
+    /// * `aqi` - The calculated AQI
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let report = monitor.record(pm25_concentration, &mut output);
+    /// stats.update(report.aqi);
+    /// ```
+    pub fn update(&mut self, aqi: u16) {
+        if self.count == 0 {
+            self.min = aqi;
+            self.max = aqi;
+        } else {
+            self.min = self.min.min(aqi);
+            self.max = self.max.max(aqi);
+        }
+        self.count = self.count.saturating_add(1);
+        self.sum += aqi as u64;
+    }
+
+    /// Number of readings since the statistics were last reset.
+    pub fn len(&self) -> u32 {
+        self.count
+    }
+
+    /// Whether there have been no readings since the statistics were last reset.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Lowest AQI since the statistics were last reset, if any.
+    pub fn min(&self) -> Option<u16> {
+        (!self.is_empty()).then_some(self.min)
+    }
+
+    /// Highest AQI since the statistics were last reset, if any.
+    pub fn max(&self) -> Option<u16> {
+        (!self.is_empty()).then_some(self.max)
+    }
+
+    /// Average AQI since the statistics were last reset, rounded to
+    /// the nearest whole number, if any.
+    pub fn average(&self) -> Option<u16> {
+        if self.is_empty() {
+            return None;
+        }
+        let count = self.count as u64;
+        Some(((self.sum + count / 2) / count) as u16)
+    }
+
+    /// Clear all readings, starting a new session.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut stats = AqiStats::new();
+        assert!(stats.is_empty());
+        assert_eq!(stats.min(), None);
+        assert_eq!(stats.max(), None);
+        assert_eq!(stats.average(), None);
+
+        for aqi in [115, 96, 97, 150] {
+            stats.update(aqi);
+        }
+        assert_eq!(stats.len(), 4);
+        assert_eq!(stats.min(), Some(96));
+        assert_eq!(stats.max(), Some(150));
+        // 458 / 4 = 114.5, rounded up
+        assert_eq!(stats.average(), Some(115));
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut stats = AqiStats::new();
+        stats.update(300);
+        stats.update(20);
+        stats.reset();
+        assert_eq!(stats, AqiStats::new());
+        assert_eq!(stats.average(), None);
+
+        // Readings after a reset are not affected by earlier ones
+        stats.update(50);
+        assert_eq!(stats.min(), Some(50));
+        assert_eq!(stats.max(), Some(50));
+        assert_eq!(stats.average(), Some(50));
+    }
+}
@@ -0,0 +1,111 @@
+//! Stuck sensor detection module
+//!
+//! This module provides detection of a frozen sensor, which may
+//! continue to respond on the bus while reporting the same value
+//! for every reading.
+
+/// The StuckDetector struct tracks the most recent PM2.5 reading
+/// and how many consecutive times it has been seen. Once the same
+/// value has been read more than `threshold` times in a row, the
+/// sensor is flagged as potentially stuck.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StuckDetector {
+    last: Option<u16>,
+    repeats: u16,
+    threshold: u16,
+}
+
+impl StuckDetector {
+    /// Create a new StuckDetector.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - Number of consecutive identical readings
+    ///   allowed before the sensor is considered stuck
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut stuck_detector = StuckDetector::new(20);
+    /// ```
+    pub const fn new(threshold: u16) -> Self {
+        Self {
+            last: None,
+            repeats: 0,
+            threshold,
+        }
+    }
+
+    /// Record a new reading and check whether the sensor appears stuck.
+    ///
+    /// # Arguments
+    ///
+    /// * `pm25` - The PM 2.5 value from the sensor
+    ///
+    /// # Returns
+    ///
+    /// true if the reading has repeated more than `threshold` times in a row.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if stuck_detector.update(data.pm2_5_env) {
+    ///     hprintln!("Warning: sensor may be frozen");
+    /// }
+    /// ```
+    pub fn update(&mut self, pm25: u16) -> bool {
+        if self.last == Some(pm25) {
+            self.repeats = self.repeats.saturating_add(1);
+        } else {
+            self.last = Some(pm25);
+            self.repeats = 1;
+        }
+        self.is_stuck()
+    }
+
+    /// Whether the current reading has repeated more than `threshold` times.
+    pub fn is_stuck(&self) -> bool {
+        self.repeats > self.threshold
+    }
+
+    /// Clear the tracked reading, e.g. after power-cycling the sensor.
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.repeats = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varying_readings_never_flag() {
+        let mut detector = StuckDetector::new(3);
+        for pm25 in [10, 11, 10, 11, 12, 10, 11, 12, 13, 10] {
+            assert!(!detector.update(pm25));
+        }
+    }
+
+    #[test]
+    fn test_identical_readings_flag() {
+        let mut detector = StuckDetector::new(3);
+        // Exactly K identical readings are not yet stuck
+        assert!(!detector.update(41));
+        assert!(!detector.update(41));
+        assert!(!detector.update(41));
+        assert!(detector.update(41));
+        assert!(detector.update(41));
+
+        // A new value clears the flag
+        assert!(!detector.update(42));
+        assert!(!detector.is_stuck());
+
+        detector.update(42);
+        detector.update(42);
+        assert!(detector.update(42));
+        detector.reset();
+        assert!(!detector.is_stuck());
+        assert!(!detector.update(42));
+    }
+}
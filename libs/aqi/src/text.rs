@@ -0,0 +1,83 @@
+//! Text module
+//!
+//! This module provides a writer for formatting short text, such as
+//! status sentences and bars for character displays, into a caller's
+//! byte buffer without allocating.
+
+use core::fmt::{self, Write};
+
+/// Struct TruncatingWriter formats into a byte buffer, silently
+/// truncating once the buffer is full rather than failing, so that
+/// text always fits the display it is meant for.
+pub struct TruncatingWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> TruncatingWriter<'a> {
+    /// Creates a TruncatingWriter which writes from the start of `buf`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut buf = [0u8; 8];
+    /// let mut writer = TruncatingWriter::new(&mut buf);
+    /// let _ = write!(writer, "AQI {}", 42);
+    /// assert_eq!(writer.into_str(), "AQI 42");
+    /// ```
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    /// Finishes writing, returning the text written so far.
+    ///
+    /// # Returns
+    ///
+    /// The written text, borrowed from the buffer. If truncation split
+    /// a multi-byte character, the partial character is left out.
+    pub fn into_str(self) -> &'a str {
+        let text = &self.buf[..self.len];
+        match core::str::from_utf8(text) {
+            Ok(text) => text,
+            Err(e) => core::str::from_utf8(&text[..e.valid_up_to()]).unwrap_or_default(),
+        }
+    }
+}
+
+impl Write for TruncatingWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (slot, &byte) in self.buf[self.len..].iter_mut().zip(s.as_bytes()) {
+            *slot = byte;
+            self.len += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncating_writer() {
+        let mut buf = [0u8; 8];
+        let mut writer = TruncatingWriter::new(&mut buf);
+        write!(writer, "AQI {}", 42).unwrap();
+        assert_eq!(writer.into_str(), "AQI 42");
+
+        // Text past the end of the buffer is dropped
+        let mut writer = TruncatingWriter::new(&mut buf);
+        write!(writer, "AQI {} and rising", 142).unwrap();
+        assert_eq!(writer.into_str(), "AQI 142 ");
+
+        // A multi-byte character cut short is left out entirely
+        let mut short = [0u8; 4];
+        let mut writer = TruncatingWriter::new(&mut short);
+        write!(writer, "42 µg").unwrap();
+        assert_eq!(writer.into_str(), "42 ");
+
+        let mut writer = TruncatingWriter::new(&mut []);
+        write!(writer, "AQI").unwrap();
+        assert_eq!(writer.into_str(), "");
+    }
+}
@@ -0,0 +1,374 @@
+//! Trend module
+//!
+//! This module provides the direction in which air quality is heading
+//! and how quickly it is changing, and a short consumer-facing sentence
+//! combining the direction with the current AQI category, such as
+//! "Good and improving.", for friendly displays.
+
+use crate::get_aqi_category;
+use crate::text::TruncatingWriter;
+use core::cmp::Ordering;
+use core::fmt::{self, Write};
+
+/// Trend enum provides the direction in which air quality is heading.
+/// A falling AQI means the air is improving. The Display implementation
+/// produces a lowercase name for the trend.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Trend {
+    /// The AQI is falling
+    Improving,
+    /// The AQI is holding roughly steady
+    #[default]
+    Steady,
+    /// The AQI is rising
+    Worsening,
+}
+
+impl Trend {
+    /// Classifies a change in AQI as a Trend, treating changes within
+    /// `threshold` as steady so that sensor noise is not reported as
+    /// a trend.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta` - The change in AQI, such as from `aqi_delta`
+    /// * `threshold` - The largest change in AQI considered steady
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(Trend::from_delta(-12, 5), Trend::Improving);
+    /// assert_eq!(Trend::from_delta(3, 5), Trend::Steady);
+    /// ```
+    pub fn from_delta(delta: i32, threshold: u16) -> Self {
+        if delta < -(threshold as i32) {
+            Trend::Improving
+        } else if delta > threshold as i32 {
+            Trend::Worsening
+        } else {
+            Trend::Steady
+        }
+    }
+
+    /// Classifies a change between two AQI values as a Trend. A change
+    /// into another category is always reported in its direction, even
+    /// when it is within `threshold`, so that a sentence never calls
+    /// the air steady as it crosses into a new category. Changes within
+    /// a category are classified as in `from_delta`.
+    ///
+    /// # Arguments
+    ///
+    /// * `prev_aqi` - The earlier AQI
+    /// * `aqi` - The current AQI
+    /// * `threshold` - The largest change in AQI considered steady
+    ///   within a category
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(Trend::from_change(49, 52, 5), Trend::Worsening);
+    /// assert_eq!(Trend::from_change(60, 63, 5), Trend::Steady);
+    /// ```
+    pub fn from_change(prev_aqi: u16, aqi: u16, threshold: u16) -> Self {
+        match get_aqi_category(aqi).cmp(&get_aqi_category(prev_aqi)) {
+            Ordering::Greater => Trend::Worsening,
+            Ordering::Less => Trend::Improving,
+            Ordering::Equal => Self::from_delta(aqi as i32 - prev_aqi as i32, threshold),
+        }
+    }
+}
+
+impl fmt::Display for Trend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Trend::Improving => "improving",
+            Trend::Steady => "steady",
+            Trend::Worsening => "worsening",
+        })
+    }
+}
+
+/// Slope in AQI per minute at and above which a change is gradual
+/// rather than stable.
+pub const GRADUAL_AQI_PER_MIN: u16 = 1;
+/// Slope in AQI per minute at and above which a change is rapid, as
+/// when wildfire smoke arrives.
+pub const RAPID_AQI_PER_MIN: u16 = 5;
+
+/// ChangeRate enum provides how quickly the AQI is changing, in
+/// either direction. The Display implementation produces a lowercase
+/// name for the rate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChangeRate {
+    /// Changing by less than `GRADUAL_AQI_PER_MIN`
+    #[default]
+    Stable,
+    /// Changing by at least `GRADUAL_AQI_PER_MIN`, but less than
+    /// `RAPID_AQI_PER_MIN`
+    Gradual,
+    /// Changing by at least `RAPID_AQI_PER_MIN`
+    Rapid,
+}
+
+impl fmt::Display for ChangeRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChangeRate::Stable => "stable",
+            ChangeRate::Gradual => "gradual",
+            ChangeRate::Rapid => "rapid",
+        })
+    }
+}
+
+/// Classifies how quickly the AQI changed between two timestamped
+/// readings, by its slope in AQI per minute. Timestamps are expected
+/// to come from a monotonic millisecond clock, and wraparound of the
+/// clock is handled. Combine with `Trend` to tell rapid deterioration
+/// from rapid improvement.
+///
+/// # Arguments
+///
+/// * `prev_ts` - Time of the earlier reading in milliseconds
+/// * `prev_aqi` - The AQI of the earlier reading
+/// * `now_ts` - Time of the later reading in milliseconds
+/// * `now_aqi` - The AQI of the later reading
+///
+/// # Returns
+///
+/// The ChangeRate of the slope. If no time passed between the
+/// readings, no slope can be calculated, so this is Stable.
+///
+/// # Examples
+///
+/// ```
+/// let rate = change_rate(last_ms, last_aqi, now_ms, aqi);
+/// if rate == ChangeRate::Rapid && aqi > last_aqi {
+///     hprintln!("Warning: AQI rising rapidly");
+/// }
+/// ```
+pub fn change_rate(prev_ts: u32, prev_aqi: u16, now_ts: u32, now_aqi: u16) -> ChangeRate {
+    let elapsed_ms = now_ts.wrapping_sub(prev_ts) as u64;
+    if elapsed_ms == 0 {
+        return ChangeRate::Stable;
+    }
+
+    // Compare slopes without dividing, as change * 1 minute against
+    // threshold * elapsed time
+    let change = prev_aqi.abs_diff(now_aqi) as u64 * 60_000;
+    if change >= RAPID_AQI_PER_MIN as u64 * elapsed_ms {
+        ChangeRate::Rapid
+    } else if change >= GRADUAL_AQI_PER_MIN as u64 * elapsed_ms {
+        ChangeRate::Gradual
+    } else {
+        ChangeRate::Stable
+    }
+}
+
+/// Struct RateWindow measures how quickly the AQI changes across a
+/// window of at least `window_ms`, rather than between consecutive
+/// readings. Readings close together can differ by only a little, such
+/// as when one crosses a category boundary, which would otherwise give
+/// a steep slope from a small change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateWindow {
+    window_ms: u32,
+    start: Option<(u32, u16)>,
+}
+
+impl RateWindow {
+    /// Create a new RateWindow, which starts with the first reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_ms` - The shortest time the rate is measured over
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut rate_window = RateWindow::new(60_000);
+    /// ```
+    pub const fn new(window_ms: u32) -> Self {
+        Self {
+            window_ms,
+            start: None,
+        }
+    }
+
+    /// Records a timestamped reading. Once at least `window_ms` has
+    /// passed since the start of the window, the rate is classified as
+    /// by `change_rate`, and a new window starts from this reading.
+    /// Timestamps are expected to come from a monotonic millisecond
+    /// clock, and wraparound of the clock is handled.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp_ms` - Time of the reading in milliseconds
+    /// * `aqi` - The AQI of the reading
+    ///
+    /// # Returns
+    ///
+    /// The AQI at the start of the window and the ChangeRate across it,
+    /// or None while the window is still filling.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if let Some((from, rate)) = rate_window.update(now_ms, aqi) {
+    ///     if rate == ChangeRate::Rapid && aqi > from {
+    ///         hprintln!("Warning: AQI rising rapidly");
+    ///     }
+    /// }
+    /// ```
+    pub fn update(&mut self, timestamp_ms: u32, aqi: u16) -> Option<(u16, ChangeRate)> {
+        let Some((start_ms, start_aqi)) = self.start else {
+            self.start = Some((timestamp_ms, aqi));
+            return None;
+        };
+        if timestamp_ms.wrapping_sub(start_ms) < self.window_ms {
+            return None;
+        }
+
+        self.start = Some((timestamp_ms, aqi));
+        Some((
+            start_aqi,
+            change_rate(start_ms, start_aqi, timestamp_ms, aqi),
+        ))
+    }
+}
+
+/// Formats a short sentence combining the category of an AQI with its
+/// trend, such as "Good and improving." or "Unhealthy and worsening.",
+/// for consumer-facing displays.
+///
+/// # Arguments
+///
+/// * `aqi` - The calculated AQI
+/// * `trend` - The direction in which the AQI is heading
+/// * `buf` - Buffer to format the sentence into. 45 bytes fits any
+///   sentence, and the text is truncated if the buffer is shorter.
+///
+/// # Returns
+///
+/// The formatted sentence, borrowed from `buf`.
+///
+/// # Examples
+///
+/// ```
+/// let mut buf = [0u8; 45];
+/// let trend = Trend::from_change(last_aqi, report.aqi, 5);
+/// hprintln!("{}", status_sentence(report.aqi, trend, &mut buf));
+/// ```
+pub fn status_sentence(aqi: u16, trend: Trend, buf: &mut [u8]) -> &str {
+    let mut writer = TruncatingWriter::new(buf);
+    // Writing never fails, as the text is truncated instead
+    let _ = write!(writer, "{} and {}.", get_aqi_category(aqi), trend);
+    writer.into_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_delta() {
+        assert_eq!(Trend::from_delta(-12, 5), Trend::Improving);
+        assert_eq!(Trend::from_delta(-5, 5), Trend::Steady);
+        assert_eq!(Trend::from_delta(0, 5), Trend::Steady);
+        assert_eq!(Trend::from_delta(5, 5), Trend::Steady);
+        assert_eq!(Trend::from_delta(6, 5), Trend::Worsening);
+    }
+
+    #[test]
+    fn test_from_change() {
+        // Small changes across a category boundary are not steady
+        assert_eq!(Trend::from_change(49, 52, 5), Trend::Worsening);
+        assert_eq!(Trend::from_change(101, 100, 5), Trend::Improving);
+        assert_eq!(Trend::from_change(150, 151, 5), Trend::Worsening);
+
+        // Within a category, the threshold applies as in from_delta
+        assert_eq!(Trend::from_change(60, 63, 5), Trend::Steady);
+        assert_eq!(Trend::from_change(60, 66, 5), Trend::Worsening);
+        assert_eq!(Trend::from_change(66, 60, 5), Trend::Improving);
+        assert_eq!(Trend::from_change(75, 75, 5), Trend::Steady);
+    }
+
+    #[test]
+    fn test_change_rate() {
+        // 50 AQI over 5 minutes, as when smoke arrives
+        assert_eq!(change_rate(0, 40, 300_000, 90), ChangeRate::Rapid);
+        // 20 AQI over 10 minutes
+        assert_eq!(change_rate(0, 60, 600_000, 80), ChangeRate::Gradual);
+        // 5 AQI over 10 minutes
+        assert_eq!(change_rate(0, 60, 600_000, 65), ChangeRate::Stable);
+
+        // The rate applies in either direction
+        assert_eq!(change_rate(0, 90, 300_000, 40), ChangeRate::Rapid);
+
+        // Thresholds are inclusive
+        assert_eq!(change_rate(0, 40, 60_000, 45), ChangeRate::Rapid);
+        assert_eq!(change_rate(0, 40, 60_000, 44), ChangeRate::Gradual);
+        assert_eq!(change_rate(0, 40, 60_000, 41), ChangeRate::Gradual);
+
+        // Wraparound of the clock
+        assert_eq!(
+            change_rate(u32::MAX - 29_999, 40, 30_000, 90),
+            ChangeRate::Rapid
+        );
+
+        // No time passed, so no slope can be calculated
+        assert_eq!(change_rate(1000, 40, 1000, 90), ChangeRate::Stable);
+        assert_eq!(change_rate(1000, 40, 1000, 40), ChangeRate::Stable);
+    }
+
+    #[test]
+    fn test_rate_window() {
+        let mut window = RateWindow::new(60_000);
+        assert_eq!(window.update(0, 50), None);
+
+        // Crossing a category a second later is not yet a rate
+        assert_eq!(window.update(1_000, 51), None);
+        assert_eq!(window.update(59_999, 52), None);
+
+        // Once the window has passed, the rate is across all of it
+        assert_eq!(window.update(60_000, 53), Some((50, ChangeRate::Gradual)));
+
+        // A new window starts from the last reading
+        assert_eq!(window.update(61_000, 60), None);
+        assert_eq!(window.update(120_000, 60), Some((53, ChangeRate::Rapid)));
+        assert_eq!(window.update(180_000, 60), Some((60, ChangeRate::Stable)));
+
+        // Wraparound of the clock
+        let mut window = RateWindow::new(60_000);
+        assert_eq!(window.update(u32::MAX - 29_999, 40), None);
+        assert_eq!(window.update(30_000, 90), Some((40, ChangeRate::Rapid)));
+    }
+
+    #[test]
+    fn test_status_sentence() {
+        let mut buf = [0u8; 45];
+        assert_eq!(
+            status_sentence(42, Trend::Improving, &mut buf),
+            "Good and improving."
+        );
+        assert_eq!(
+            status_sentence(75, Trend::Steady, &mut buf),
+            "Moderate and steady."
+        );
+        assert_eq!(
+            status_sentence(155, Trend::Worsening, &mut buf),
+            "Unhealthy and worsening."
+        );
+        // The longest category and trend fit the documented length
+        assert_eq!(
+            status_sentence(120, Trend::Worsening, &mut buf).len(),
+            buf.len()
+        );
+
+        // Sentences are truncated to fit a shorter buffer
+        let mut short = [0u8; 8];
+        assert_eq!(
+            status_sentence(42, Trend::Improving, &mut short),
+            "Good and"
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! Warm-up module
+//!
+//! This module provides the spinner shown while the sensor warms up
+//! after power-on, before an initial reading is taken. The warm-up
+//! itself is timed by `pmsa003i::wait_for_warmup`.
+
+use crate::LedRole;
+
+/// The LEDs lit in turn by the measuring spinner, clockwise around the
+/// compass starting from the north LED.
+pub const MEASURING_SPINNER: [LedRole; 8] = LedRole::CLOCKWISE;
+
+/// Provides the LED lit by the measuring spinner at a given time,
+/// advancing one LED clockwise every `step_ms`.
+///
+/// # Arguments
+///
+/// * `elapsed_ms` - Time since the spinner started in milliseconds
+/// * `step_ms` - Time each LED stays lit, treated as 1 if zero
+///
+/// # Examples
+///
+/// ```
+/// let elapsed_ms = Instant::now().as_millis() as u32;
+/// led_controller.set_roles(&[measuring_led(elapsed_ms, 125)]);
+/// ```
+pub const fn measuring_led(elapsed_ms: u32, step_ms: u32) -> LedRole {
+    let step = elapsed_ms / if step_ms == 0 { 1 } else { step_ms };
+    MEASURING_SPINNER[step as usize % MEASURING_SPINNER.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measuring_led() {
+        assert_eq!(measuring_led(0, 125), LedRole::Red1);
+        assert_eq!(measuring_led(124, 125), LedRole::Red1);
+        assert_eq!(measuring_led(125, 125), LedRole::Orange1);
+        assert_eq!(measuring_led(7 * 125, 125), LedRole::Blue1);
+
+        // The spinner wraps around to the start after a full turn
+        assert_eq!(measuring_led(8 * 125, 125), LedRole::Red1);
+
+        // Each LED is visited exactly once per turn
+        let mask = (0..8).fold(0, |mask, step| mask | measuring_led(step * 125, 125).mask());
+        assert_eq!(mask, 0xFF);
+
+        assert_eq!(measuring_led(3, 0), LedRole::Blue2);
+    }
+}
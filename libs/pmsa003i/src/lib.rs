@@ -0,0 +1,1894 @@
+//! PMSA003I library
+//!
+//! This library provides supporting functionality for data retrieval
+//! and validation from the PMSA003I sensor. Data is read over any I2C
+//! bus implementing the `embedded-hal-async` I2C trait, allowing the
+//! same logic to run against real hardware or a mock bus in tests.
+
+#![no_std]
+
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock;
+
+use core::fmt;
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::i2c::{Error as _, ErrorKind, I2c};
+
+pub const SENSOR_I2C_ADDR: u8 = 0x12;
+pub const TOTAL_REGISTERS: usize = 32;
+const EXPECTED_HEADER: [u8; 2] = [0x42, 0x4D];
+// The checksum occupies the final two registers, and covers every register before it
+const CHECKSUM_OFFSET: usize = TOTAL_REGISTERS - 2;
+// The sensor version occupies the register following the particle counts
+const VERSION_OFFSET: usize = 28;
+// The error code occupies the register following the version
+const ERROR_CODE_OFFSET: usize = 29;
+
+/// How long the RESET pin is held low to reset the sensor.
+pub const RESET_PULSE_MS: u32 = 10;
+/// Delay between attempts to read a valid frame after a reset.
+pub const RESET_POLL_INTERVAL_MS: u32 = 250;
+/// Number of attempts to read a valid frame after a reset before giving up.
+pub const RESET_MAX_POLLS: u8 = 20;
+
+/// Number of attempts `fetch_data_retry` makes before giving up.
+pub const FETCH_MAX_ATTEMPTS: u8 = 3;
+/// Delay between attempts made by `fetch_data_retry`.
+pub const FETCH_RETRY_INTERVAL_MS: u32 = 100;
+
+/// Delay between reads made by `wait_for_new_frame`. The sensor
+/// updates its frame roughly once a second.
+pub const NEW_FRAME_POLL_INTERVAL_MS: u32 = 200;
+/// Number of reads `wait_for_new_frame` makes before giving up, enough
+/// to span two of the sensor's update intervals.
+pub const NEW_FRAME_MAX_POLLS: u8 = 10;
+
+/// Time after power-on before readings are stable. The datasheet
+/// recommends letting the fan run for at least 30 seconds.
+pub const WARMUP_MS: u32 = 30_000;
+
+/// Number of SCL pulses clocked out to recover a stuck bus. Nine is
+/// enough for a device holding SDA low mid-byte to finish the byte
+/// and the acknowledge bit.
+pub const RECOVERY_CLOCK_PULSES: u8 = 9;
+/// Half of the SCL period used during bus recovery, giving roughly
+/// the 100kHz used for normal transfers.
+pub const RECOVERY_HALF_PERIOD_US: u32 = 5;
+
+/// The Pmsa003iData struct holds all air quality measurements
+/// performed by the PMSA003I sensor. Most values are not relevant
+/// for the current application.
+#[derive(Debug, Default)]
+pub struct Pmsa003iData {
+    // CF is "Calibration Factory", and generally not useful for our needs.
+    _pm1_0_standard: u16, // PM1.0 concentration unit μ g/m3（CF=1，standard particle）
+    pm2_5_standard: u16,  // PM2.5 concentration unit μ g/m3（CF=1，standard particle）
+    _pm10_standard: u16,  // PM10 concentration unit μ g/m3（CF=1，standard particle）
+
+    // The environmental units take into account factors like ambient pressure.
+    // This is typically what is used in an AQI report or forecast.
+    pm1_0_env: u16,     // PM1.0 concentration unit μ g/m3（environmental units）
+    pub pm2_5_env: u16, // PM2.5 concentration unit μ g/m3（environmental units）
+    pm10_env: u16,      // PM10 concentration unit μ g/m3  (environmental units)
+
+    // The particle count per volume of air is often used in a cleanroom context.
+    particles_0_3: u16, // Number of particles with diameter beyond 0.3 um in 0.1L of air
+    particles_0_5: u16, // Number of particles with diameter beyond 0.5 um in 0.1L of air
+    particles_1_0: u16, // Number of particles with diameter beyond 1.0 um in 0.1L of air
+    particles_2_5: u16, // Number of particles with diameter beyond 2.5 um in 0.1L of air
+    particles_5_0: u16, // Number of particles with diameter beyond 5.0 um in 0.1L of air
+    particles_10: u16,  // Number of particles with diameter beyond 10 um in 0.1L of air
+}
+
+impl Pmsa003iData {
+    /// Gets all three PM mass concentrations in environmental units,
+    /// e.g. for logging them together.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the (PM1.0, PM2.5, PM10) concentrations in µg/m³.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let (pm1_0, pm2_5, pm10) = data.mass_concentrations();
+    /// hprintln!("PM1.0: {}, PM2.5: {}, PM10: {}", pm1_0, pm2_5, pm10);
+    /// let aqi = aqi::calculate_aqi(pm2_5 as f32);
+    /// ```
+    pub fn mass_concentrations(&self) -> (u16, u16, u16) {
+        (self.pm1_0_env, self.pm2_5_env, self.pm10_env)
+    }
+
+    /// Gets the PM2.5 concentration in standard (CF=1) units, e.g.
+    /// as a fallback for the environmental value or for humidity
+    /// correction.
+    ///
+    /// # Returns
+    ///
+    /// The PM2.5 concentration in µg/m³.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let corrected = aqi::correct_pm25(data.pm2_5_standard() as f32, rh);
+    /// ```
+    pub fn pm2_5_standard(&self) -> u16 {
+        self.pm2_5_standard
+    }
+}
+
+/// SizeBucket enum provides the particle size ranges counted by
+/// the PMSA003I sensor, by diameter in microns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeBucket {
+    /// Between 0.3 and 0.5 um, typical of smoke and combustion
+    From0_3To0_5,
+    /// Between 0.5 and 1.0 um
+    From0_5To1_0,
+    /// Between 1.0 and 2.5 um
+    From1_0To2_5,
+    /// Between 2.5 and 5.0 um, typical of coarse dust and pollen
+    From2_5To5_0,
+    /// Between 5.0 and 10 um
+    From5_0To10,
+    /// Beyond 10 um
+    Over10,
+}
+
+/// Finds the particle size range with the most particles, giving a rough
+/// sense of particle composition, e.g. fine smoke versus coarse dust.
+///
+/// The sensor reports cumulative counts of particles beyond each size, so
+/// the count within each range is the difference between adjacent counts.
+/// If ranges are tied, the smaller size is returned, so a reading with no
+/// particles returns `SizeBucket::From0_3To0_5`.
+///
+/// # Arguments
+///
+/// * `data` - Parsed data from the sensor
+///
+/// # Returns
+///
+/// The SizeBucket with the most particles.
+///
+/// # Examples
+///
+/// ```
+/// if dominant_size_bucket(&data) == SizeBucket::From0_3To0_5 {
+///     hprintln!("Mostly fine particles, such as smoke");
+/// }
+/// ```
+pub fn dominant_size_bucket(data: &Pmsa003iData) -> SizeBucket {
+    // Counts may not be strictly decreasing due to sensor noise
+    let buckets = [
+        (
+            SizeBucket::From0_3To0_5,
+            data.particles_0_3.saturating_sub(data.particles_0_5),
+        ),
+        (
+            SizeBucket::From0_5To1_0,
+            data.particles_0_5.saturating_sub(data.particles_1_0),
+        ),
+        (
+            SizeBucket::From1_0To2_5,
+            data.particles_1_0.saturating_sub(data.particles_2_5),
+        ),
+        (
+            SizeBucket::From2_5To5_0,
+            data.particles_2_5.saturating_sub(data.particles_5_0),
+        ),
+        (
+            SizeBucket::From5_0To10,
+            data.particles_5_0.saturating_sub(data.particles_10),
+        ),
+        (SizeBucket::Over10, data.particles_10),
+    ];
+
+    let mut dominant = buckets[0];
+    for bucket in &buckets[1..] {
+        if bucket.1 > dominant.1 {
+            dominant = *bucket;
+        }
+    }
+    dominant.0
+}
+
+/// Calculates the coarse particle fraction, PM2.5 to PM10, in
+/// environmental units. A high coarse fraction relative to PM2.5
+/// suggests dust, while combustion smoke is mostly fine particles.
+///
+/// # Arguments
+///
+/// * `data` - Parsed data from the sensor
+///
+/// # Returns
+///
+/// The coarse concentration in µg/m³. This is 0 if PM2.5 exceeds
+/// PM10, which can happen due to sensor noise.
+///
+/// # Examples
+///
+/// ```
+/// hprintln!("Coarse particles: {} µg/m³", pm_coarse(&data));
+/// ```
+pub fn pm_coarse(data: &Pmsa003iData) -> u16 {
+    data.pm10_env.saturating_sub(data.pm2_5_env)
+}
+
+/// Decides whether to fall back to the standard (CF=1) PM2.5 value.
+/// The sensor occasionally reports a zero environmental PM2.5 value
+/// while the standard value is nonzero, which would otherwise show as
+/// a falsely "Good" reading.
+///
+/// # Arguments
+///
+/// * `env` - The PM2.5 value in environmental units
+/// * `standard` - The PM2.5 value in standard (CF=1) units
+///
+/// # Returns
+///
+/// The standard value if it should be used in place of the
+/// environmental value, or None if the environmental value is usable.
+///
+/// # Examples
+///
+/// ```
+/// let mut pm25 = data.pm2_5_env;
+/// if let Some(standard) = pm25_fallback(data.pm2_5_env, data.pm2_5_standard()) {
+///     hprintln!("Falling back to standard PM2.5 value");
+///     pm25 = standard;
+/// }
+/// ```
+pub fn pm25_fallback(env: u16, standard: u16) -> Option<u16> {
+    if env == 0 && standard != 0 {
+        Some(standard)
+    } else {
+        None
+    }
+}
+
+/// Calculates the difference between the environmental and standard
+/// (CF=1) PM2.5 values. The sensor derives the environmental value
+/// from the standard value using its own correction for ambient
+/// conditions such as pressure, so this shows how much that correction
+/// moves the reading, e.g. for studying the sensor's behavior.
+///
+/// # Arguments
+///
+/// * `data` - Parsed data from the sensor
+///
+/// # Returns
+///
+/// The environmental PM2.5 value minus the standard PM2.5 value, in
+/// µg/m³. This is negative when the environmental value is lower.
+///
+/// # Examples
+///
+/// ```
+/// let data = parse_data(&sensor_data)?;
+/// hprintln!("Environmental correction: {:+} µg/m³", pressure_correction_delta(&data));
+/// ```
+pub fn pressure_correction_delta(data: &Pmsa003iData) -> i32 {
+    data.pm2_5_env as i32 - data.pm2_5_standard as i32
+}
+
+/// Merges readings of the same concentration from two redundant
+/// sensors, checking that they agree. A large difference between the
+/// two suggests that one of the sensors is failing.
+///
+/// # Arguments
+///
+/// * `a` - The concentration from the first sensor, in µg/m³
+/// * `b` - The concentration from the second sensor, in µg/m³
+/// * `max_diff` - The largest difference in µg/m³ for which the
+///   sensors are considered to agree
+///
+/// # Returns
+///
+/// The average of the two readings, rounded to the nearest whole
+/// µg/m³, or a SensorDisagreement error if they differ by more than
+/// `max_diff`.
+///
+/// # Examples
+///
+/// ```
+/// match merge_readings(first.pm2_5_env, second.pm2_5_env, 5) {
+///     Ok(pm25) => hprintln!("PM2.5 concentration: {} µg/m³", pm25),
+///     Err(e) => hprintln!("Error merging readings: {}", e),
+/// }
+/// ```
+pub fn merge_readings(a: u16, b: u16, max_diff: u16) -> Result<u16, Pmsa003iError> {
+    if a.abs_diff(b) > max_diff {
+        return Err(Pmsa003iError::SensorDisagreement);
+    }
+    Ok((a as u32 + b as u32).div_ceil(2) as u16)
+}
+
+/// Amount in µg/m³ by which PM2.5 may exceed PM10 before data is
+/// considered inconsistent, allowing for sensor noise.
+pub const PM_CONSISTENCY_TOLERANCE: u16 = 2;
+
+/// Validates that the parsed PM concentrations are physically plausible.
+/// PM2.5 particles are a subset of PM10 particles, so a PM2.5 concentration
+/// above the PM10 concentration indicates a bad frame which happened to
+/// pass the checksum. Differences within `PM_CONSISTENCY_TOLERANCE` are
+/// allowed, as noise can put PM2.5 slightly above PM10.
+///
+/// # Arguments
+///
+/// * `data` - Parsed data from the sensor
+///
+/// # Returns
+///
+/// Result of Ok(_) or an Err of `Pmsa003iError::InconsistentPm`.
+///
+/// # Examples
+///
+/// ```
+/// if let Err(e) = check_pm_consistency(&data) {
+///     hprintln!("Error validating data: {}", e);
+///     return None;
+/// }
+/// ```
+pub fn check_pm_consistency(data: &Pmsa003iData) -> Result<(), Pmsa003iError> {
+    if data.pm2_5_env > data.pm10_env.saturating_add(PM_CONSISTENCY_TOLERANCE) {
+        return Err(Pmsa003iError::InconsistentPm);
+    }
+    Ok(())
+}
+
+/// Pmsa003iError enum provides the reasons a frame from
+/// the PMSA003I sensor may be rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pmsa003iError {
+    /// Fewer than 32 bytes were available, so the frame is incomplete
+    BufferTooShort,
+    /// The frame did not start with the expected 0x42 0x4D header
+    InvalidHeader,
+    /// The frame checksum did not match the sum of its data bytes
+    InvalidChecksum,
+    /// The PM2.5 concentration exceeded the PM10 concentration,
+    /// which is physically impossible
+    InconsistentPm,
+    /// Readings from redundant sensors differed by more than allowed
+    SensorDisagreement,
+    /// Communication with the sensor failed
+    I2c(ErrorKind),
+}
+
+impl fmt::Display for Pmsa003iError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Pmsa003iError::BufferTooShort => "Buffer too short, expected at least 32 bytes",
+            Pmsa003iError::InvalidHeader => "Header validation failed",
+            Pmsa003iError::InvalidChecksum => "Checksum validation failed",
+            Pmsa003iError::InconsistentPm => "PM2.5 concentration exceeds PM10 concentration",
+            Pmsa003iError::SensorDisagreement => "Readings from redundant sensors disagree",
+            Pmsa003iError::I2c(kind) => return write!(f, "I2C error: {}", kind),
+        };
+        f.write_str(message)
+    }
+}
+
+/// TransferMode selects how the register address write and
+/// the frame read are issued on the bus.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TransferMode {
+    /// A single transaction, with a repeated start between the
+    /// register address write and the frame read.
+    #[default]
+    RepeatedStart,
+    /// Separate write and read transactions, each with its own
+    /// start and stop condition. This is a fallback for buses or
+    /// I2C multiplexers that mishandle repeated starts.
+    WriteThenRead,
+}
+
+/// SensorPresence enum provides whether the sensor is known
+/// to be connected, based on whether it responds on the bus.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SensorPresence {
+    /// The sensor has not been checked yet
+    #[default]
+    Unknown,
+    /// The sensor responded to the most recent check
+    Present,
+    /// The sensor did not respond to the most recent check
+    Absent,
+}
+
+/// The PresenceTracker struct follows whether the sensor is
+/// connected across repeated checks, such as periodic pings,
+/// and reports only when that changes. This allows a single
+/// message to be shown when the sensor is lost or found,
+/// rather than one for every failed check.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PresenceTracker {
+    presence: SensorPresence,
+}
+
+impl PresenceTracker {
+    /// Create a new PresenceTracker, before the sensor has been checked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut presence = PresenceTracker::new();
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            presence: SensorPresence::Unknown,
+        }
+    }
+
+    /// Record the result of checking for the sensor.
+    ///
+    /// # Arguments
+    ///
+    /// * `responded` - Whether the sensor responded to the check
+    ///
+    /// # Returns
+    ///
+    /// The new SensorPresence if it changed, or None otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let responded = ping(&mut i2c, SENSOR_I2C_ADDR).await.is_ok();
+    /// if let Some(SensorPresence::Absent) = presence.update(responded) {
+    ///     hprintln!("Sensor not found");
+    /// }
+    /// ```
+    pub fn update(&mut self, responded: bool) -> Option<SensorPresence> {
+        let presence = if responded {
+            SensorPresence::Present
+        } else {
+            SensorPresence::Absent
+        };
+        if presence == self.presence {
+            return None;
+        }
+        self.presence = presence;
+        Some(presence)
+    }
+
+    /// The current SensorPresence.
+    pub fn presence(&self) -> SensorPresence {
+        self.presence
+    }
+
+    /// Whether the sensor responded to the most recent check.
+    pub fn is_present(&self) -> bool {
+        self.presence == SensorPresence::Present
+    }
+}
+
+/// The SensorHealth struct holds the status reported in the error
+/// code register of each frame. The datasheet names this register
+/// "Error code" without defining its values, so the code is kept raw.
+/// It is usually zero, and a nonzero code is worth logging in case it
+/// gives warning of a failing sensor.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SensorHealth {
+    error_code: u8,
+}
+
+impl SensorHealth {
+    /// The raw error code reported by the sensor.
+    pub fn error_code(&self) -> u8 {
+        self.error_code
+    }
+
+    /// Whether the sensor reported an error code of zero.
+    pub fn is_healthy(&self) -> bool {
+        self.error_code == 0
+    }
+}
+
+impl fmt::Display for SensorHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_healthy() {
+            return f.write_str("OK");
+        }
+        write!(f, "error code 0x{:02X}", self.error_code)
+    }
+}
+
+/// Parses raw buffer data from the PMSA003I sensor
+/// into a struct with named values.
+///
+/// # Arguments
+///
+/// * `buffer` - 32 bytes of data from the sensor
+///
+/// # Returns
+///
+/// A Pmsa003iData struct or an error.
+///
+/// # Examples
+///
+/// ```
+/// let data = parse_data(&sensor_data).unwrap_or_else(|err| {
+///     hprintln!("Error parsing data: {}", err);
+///     Pmsa003iData::default()
+/// });
+///
+/// let pm25_concentration = data.pm2_5_env;
+/// ```
+pub fn parse_data(buffer: &[u8]) -> Result<Pmsa003iData, &'static str> {
+    if buffer.len() < TOTAL_REGISTERS {
+        return Err("Buffer too short, expected at least 32 bytes");
+    }
+
+    Ok(Pmsa003iData {
+        _pm1_0_standard: u16::from_be_bytes([buffer[4], buffer[5]]),
+        pm2_5_standard: u16::from_be_bytes([buffer[6], buffer[7]]),
+        _pm10_standard: u16::from_be_bytes([buffer[8], buffer[9]]),
+        pm1_0_env: u16::from_be_bytes([buffer[10], buffer[11]]),
+        pm2_5_env: u16::from_be_bytes([buffer[12], buffer[13]]),
+        pm10_env: u16::from_be_bytes([buffer[14], buffer[15]]),
+        particles_0_3: u16::from_be_bytes([buffer[16], buffer[17]]),
+        particles_0_5: u16::from_be_bytes([buffer[18], buffer[19]]),
+        particles_1_0: u16::from_be_bytes([buffer[20], buffer[21]]),
+        particles_2_5: u16::from_be_bytes([buffer[22], buffer[23]]),
+        particles_5_0: u16::from_be_bytes([buffer[24], buffer[25]]),
+        particles_10: u16::from_be_bytes([buffer[26], buffer[27]]),
+    })
+}
+
+/// Validates and parses a single frame from the start of a buffer, for
+/// stream-based transports where the buffer may hold more than one frame.
+/// On success, the number of bytes consumed is returned alongside the data
+/// so that a ring buffer reader can advance past the frame.
+///
+/// # Arguments
+///
+/// * `buffer` - Data from the sensor, starting at a frame header
+///
+/// # Returns
+///
+/// A Result containing the parsed data and the number of bytes consumed,
+/// or a Pmsa003iError.
+///
+/// # Examples
+///
+/// ```
+/// match parse_frame(ring_buffer.as_slice()) {
+///     Ok((data, consumed)) => {
+///         ring_buffer.advance(consumed);
+///         let aqi = aqi::calculate_aqi(data.pm2_5_env as f32);
+///     }
+///     Err(Pmsa003iError::BufferTooShort) => {} // Wait for more data
+///     Err(e) => hprintln!("Error parsing frame: {}", e),
+/// }
+/// ```
+pub fn parse_frame(buffer: &[u8]) -> Result<(Pmsa003iData, usize), Pmsa003iError> {
+    validate_frame(buffer)?;
+    let data = parse_data(buffer).map_err(|_| Pmsa003iError::BufferTooShort)?;
+    Ok((data, TOTAL_REGISTERS))
+}
+
+/// Fetches data in an async manner using a non-blocking I2C instance.
+/// The register address write and frame read are issued as a single
+/// transaction with a repeated start.
+///
+/// # Arguments
+///
+/// * `i2c` - An async I2C instance, such as an Embassy Async I2C
+///
+/// # Returns
+///
+/// A Result containing all retrieved data or an i2c Error.
+///
+/// # Examples
+///
+/// ```
+/// let mut i2c = I2c::new(
+///     p.I2C2,
+///     scl,
+///     sda,
+///     Irqs,
+///     p.DMA1_CH4,
+///     p.DMA1_CH5,
+///     Hertz(100_000),
+///     Config::default(),
+/// );
+///
+/// match fetch_data(&mut i2c).await {
+///     Ok(sensor_data) => {
+///         if let Err(e) = validate_header(&sensor_data) {
+///             hprintln!("Error validating header: {}", e);
+///             continue;
+///         }
+///     }
+///     Err(e) => hprintln!("Error reading registers: {:?}", e),
+/// }
+/// ```
+pub async fn fetch_data<I: I2c>(i2c: &mut I) -> Result<[u8; TOTAL_REGISTERS], I::Error> {
+    fetch_data_with_mode(i2c, TransferMode::RepeatedStart).await
+}
+
+/// Fetches data in an async manner using the specified transfer mode.
+///
+/// # Arguments
+///
+/// * `i2c` - An async I2C instance, such as an Embassy Async I2C
+/// * `mode` - Whether to use a repeated start or separate transactions
+///
+/// # Returns
+///
+/// A Result containing all retrieved data or an i2c Error.
+///
+/// # Examples
+///
+/// ```
+/// match fetch_data_with_mode(&mut i2c, TransferMode::WriteThenRead).await {
+///     Ok(sensor_data) => print_all_regs(&sensor_data),
+///     Err(e) => hprintln!("Error reading registers: {:?}", e),
+/// }
+/// ```
+pub async fn fetch_data_with_mode<I: I2c>(
+    i2c: &mut I,
+    mode: TransferMode,
+) -> Result<[u8; TOTAL_REGISTERS], I::Error> {
+    let mut buffer = [0u8; TOTAL_REGISTERS];
+    match mode {
+        TransferMode::RepeatedStart => {
+            i2c.write_read(SENSOR_I2C_ADDR, &[0x00], &mut buffer)
+                .await?;
+        }
+        TransferMode::WriteThenRead => {
+            i2c.write(SENSOR_I2C_ADDR, &[0x00]).await?;
+            i2c.read(SENSOR_I2C_ADDR, &mut buffer).await?;
+        }
+    }
+    Ok(buffer)
+}
+
+/// Fetches data using the specified transfer mode, retrying failed
+/// reads up to `FETCH_MAX_ATTEMPTS` times in total, with a delay of
+/// `FETCH_RETRY_INTERVAL_MS` between attempts. Errors which call for
+/// bus recovery, as decided by `needs_bus_recovery`, are returned
+/// immediately, since retrying cannot succeed until the bus is freed.
+///
+/// # Arguments
+///
+/// * `i2c` - An async I2C instance, such as an Embassy Async I2C
+/// * `delay` - An async delay provider
+/// * `mode` - Whether to use a repeated start or separate transactions
+///
+/// # Returns
+///
+/// A Result containing all retrieved data, or the i2c Error from the
+/// last attempt.
+///
+/// # Examples
+///
+/// ```
+/// match fetch_data_retry(&mut i2c, &mut Delay, TransferMode::RepeatedStart).await {
+///     Ok(sensor_data) => print_all_regs(&sensor_data),
+///     Err(e) => hprintln!("Error reading registers: {:?}", e),
+/// }
+/// ```
+pub async fn fetch_data_retry<I: I2c, D: DelayNs>(
+    i2c: &mut I,
+    delay: &mut D,
+    mode: TransferMode,
+) -> Result<[u8; TOTAL_REGISTERS], I::Error> {
+    let mut attempts = 1;
+    loop {
+        match fetch_data_with_mode(i2c, mode).await {
+            Err(e) if attempts < FETCH_MAX_ATTEMPTS && !needs_bus_recovery(e.kind()) => {
+                attempts += 1;
+                delay.delay_ms(FETCH_RETRY_INTERVAL_MS).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Checks whether a frame is a repeat of the previous frame, as the
+/// sensor returns when read faster than it updates. Whole frames are
+/// compared, and the particle counts and checksum make an identical
+/// frame from a genuine update very unlikely.
+///
+/// # Arguments
+///
+/// * `previous` - The frame from the previous read
+/// * `current` - The frame just read
+///
+/// # Returns
+///
+/// true if the frames are identical, so the current frame is stale.
+///
+/// # Examples
+///
+/// ```
+/// if is_repeated_frame(&last_frame, &sensor_data) {
+///     hprintln!("Sensor frame not updated");
+/// }
+/// ```
+pub fn is_repeated_frame(previous: &[u8], current: &[u8]) -> bool {
+    previous == current
+}
+
+/// Reads the sensor until it returns a frame different from
+/// `previous`, polling up to `NEW_FRAME_MAX_POLLS` times with a delay
+/// of `NEW_FRAME_POLL_INTERVAL_MS` before each read. Each read is
+/// retried as by `fetch_data_retry`.
+///
+/// # Arguments
+///
+/// * `i2c` - An async I2C instance, such as an Embassy Async I2C
+/// * `delay` - An async delay provider
+/// * `mode` - Whether to use a repeated start or separate transactions
+/// * `previous` - The stale frame to wait past
+///
+/// # Returns
+///
+/// A Result containing the new frame, or None if the sensor did not
+/// update in time, or the i2c Error if a read failed.
+///
+/// # Examples
+///
+/// ```
+/// if is_repeated_frame(&last_frame, &sensor_data) {
+///     match wait_for_new_frame(&mut i2c, &mut Delay, mode, &sensor_data).await {
+///         Ok(Some(new_data)) => sensor_data = new_data,
+///         Ok(None) => hprintln!("Sensor frame not updated"),
+///         Err(e) => hprintln!("Error reading registers: {:?}", e),
+///     }
+/// }
+/// ```
+pub async fn wait_for_new_frame<I: I2c, D: DelayNs>(
+    i2c: &mut I,
+    delay: &mut D,
+    mode: TransferMode,
+    previous: &[u8; TOTAL_REGISTERS],
+) -> Result<Option<[u8; TOTAL_REGISTERS]>, I::Error> {
+    for _ in 0..NEW_FRAME_MAX_POLLS {
+        delay.delay_ms(NEW_FRAME_POLL_INTERVAL_MS).await;
+        let sensor_data = fetch_data_retry(i2c, delay, mode).await?;
+        if !is_repeated_frame(previous, &sensor_data) {
+            return Ok(Some(sensor_data));
+        }
+    }
+    Ok(None)
+}
+
+/// Pings a device with an address-only transaction, a write of zero
+/// bytes. A device which is present acknowledges its address, and
+/// since no data byte follows, nothing is written to any of its
+/// registers. The address is taken as an argument so the ping can be
+/// aimed elsewhere, e.g. at an I2C multiplexer in front of the sensor,
+/// without changing the multiplexer's channel selection. It is
+/// normally `SENSOR_I2C_ADDR`.
+///
+/// # Arguments
+///
+/// * `i2c` - An async I2C instance, such as an Embassy Async I2C
+/// * `address` - The 7-bit I2C address to ping
+///
+/// # Returns
+///
+/// Result of Ok(_) if the device acknowledged, or an i2c Error.
+///
+/// # Examples
+///
+/// ```
+/// if let Err(e) = ping(&mut i2c, SENSOR_I2C_ADDR).await {
+///     hprintln!("Sensor did not respond: {:?}", e);
+/// }
+/// ```
+pub async fn ping<I: I2c>(i2c: &mut I, address: u8) -> Result<(), I::Error> {
+    i2c.write(address, &[]).await
+}
+
+/// Reads the sensor version, so that firmware can log which
+/// revision of the sensor is attached. The version is reported
+/// in every frame, so this fetches and validates a single frame.
+///
+/// # Arguments
+///
+/// * `i2c` - An async I2C instance, such as an Embassy Async I2C
+/// * `mode` - Whether to use a repeated start or separate transactions
+///
+/// # Returns
+///
+/// A Result containing the version byte, or a Pmsa003iError.
+///
+/// # Examples
+///
+/// ```
+/// match read_version(&mut i2c, TransferMode::RepeatedStart).await {
+///     Ok(version) => hprintln!("Sensor version: 0x{:02X}", version),
+///     Err(e) => hprintln!("Error reading sensor version: {}", e),
+/// }
+/// ```
+pub async fn read_version<I: I2c>(i2c: &mut I, mode: TransferMode) -> Result<u8, Pmsa003iError> {
+    let sensor_data = fetch_data_with_mode(i2c, mode)
+        .await
+        .map_err(|e| Pmsa003iError::I2c(e.kind()))?;
+    parse_version(&sensor_data)
+}
+
+/// Extracts the sensor version from a frame, after validating
+/// its header and checksum.
+///
+/// # Arguments
+///
+/// * `buffer` - Entire array of u8 data from the sensor
+///
+/// # Returns
+///
+/// A Result containing the version byte, or a Pmsa003iError.
+///
+/// # Examples
+///
+/// ```
+/// let version = parse_version(&sensor_data)?;
+/// ```
+pub fn parse_version(buffer: &[u8]) -> Result<u8, Pmsa003iError> {
+    validate_frame(buffer)?;
+    Ok(buffer[VERSION_OFFSET])
+}
+
+/// Extracts the sensor health from a frame, after validating
+/// its header and checksum.
+///
+/// # Arguments
+///
+/// * `buffer` - Entire array of u8 data from the sensor
+///
+/// # Returns
+///
+/// A Result containing the SensorHealth, or a Pmsa003iError.
+///
+/// # Examples
+///
+/// ```
+/// let health = parse_health(&sensor_data)?;
+/// if !health.is_healthy() {
+///     hprintln!("Warning: sensor reported {}", health);
+/// }
+/// ```
+pub fn parse_health(buffer: &[u8]) -> Result<SensorHealth, Pmsa003iError> {
+    validate_frame(buffer)?;
+    Ok(SensorHealth {
+        error_code: buffer[ERROR_CODE_OFFSET],
+    })
+}
+
+/// Validates the header data retrieved from the PMSA003I sensor.
+/// The sensor has hardcoded values of 0x42 and 0x4D in the first
+/// two register. This function ensures the retrieved data includes
+/// those values.
+///
+/// # Arguments
+///
+/// * `buffer` - Entire array of u8 data from the sensor, or at
+///   least its first two bytes
+///
+/// # Returns
+///
+/// Result of Ok(_) or an Err with message.
+///
+/// # Examples
+///
+/// ```
+/// let mut i2c = I2c::new(
+///     p.I2C2,
+///     scl,
+///     sda,
+///     Irqs,
+///     p.DMA1_CH4,
+///     p.DMA1_CH5,
+///     Hertz(100_000),
+///     Config::default(),
+/// );
+///
+/// match fetch_data(&mut i2c).await {
+///     Ok(sensor_data) => {
+///         if let Err(e) = validate_header(&sensor_data) {
+///             hprintln!("Error validating header: {}", e);
+///             continue;
+///         }
+///     }
+///     Err(e) => hprintln!("Error reading registers: {:?}", e),
+/// }
+/// ```
+pub fn validate_header(buffer: &[u8]) -> Result<(), &'static str> {
+    if buffer.is_empty() {
+        return Err("Buffer is empty");
+    }
+
+    match buffer.get(..EXPECTED_HEADER.len()) {
+        Some(header_bytes) if header_bytes == EXPECTED_HEADER => Ok(()),
+        Some(_) => Err("Header validation failed"),
+        None => Err("Buffer too short, expected at least 2 header bytes"),
+    }
+}
+
+/// Validates the data and checksum retrieved from the PMSA003I sensor.
+/// The sensor provides checksum values against which the payload may be validated.
+/// The checksum values are contained in the last 2 bytes returned from the
+/// sensor, and are compared against the sum of the first 30 bytes of data.
+///
+/// The sum is accumulated with wrapping addition to match the sensor's
+/// own 16-bit checksum. Since 30 bytes sum to at most 7650, the sum
+/// cannot actually overflow, so wrapping never changes the result, and
+/// a frame of any byte values validates against its true sum.
+///
+/// # Arguments
+///
+/// * `buffer` - Entire array of u8 data from the sensor
+///
+/// # Returns
+///
+/// Result of Ok(_) or an Err with message.
+///
+/// # Examples
+///
+/// ```
+/// let mut i2c = I2c::new(
+///     p.I2C2,
+///     scl,
+///     sda,
+///     Irqs,
+///     p.DMA1_CH4,
+///     p.DMA1_CH5,
+///     Hertz(100_000),
+///     Config::default(),
+/// );
+///
+/// match fetch_data(&mut i2c).await {
+///     Ok(sensor_data) => {
+///         if let Err(e) = validate_checksum(&sensor_data) {
+///             hprintln!("Error validating checksum: {}", e);
+///             continue;
+///         }
+///     }
+///     Err(e) => hprintln!("Error reading registers: {:?}", e),
+/// }
+/// ```
+pub fn validate_checksum(buffer: &[u8]) -> Result<(), &'static str> {
+    let (Some(data_bytes), Some(&[high, low])) = (
+        buffer.get(..CHECKSUM_OFFSET),
+        buffer.get(CHECKSUM_OFFSET..TOTAL_REGISTERS),
+    ) else {
+        return Err("Could not validate checksum, incorrect number of bytes received");
+    };
+
+    // Calculate sum of first 30 bytes as checksum
+    let mut calculated_sum: u16 = 0;
+    for &byte in data_bytes {
+        calculated_sum = calculated_sum.wrapping_add(byte as u16);
+    }
+    let received_sum = u16::from_be_bytes([high, low]);
+
+    if calculated_sum == received_sum {
+        Ok(())
+    } else {
+        Err("Checksum validation failed")
+    }
+}
+
+/// Validates the header and then the checksum of a frame, returning
+/// the first error encountered.
+///
+/// # Arguments
+///
+/// * `buffer` - The raw frame read from the sensor
+///
+/// # Returns
+///
+/// Ok if the frame is valid, otherwise `BufferTooShort` if fewer than
+/// `TOTAL_REGISTERS` bytes were given, `InvalidHeader` if the start
+/// bytes do not match, or `InvalidChecksum` if the checksum does not
+/// match the data.
+///
+/// # Examples
+///
+/// ```
+/// match fetch_data(&mut i2c).await {
+///     Ok(sensor_data) => {
+///         if let Err(e) = validate_frame(&sensor_data) {
+///             hprintln!("Error validating frame: {}", e);
+///             continue;
+///         }
+///     }
+///     Err(e) => hprintln!("Error reading registers: {:?}", e),
+/// }
+/// ```
+pub fn validate_frame(buffer: &[u8]) -> Result<(), Pmsa003iError> {
+    if buffer.len() < TOTAL_REGISTERS {
+        return Err(Pmsa003iError::BufferTooShort);
+    }
+    validate_header(buffer).map_err(|_| Pmsa003iError::InvalidHeader)?;
+    validate_checksum(buffer).map_err(|_| Pmsa003iError::InvalidChecksum)
+}
+
+/// Resets the sensor and waits for it to come back with a valid frame.
+///
+/// The PMSA003I has no I2C reset command, so the reset is performed
+/// using its active-low RESET pin, which must be wired to a GPIO. The
+/// pin is held low for `RESET_PULSE_MS`, then released, and the sensor
+/// is polled every `RESET_POLL_INTERVAL_MS` for up to `RESET_MAX_POLLS`
+/// attempts until a frame passes header and checksum validation. Frames
+/// are typically available within a second, but per the datasheet the
+/// readings should not be considered stable until the fan has run for
+/// at least 30 seconds.
+///
+/// # Arguments
+///
+/// * `i2c` - An async I2C instance, such as an Embassy Async I2C
+/// * `reset_pin` - The GPIO output connected to the sensor's RESET pin
+/// * `delay` - An async delay provider
+/// * `mode` - Whether to use a repeated start or separate transactions
+///
+/// # Returns
+///
+/// Result of Ok(_) or an Err with message.
+///
+/// # Examples
+///
+/// ```
+/// let mut reset_pin = Output::new(p.PD8, Level::High, Speed::Low);
+///
+/// if let Err(e) = reset(&mut i2c, &mut reset_pin, &mut Delay, TransferMode::RepeatedStart).await {
+///     hprintln!("Error resetting sensor: {}", e);
+/// }
+/// ```
+pub async fn reset<I: I2c, P: OutputPin, D: DelayNs>(
+    i2c: &mut I,
+    reset_pin: &mut P,
+    delay: &mut D,
+    mode: TransferMode,
+) -> Result<(), &'static str> {
+    reset_pin
+        .set_low()
+        .map_err(|_| "Could not drive sensor reset pin low")?;
+    delay.delay_ms(RESET_PULSE_MS).await;
+    reset_pin
+        .set_high()
+        .map_err(|_| "Could not release sensor reset pin")?;
+
+    wait_for_frame(i2c, delay, mode)
+        .await
+        .map_err(|_| "Sensor did not return a valid frame after reset")
+}
+
+/// Puts the sensor to sleep, stopping the fan and laser to save power.
+/// The PMSA003I has no I2C sleep command, so this is done by pulling
+/// its SET pin low, which must be wired to a GPIO. Use `wake` to
+/// resume.
+///
+/// # Arguments
+///
+/// * `set_pin` - The GPIO output connected to the sensor's SET pin
+///
+/// # Returns
+///
+/// Result of Ok(_) or an Err with message.
+///
+/// # Examples
+///
+/// ```
+/// let mut set_pin = Output::new(p.PD9, Level::High, Speed::Low);
+///
+/// if let Err(e) = sleep(&mut set_pin) {
+///     hprintln!("Error putting sensor to sleep: {}", e);
+/// }
+/// ```
+pub fn sleep<P: OutputPin>(set_pin: &mut P) -> Result<(), &'static str> {
+    set_pin
+        .set_low()
+        .map_err(|_| "Could not drive sensor SET pin low")
+}
+
+/// Wakes the sensor after `sleep` and waits for it to come back with a
+/// valid frame, polling as `reset` does. As after a reset, readings
+/// should not be considered stable until the fan has run for at least
+/// 30 seconds.
+///
+/// # Arguments
+///
+/// * `i2c` - An async I2C instance, such as an Embassy Async I2C
+/// * `set_pin` - The GPIO output connected to the sensor's SET pin
+/// * `delay` - An async delay provider
+/// * `mode` - Whether to use a repeated start or separate transactions
+///
+/// # Returns
+///
+/// Result of Ok(_) or an Err with message.
+///
+/// # Examples
+///
+/// ```
+/// if let Err(e) = wake(&mut i2c, &mut set_pin, &mut Delay, TransferMode::RepeatedStart).await {
+///     hprintln!("Error waking sensor: {}", e);
+/// }
+/// ```
+pub async fn wake<I: I2c, P: OutputPin, D: DelayNs>(
+    i2c: &mut I,
+    set_pin: &mut P,
+    delay: &mut D,
+    mode: TransferMode,
+) -> Result<(), &'static str> {
+    set_pin
+        .set_high()
+        .map_err(|_| "Could not release sensor SET pin")?;
+    wait_for_frame(i2c, delay, mode)
+        .await
+        .map_err(|_| "Sensor did not return a valid frame after waking")
+}
+
+/// Waits until the sensor has warmed up, in steps of `step_ms`, calling
+/// `on_step` before each step, e.g. to animate a display. Time is
+/// measured by the delays requested rather than a clock, so only the
+/// delay provider is needed.
+///
+/// # Arguments
+///
+/// * `delay` - An async delay provider
+/// * `elapsed_ms` - Time already passed since the sensor was powered on
+/// * `warmup_ms` - Time needed before readings are stable, normally
+///   `WARMUP_MS`
+/// * `step_ms` - Time between calls to `on_step`, treated as 1 if zero
+/// * `on_step` - Called with the time since power-on before each step
+///
+/// # Examples
+///
+/// ```
+/// let elapsed_ms = Instant::now().as_millis() as u32;
+/// wait_for_warmup(&mut Delay, elapsed_ms, WARMUP_MS, 125, |elapsed_ms| {
+///     hprintln!("Warming up, {} ms elapsed", elapsed_ms);
+/// })
+/// .await;
+/// ```
+pub async fn wait_for_warmup<D: DelayNs, F: FnMut(u32)>(
+    delay: &mut D,
+    mut elapsed_ms: u32,
+    warmup_ms: u32,
+    step_ms: u32,
+    mut on_step: F,
+) {
+    let step_ms = step_ms.max(1);
+    while elapsed_ms < warmup_ms {
+        on_step(elapsed_ms);
+        let wait_ms = step_ms.min(warmup_ms - elapsed_ms);
+        delay.delay_ms(wait_ms).await;
+        elapsed_ms += wait_ms;
+    }
+}
+
+/// Polls the sensor every `RESET_POLL_INTERVAL_MS` for up to
+/// `RESET_MAX_POLLS` attempts, until a frame passes header and
+/// checksum validation, reading with the given transfer mode.
+async fn wait_for_frame<I: I2c, D: DelayNs>(
+    i2c: &mut I,
+    delay: &mut D,
+    mode: TransferMode,
+) -> Result<(), ()> {
+    for _ in 0..RESET_MAX_POLLS {
+        delay.delay_ms(RESET_POLL_INTERVAL_MS).await;
+        if let Ok(sensor_data) = fetch_data_with_mode(i2c, mode).await {
+            if validate_frame(&sensor_data).is_ok() {
+                return Ok(());
+            }
+        }
+    }
+    Err(())
+}
+
+/// Decides whether an I2C error calls for bus recovery before the
+/// transfer is retried. Lost arbitration and bus errors (misplaced
+/// START or STOP conditions) usually mean a device was left mid-byte
+/// holding SDA low, e.g. after a glitch or a reset during a transfer,
+/// which wedges the bus until it is clocked free. Other errors, such
+/// as a missing acknowledge or a timeout, are not helped by recovery.
+///
+/// With Embassy, `i2c::Error::Arbitration` maps to
+/// `ErrorKind::ArbitrationLoss` and `i2c::Error::Bus` to `ErrorKind::Bus`.
+///
+/// # Arguments
+///
+/// * `kind` - The kind of the I2C error
+///
+/// # Returns
+///
+/// True if the bus should be recovered before retrying.
+///
+/// # Examples
+///
+/// ```
+/// if let Err(e) = fetch_data(&mut i2c).await {
+///     if needs_bus_recovery(e.kind()) {
+///         recover_bus(&mut scl, &mut sda, &mut Delay).await?;
+///     }
+/// }
+/// ```
+pub fn needs_bus_recovery(kind: ErrorKind) -> bool {
+    matches!(kind, ErrorKind::ArbitrationLoss | ErrorKind::Bus)
+}
+
+/// Recovers a stuck I2C bus by clocking out `RECOVERY_CLOCK_PULSES`
+/// pulses on SCL with SDA released, then generating a STOP condition,
+/// as described in section 3.1.16 of the I2C specification (UM10204).
+/// Both pins must be open drain outputs, temporarily taken from the
+/// I2C peripheral, and are left released (high) on return.
+///
+/// # Arguments
+///
+/// * `scl` - The bus clock line, as an open drain output
+/// * `sda` - The bus data line, as an open drain output
+/// * `delay` - An async delay provider
+///
+/// # Returns
+///
+/// Result of Ok(_) or an Err with message.
+///
+/// # Examples
+///
+/// ```
+/// let mut scl = OutputOpenDrain::new(p.PA9, Level::High, Speed::Low);
+/// let mut sda = OutputOpenDrain::new(p.PA10, Level::High, Speed::Low);
+///
+/// if let Err(e) = recover_bus(&mut scl, &mut sda, &mut Delay).await {
+///     hprintln!("Error recovering bus: {}", e);
+/// }
+/// ```
+pub async fn recover_bus<C: OutputPin, S: OutputPin, D: DelayNs>(
+    scl: &mut C,
+    sda: &mut S,
+    delay: &mut D,
+) -> Result<(), &'static str> {
+    const SCL_ERROR: &str = "Could not drive SCL during bus recovery";
+    const SDA_ERROR: &str = "Could not drive SDA during bus recovery";
+
+    // Release SDA so the stuck device can shift out the rest of its byte
+    sda.set_high().map_err(|_| SDA_ERROR)?;
+    for _ in 0..RECOVERY_CLOCK_PULSES {
+        scl.set_low().map_err(|_| SCL_ERROR)?;
+        delay.delay_us(RECOVERY_HALF_PERIOD_US).await;
+        scl.set_high().map_err(|_| SCL_ERROR)?;
+        delay.delay_us(RECOVERY_HALF_PERIOD_US).await;
+    }
+
+    // STOP: SDA rises while SCL is high
+    scl.set_low().map_err(|_| SCL_ERROR)?;
+    sda.set_low().map_err(|_| SDA_ERROR)?;
+    delay.delay_us(RECOVERY_HALF_PERIOD_US).await;
+    scl.set_high().map_err(|_| SCL_ERROR)?;
+    delay.delay_us(RECOVERY_HALF_PERIOD_US).await;
+    sda.set_high().map_err(|_| SDA_ERROR)?;
+    delay.delay_us(RECOVERY_HALF_PERIOD_US).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{block_on, MockDelay, MockI2c, Op};
+    use core::convert::Infallible;
+    use embedded_hal::digital;
+    use embedded_hal_async::i2c::ErrorKind;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    /// A mock GPIO output that records every level it is driven to.
+    #[derive(Default)]
+    struct MockPin {
+        states: Vec<bool>,
+    }
+
+    impl digital::ErrorType for MockPin {
+        type Error = Infallible;
+    }
+
+    impl digital::OutputPin for MockPin {
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.states.push(false);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.states.push(true);
+            Ok(())
+        }
+    }
+
+    /// Fills in the checksum of a frame from its data bytes.
+    fn with_checksum(mut frame: [u8; TOTAL_REGISTERS]) -> [u8; TOTAL_REGISTERS] {
+        let sum = frame[..30]
+            .iter()
+            .fold(0u16, |sum, &byte| sum.wrapping_add(byte as u16));
+        frame[30..32].copy_from_slice(&sum.to_be_bytes());
+        frame
+    }
+
+    /// Builds a valid frame with the given PM2.5 environmental value.
+    fn frame_with_pm25(pm25: u16) -> [u8; TOTAL_REGISTERS] {
+        let mut frame = [0u8; TOTAL_REGISTERS];
+        frame[0..2].copy_from_slice(&EXPECTED_HEADER);
+        frame[12..14].copy_from_slice(&pm25.to_be_bytes());
+        with_checksum(frame)
+    }
+
+    #[test]
+    fn test_fetch_repeated_start() {
+        let mut i2c = MockI2c::new(&[frame_with_pm25(41)]);
+
+        let sensor_data = block_on(fetch_data(&mut i2c)).unwrap();
+        assert_eq!(sensor_data, frame_with_pm25(41));
+        assert_eq!(
+            i2c.transactions(),
+            [[Op::Write([0x00].to_vec()), Op::Read(TOTAL_REGISTERS)]]
+        );
+    }
+
+    #[test]
+    fn test_ping() {
+        let mut i2c = MockI2c::new(&[frame_with_pm25(41)]);
+
+        assert!(block_on(ping(&mut i2c, SENSOR_I2C_ADDR)).is_ok());
+        // Only the address is sent, no register is written or read
+        assert_eq!(i2c.transactions(), [[Op::Write(Vec::new())]]);
+    }
+
+    #[test]
+    fn test_read_pipeline() {
+        let mut frame = frame_with_pm25(41);
+        frame[14..16].copy_from_slice(&60u16.to_be_bytes());
+        let mut i2c = MockI2c::default();
+        i2c.queue_frame(with_checksum(frame));
+
+        let sensor_data = block_on(fetch_data(&mut i2c)).unwrap();
+        assert!(validate_header(&sensor_data).is_ok());
+        assert!(validate_checksum(&sensor_data).is_ok());
+        let data = parse_data(&sensor_data).unwrap();
+        assert!(check_pm_consistency(&data).is_ok());
+        assert_eq!(data.mass_concentrations(), (0, 41, 60));
+        assert_eq!(i2c.written(), [[0x00]]);
+    }
+
+    #[test]
+    fn test_absent_sensor() {
+        let mut i2c = MockI2c::default();
+        assert!(matches!(
+            block_on(ping(&mut i2c, SENSOR_I2C_ADDR)),
+            Err(ErrorKind::NoAcknowledge(_))
+        ));
+        assert!(block_on(fetch_data(&mut i2c)).is_err());
+
+        // Only the sensor's address acknowledges
+        i2c.queue_frame(frame_with_pm25(41));
+        assert!(block_on(ping(&mut i2c, SENSOR_I2C_ADDR + 1)).is_err());
+        assert!(block_on(ping(&mut i2c, SENSOR_I2C_ADDR)).is_ok());
+        assert_eq!(i2c.transactions().len(), 1);
+    }
+
+    #[test]
+    fn test_fetch_write_then_read() {
+        let mut i2c = MockI2c::new(&[frame_with_pm25(41)]);
+
+        let sensor_data =
+            block_on(fetch_data_with_mode(&mut i2c, TransferMode::WriteThenRead)).unwrap();
+        assert!(validate_header(&sensor_data).is_ok());
+        assert!(validate_checksum(&sensor_data).is_ok());
+        assert_eq!(parse_data(&sensor_data).unwrap().pm2_5_env, 41);
+        assert_eq!(
+            i2c.transactions(),
+            [[Op::Write([0x00].to_vec())], [Op::Read(TOTAL_REGISTERS)]]
+        );
+    }
+
+    #[test]
+    fn test_fetch_data_retry() {
+        let mut i2c = MockI2c::new(&[frame_with_pm25(41)]);
+        let mut delay = MockDelay::default();
+
+        let sensor_data = block_on(fetch_data_retry(
+            &mut i2c,
+            &mut delay,
+            TransferMode::RepeatedStart,
+        ));
+        assert_eq!(sensor_data, Ok(frame_with_pm25(41)));
+        assert!(delay.delays_ns.is_empty());
+
+        // With no sensor, every attempt fails, with a delay between each
+        let mut i2c = MockI2c::default();
+        let mut delay = MockDelay::default();
+
+        let sensor_data = block_on(fetch_data_retry(
+            &mut i2c,
+            &mut delay,
+            TransferMode::RepeatedStart,
+        ));
+        assert!(matches!(sensor_data, Err(ErrorKind::NoAcknowledge(_))));
+        assert_eq!(
+            delay.delays_ns,
+            [FETCH_RETRY_INTERVAL_MS * 1_000_000; FETCH_MAX_ATTEMPTS as usize - 1]
+        );
+    }
+
+    #[test]
+    fn test_wait_for_warmup() {
+        let mut delay = MockDelay::default();
+        let mut steps = Vec::new();
+
+        block_on(wait_for_warmup(&mut delay, 100, 1000, 300, |elapsed_ms| {
+            steps.push(elapsed_ms)
+        }));
+        assert_eq!(steps, [100, 400, 700]);
+        assert_eq!(delay.delays_ns, [300_000_000, 300_000_000, 300_000_000]);
+
+        // The final step is shortened to end exactly when warmed up
+        let mut delay = MockDelay::default();
+        block_on(wait_for_warmup(&mut delay, 800, 1000, 300, |_| {}));
+        assert_eq!(delay.delays_ns, [200_000_000]);
+
+        // Once warmed up, there is nothing to wait for
+        let mut delay = MockDelay::default();
+        block_on(wait_for_warmup(&mut delay, 1000, 1000, 300, |_| {
+            panic!("no steps expected")
+        }));
+        assert!(delay.delays_ns.is_empty());
+    }
+
+    #[test]
+    fn test_is_repeated_frame() {
+        assert!(is_repeated_frame(
+            &frame_with_pm25(41),
+            &frame_with_pm25(41)
+        ));
+        assert!(!is_repeated_frame(
+            &frame_with_pm25(41),
+            &frame_with_pm25(42)
+        ));
+    }
+
+    #[test]
+    fn test_wait_for_new_frame() {
+        // The sensor repeats the previous frame once before updating
+        let stale = frame_with_pm25(41);
+        let mut i2c = MockI2c::new(&[stale, frame_with_pm25(42)]);
+        let mut delay = MockDelay::default();
+
+        let sensor_data = block_on(wait_for_new_frame(
+            &mut i2c,
+            &mut delay,
+            TransferMode::RepeatedStart,
+            &stale,
+        ));
+        assert_eq!(sensor_data, Ok(Some(frame_with_pm25(42))));
+        assert_eq!(delay.delays_ns, [NEW_FRAME_POLL_INTERVAL_MS * 1_000_000; 2]);
+
+        // The sensor never updates, so the wait gives up
+        let mut i2c = MockI2c::new(&[stale]);
+        let mut delay = MockDelay::default();
+
+        let sensor_data = block_on(wait_for_new_frame(
+            &mut i2c,
+            &mut delay,
+            TransferMode::RepeatedStart,
+            &stale,
+        ));
+        assert_eq!(sensor_data, Ok(None));
+        assert_eq!(i2c.transactions().len(), NEW_FRAME_MAX_POLLS as usize);
+
+        // Read errors are returned rather than waited out
+        let mut i2c = MockI2c::default();
+        let mut delay = MockDelay::default();
+
+        let sensor_data = block_on(wait_for_new_frame(
+            &mut i2c,
+            &mut delay,
+            TransferMode::RepeatedStart,
+            &stale,
+        ));
+        assert!(matches!(sensor_data, Err(ErrorKind::NoAcknowledge(_))));
+    }
+
+    #[test]
+    fn test_reset() {
+        // The first frame after reset is still invalid
+        let mut i2c = MockI2c::new(&[[0u8; TOTAL_REGISTERS], frame_with_pm25(41)]);
+        let mut reset_pin = MockPin::default();
+        let mut delay = MockDelay::default();
+
+        assert!(block_on(reset(
+            &mut i2c,
+            &mut reset_pin,
+            &mut delay,
+            TransferMode::RepeatedStart
+        ))
+        .is_ok());
+        assert_eq!(reset_pin.states, [false, true]);
+        assert_eq!(
+            delay.delays_ns,
+            [
+                RESET_PULSE_MS * 1_000_000,
+                RESET_POLL_INTERVAL_MS * 1_000_000,
+                RESET_POLL_INTERVAL_MS * 1_000_000
+            ]
+        );
+        // Each poll reads the full frame starting at register 0x00
+        let poll = [Op::Write([0x00].to_vec()), Op::Read(TOTAL_REGISTERS)];
+        assert_eq!(i2c.transactions(), [poll.as_slice(), poll.as_slice()]);
+    }
+
+    #[test]
+    fn test_reset_timeout() {
+        let mut i2c = MockI2c::new(&[[0u8; TOTAL_REGISTERS]]);
+        let mut reset_pin = MockPin::default();
+        let mut delay = MockDelay::default();
+
+        assert!(block_on(reset(
+            &mut i2c,
+            &mut reset_pin,
+            &mut delay,
+            TransferMode::RepeatedStart
+        ))
+        .is_err());
+        assert_eq!(i2c.transactions().len(), RESET_MAX_POLLS as usize);
+    }
+
+    #[test]
+    fn test_sleep_and_wake() {
+        let mut i2c = MockI2c::new(&[[0u8; TOTAL_REGISTERS], frame_with_pm25(41)]);
+        let mut set_pin = MockPin::default();
+        let mut delay = MockDelay::default();
+
+        assert!(sleep(&mut set_pin).is_ok());
+        assert_eq!(set_pin.states, [false]);
+        assert!(i2c.transactions().is_empty());
+
+        // Waking polls until a valid frame arrives
+        assert!(block_on(wake(
+            &mut i2c,
+            &mut set_pin,
+            &mut delay,
+            TransferMode::RepeatedStart
+        ))
+        .is_ok());
+        assert_eq!(set_pin.states, [false, true]);
+        assert_eq!(i2c.transactions().len(), 2);
+    }
+
+    #[test]
+    fn test_wake_timeout() {
+        let mut i2c = MockI2c::new(&[[0u8; TOTAL_REGISTERS]]);
+        let mut set_pin = MockPin::default();
+        let mut delay = MockDelay::default();
+
+        assert!(block_on(wake(
+            &mut i2c,
+            &mut set_pin,
+            &mut delay,
+            TransferMode::RepeatedStart
+        ))
+        .is_err());
+        assert_eq!(i2c.transactions().len(), RESET_MAX_POLLS as usize);
+    }
+
+    #[test]
+    fn test_needs_bus_recovery() {
+        assert!(needs_bus_recovery(ErrorKind::ArbitrationLoss));
+        assert!(needs_bus_recovery(ErrorKind::Bus));
+        assert!(!needs_bus_recovery(ErrorKind::NoAcknowledge(
+            embedded_hal_async::i2c::NoAcknowledgeSource::Address
+        )));
+        assert!(!needs_bus_recovery(ErrorKind::Overrun));
+        assert!(!needs_bus_recovery(ErrorKind::Other));
+    }
+
+    #[test]
+    fn test_recover_bus() {
+        let mut scl = MockPin::default();
+        let mut sda = MockPin::default();
+        let mut delay = MockDelay::default();
+
+        assert!(block_on(recover_bus(&mut scl, &mut sda, &mut delay)).is_ok());
+        // Nine pulses, then low and high again around the STOP condition,
+        // leaving both lines released
+        let pulses = [false, true].repeat(RECOVERY_CLOCK_PULSES as usize + 1);
+        assert_eq!(scl.states, pulses);
+        assert_eq!(sda.states, [true, false, true]);
+        assert_eq!(
+            delay.delays_ns,
+            [RECOVERY_HALF_PERIOD_US * 1000].repeat(2 * RECOVERY_CLOCK_PULSES as usize + 3)
+        );
+    }
+
+    #[test]
+    fn test_validate_frame() {
+        let frame = frame_with_pm25(41);
+        assert!(validate_header(&frame).is_ok());
+        assert!(validate_header(&frame[..2]).is_ok());
+        assert!(validate_checksum(&frame).is_ok());
+
+        let mut bad_header = frame;
+        bad_header[1] = 0x00;
+        assert!(validate_header(&bad_header).is_err());
+
+        let mut bad_checksum = frame;
+        bad_checksum[12] = 0xFF;
+        assert!(validate_checksum(&bad_checksum).is_err());
+    }
+
+    #[test]
+    fn test_validate_frame_combined() {
+        let frame = frame_with_pm25(41);
+        assert_eq!(validate_frame(&frame), Ok(()));
+
+        let mut bad_header = frame;
+        bad_header[0] = 0x00;
+        assert_eq!(
+            validate_frame(&bad_header),
+            Err(Pmsa003iError::InvalidHeader)
+        );
+
+        let mut bad_checksum = frame;
+        bad_checksum[12] = 0xFF;
+        assert_eq!(
+            validate_frame(&bad_checksum),
+            Err(Pmsa003iError::InvalidChecksum)
+        );
+
+        // A bad header is reported before a bad checksum
+        let mut both = bad_checksum;
+        both[0] = 0x00;
+        assert_eq!(validate_frame(&both), Err(Pmsa003iError::InvalidHeader));
+
+        assert_eq!(
+            validate_frame(&frame[..TOTAL_REGISTERS - 1]),
+            Err(Pmsa003iError::BufferTooShort)
+        );
+    }
+
+    #[test]
+    fn test_validate_checksum_high_bytes() {
+        // The largest possible sum, 30 * 0xFF, still fits in the checksum
+        let mut frame = [0xFF; TOTAL_REGISTERS];
+        frame[30..32].copy_from_slice(&0x1DE2u16.to_be_bytes());
+        assert!(validate_checksum(&frame).is_ok());
+
+        frame[31] = 0xE1;
+        assert!(validate_checksum(&frame).is_err());
+    }
+
+    #[test]
+    fn test_mass_concentrations() {
+        let mut frame = frame_with_pm25(41);
+        // Standard particle values should not be reported
+        frame[4..10].copy_from_slice(&[0x00, 0x01, 0x00, 0x02, 0x00, 0x03]);
+        frame[10..12].copy_from_slice(&12u16.to_be_bytes());
+        frame[14..16].copy_from_slice(&300u16.to_be_bytes());
+
+        let data = parse_data(&frame).unwrap();
+        assert_eq!(data.mass_concentrations(), (12, 41, 300));
+    }
+
+    #[test]
+    fn test_parse_frame() {
+        // Trailing bytes from the next frame are left unconsumed
+        let mut stream = [0u8; TOTAL_REGISTERS + 8];
+        stream[..TOTAL_REGISTERS].copy_from_slice(&frame_with_pm25(41));
+        stream[TOTAL_REGISTERS..TOTAL_REGISTERS + 2].copy_from_slice(&EXPECTED_HEADER);
+
+        let (data, consumed) = parse_frame(&stream).unwrap();
+        assert_eq!(data.pm2_5_env, 41);
+        assert_eq!(consumed, TOTAL_REGISTERS);
+
+        assert_eq!(
+            parse_frame(&stream[TOTAL_REGISTERS..]).unwrap_err(),
+            Pmsa003iError::BufferTooShort
+        );
+        assert_eq!(
+            parse_frame(&stream[1..]).unwrap_err(),
+            Pmsa003iError::InvalidHeader
+        );
+        let mut bad_checksum = frame_with_pm25(41);
+        bad_checksum[12] = 0xFF;
+        assert_eq!(
+            parse_frame(&bad_checksum).unwrap_err(),
+            Pmsa003iError::InvalidChecksum
+        );
+    }
+
+    #[test]
+    fn test_presence_tracker() {
+        let mut presence = PresenceTracker::new();
+        assert_eq!(presence.presence(), SensorPresence::Unknown);
+
+        // The first check always reports, then only changes are reported
+        assert_eq!(presence.update(false), Some(SensorPresence::Absent));
+        assert_eq!(presence.update(false), None);
+        assert!(!presence.is_present());
+
+        assert_eq!(presence.update(true), Some(SensorPresence::Present));
+        assert_eq!(presence.update(true), None);
+        assert!(presence.is_present());
+
+        assert_eq!(presence.update(false), Some(SensorPresence::Absent));
+        assert_eq!(presence.presence(), SensorPresence::Absent);
+    }
+
+    /// Builds a valid frame with the given cumulative particle counts,
+    /// from beyond 0.3 um up to beyond 10 um.
+    fn frame_with_particles(counts: [u16; 6]) -> [u8; TOTAL_REGISTERS] {
+        let mut frame = frame_with_pm25(0);
+        for (i, count) in counts.iter().enumerate() {
+            frame[16 + 2 * i..18 + 2 * i].copy_from_slice(&count.to_be_bytes());
+        }
+        with_checksum(frame)
+    }
+
+    #[test]
+    fn test_dominant_size_bucket() {
+        let bucket =
+            |counts| dominant_size_bucket(&parse_data(&frame_with_particles(counts)).unwrap());
+
+        // Smoke, with most particles below 0.5 um
+        assert_eq!(bucket([1200, 300, 40, 5, 1, 0]), SizeBucket::From0_3To0_5);
+        // Most particles between 1.0 and 2.5 um
+        assert_eq!(
+            bucket([900, 800, 700, 100, 20, 5]),
+            SizeBucket::From1_0To2_5
+        );
+        // Dust, with most particles between 2.5 and 5.0 um
+        assert_eq!(
+            bucket([600, 550, 500, 450, 50, 10]),
+            SizeBucket::From2_5To5_0
+        );
+        assert_eq!(bucket([40, 40, 40, 40, 40, 40]), SizeBucket::Over10);
+
+        // Ties and empty readings favor the smaller size
+        assert_eq!(bucket([0; 6]), SizeBucket::From0_3To0_5);
+        // Noisy counts which increase with size do not underflow
+        assert_eq!(bucket([10, 20, 30, 40, 50, 0]), SizeBucket::From5_0To10);
+    }
+
+    #[test]
+    fn test_pm_coarse() {
+        let mut frame = frame_with_pm25(41);
+        frame[14..16].copy_from_slice(&60u16.to_be_bytes());
+        assert_eq!(pm_coarse(&parse_data(&frame).unwrap()), 19);
+
+        // Sensor noise can put PM2.5 above PM10
+        frame[14..16].copy_from_slice(&40u16.to_be_bytes());
+        assert_eq!(pm_coarse(&parse_data(&frame).unwrap()), 0);
+    }
+
+    #[test]
+    fn test_check_pm_consistency() {
+        let mut frame = frame_with_pm25(41);
+        frame[14..16].copy_from_slice(&60u16.to_be_bytes());
+        assert_eq!(check_pm_consistency(&parse_data(&frame).unwrap()), Ok(()));
+
+        // Slightly above PM10 is within the tolerance for noise
+        frame[14..16].copy_from_slice(&(41 - PM_CONSISTENCY_TOLERANCE).to_be_bytes());
+        assert_eq!(check_pm_consistency(&parse_data(&frame).unwrap()), Ok(()));
+
+        frame[14..16].copy_from_slice(&(40 - PM_CONSISTENCY_TOLERANCE).to_be_bytes());
+        assert_eq!(
+            check_pm_consistency(&parse_data(&frame).unwrap()),
+            Err(Pmsa003iError::InconsistentPm)
+        );
+    }
+
+    #[test]
+    fn test_read_version() {
+        let mut frame = frame_with_pm25(41);
+        frame[VERSION_OFFSET] = 0x97;
+        let mut i2c = MockI2c::new(&[with_checksum(frame)]);
+
+        assert_eq!(
+            block_on(read_version(&mut i2c, TransferMode::RepeatedStart)),
+            Ok(0x97)
+        );
+
+        // Separate transactions read the same frame
+        let mut i2c = MockI2c::new(&[with_checksum(frame)]);
+        assert_eq!(
+            block_on(read_version(&mut i2c, TransferMode::WriteThenRead)),
+            Ok(0x97)
+        );
+
+        // The version is not trusted from a corrupt frame
+        assert_eq!(parse_version(&frame), Err(Pmsa003iError::InvalidChecksum));
+        assert_eq!(
+            parse_version(&frame[..VERSION_OFFSET]),
+            Err(Pmsa003iError::BufferTooShort)
+        );
+    }
+
+    #[test]
+    fn test_parse_health() {
+        let frame = frame_with_pm25(41);
+        let health = parse_health(&frame).unwrap();
+        assert!(health.is_healthy());
+        assert_eq!(health.error_code(), 0);
+        assert_eq!(health.to_string(), "OK");
+
+        let mut failing = frame;
+        failing[ERROR_CODE_OFFSET] = 0x01;
+        let failing = with_checksum(failing);
+        let health = parse_health(&failing).unwrap();
+        assert!(!health.is_healthy());
+        assert_eq!(health.error_code(), 0x01);
+        assert_eq!(health.to_string(), "error code 0x01");
+
+        // Readings in the rest of the frame are unaffected by the status
+        assert_eq!(parse_data(&failing).unwrap().pm2_5_env, 41);
+
+        // The status is not trusted from a corrupt frame
+        let mut corrupt = failing;
+        corrupt[ERROR_CODE_OFFSET] = 0x02;
+        assert_eq!(parse_health(&corrupt), Err(Pmsa003iError::InvalidChecksum));
+    }
+
+    #[test]
+    fn test_pm25_fallback() {
+        // Falls back only when env is zero but standard is not
+        assert_eq!(pm25_fallback(0, 12), Some(12));
+        assert_eq!(pm25_fallback(0, 0), None);
+        assert_eq!(pm25_fallback(41, 0), None);
+        assert_eq!(pm25_fallback(41, 45), None);
+    }
+
+    #[test]
+    fn test_pressure_correction_delta() {
+        let mut frame = frame_with_pm25(41);
+        frame[6..8].copy_from_slice(&35u16.to_be_bytes());
+        let data = parse_data(&with_checksum(frame)).unwrap();
+        assert_eq!(pressure_correction_delta(&data), 6);
+
+        // Environmental value below the standard value
+        frame[6..8].copy_from_slice(&50u16.to_be_bytes());
+        let data = parse_data(&with_checksum(frame)).unwrap();
+        assert_eq!(pressure_correction_delta(&data), -9);
+
+        // The full range of both values is representable
+        frame[6..8].copy_from_slice(&u16::MAX.to_be_bytes());
+        frame[12..14].copy_from_slice(&0u16.to_be_bytes());
+        let data = parse_data(&with_checksum(frame)).unwrap();
+        assert_eq!(pressure_correction_delta(&data), -(u16::MAX as i32));
+    }
+
+    #[test]
+    fn test_merge_readings() {
+        assert_eq!(merge_readings(41, 41, 0), Ok(41));
+        assert_eq!(merge_readings(40, 44, 5), Ok(42));
+        assert_eq!(merge_readings(44, 40, 5), Ok(42));
+        // The average rounds to the nearest whole µg/m³
+        assert_eq!(merge_readings(40, 41, 1), Ok(41));
+        assert_eq!(merge_readings(u16::MAX, u16::MAX, 0), Ok(u16::MAX));
+
+        // A difference of exactly max_diff still agrees
+        assert_eq!(merge_readings(40, 45, 5), Ok(43));
+        assert_eq!(
+            merge_readings(40, 46, 5),
+            Err(Pmsa003iError::SensorDisagreement)
+        );
+        assert_eq!(
+            merge_readings(120, 12, 5),
+            Err(Pmsa003iError::SensorDisagreement)
+        );
+    }
+
+    #[test]
+    fn test_undersized_buffer() {
+        let frame = frame_with_pm25(41);
+        assert!(validate_header(&[]).is_err());
+        assert!(validate_header(&frame[..1]).is_err());
+        assert!(validate_checksum(&[]).is_err());
+        assert!(validate_checksum(&frame[..TOTAL_REGISTERS - 1]).is_err());
+        assert!(parse_data(&frame[..TOTAL_REGISTERS - 1]).is_err());
+    }
+}
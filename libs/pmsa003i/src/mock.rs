@@ -0,0 +1,189 @@
+//! Mock module
+//!
+//! This module provides a mock I2C bus standing in for the PMSA003I,
+//! or any other device answering with fixed frames, along with a mock
+//! delay and a minimal executor, for testing sensor logic on the host
+//! without hardware. It is built for this crate's own tests, and for
+//! other crates' tests with the `test-util` feature enabled.
+
+use crate::SENSOR_I2C_ADDR;
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use std::vec::Vec;
+
+/// Op enum provides the kinds of operations seen by the mock bus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    /// Bytes written to the sensor
+    Write(Vec<u8>),
+    /// Number of bytes read from the sensor
+    Read(usize),
+}
+
+/// The MockI2c struct is a mock I2C bus that records every transaction
+/// and fills reads from a queue of scripted frames. Once only one frame
+/// remains, it is returned for every subsequent read. With no frames
+/// queued, or at any address other than the device's, which defaults
+/// to `SENSOR_I2C_ADDR`, nothing acknowledges, as if the device were
+/// absent.
+#[derive(Debug)]
+pub struct MockI2c {
+    address: u8,
+    frames: Vec<Vec<u8>>,
+    transactions: Vec<Vec<Op>>,
+}
+
+impl Default for MockI2c {
+    fn default() -> Self {
+        Self {
+            address: SENSOR_I2C_ADDR,
+            frames: Vec::new(),
+            transactions: Vec::new(),
+        }
+    }
+}
+
+impl MockI2c {
+    /// Create a new MockI2c which responds with the given frames in order.
+    ///
+    /// # Arguments
+    ///
+    /// * `frames` - Frames returned by successive reads
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut i2c = MockI2c::new(&[frame]);
+    /// let sensor_data = fetch_data(&mut i2c).await.unwrap();
+    /// ```
+    pub fn new<const N: usize>(frames: &[[u8; N]]) -> Self {
+        Self {
+            frames: frames.iter().map(|frame| frame.to_vec()).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Answer at the given address instead of `SENSOR_I2C_ADDR`, to
+    /// stand in for another device.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The 7-bit I2C address the mock device answers at
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut i2c = MockI2c::new(&[frame]).with_address(sht::SENSOR_I2C_ADDR);
+    /// ```
+    pub fn with_address(mut self, address: u8) -> Self {
+        self.address = address;
+        self
+    }
+
+    /// Queue a frame to be returned after those already queued.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - Frame returned by a later read
+    pub fn queue_frame<const N: usize>(&mut self, frame: [u8; N]) {
+        self.frames.push(frame.to_vec());
+    }
+
+    /// The operations of every acknowledged transaction, in order.
+    pub fn transactions(&self) -> &[Vec<Op>] {
+        &self.transactions
+    }
+
+    /// The bytes of every write to the sensor, in order, such as the
+    /// register address written before each read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// fetch_data(&mut i2c).await.unwrap();
+    /// assert_eq!(i2c.written(), [[0x00]]);
+    /// ```
+    pub fn written(&self) -> Vec<&[u8]> {
+        self.transactions
+            .iter()
+            .flatten()
+            .filter_map(|op| match op {
+                Op::Write(bytes) => Some(bytes.as_slice()),
+                Op::Read(_) => None,
+            })
+            .collect()
+    }
+}
+
+impl ErrorType for MockI2c {
+    type Error = ErrorKind;
+}
+
+impl I2c for MockI2c {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if address != self.address || self.frames.is_empty() {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+
+        let mut ops = Vec::new();
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => ops.push(Op::Write(bytes.to_vec())),
+                Operation::Read(buffer) => {
+                    let frame = if self.frames.len() > 1 {
+                        self.frames.remove(0)
+                    } else {
+                        self.frames[0].clone()
+                    };
+                    buffer.copy_from_slice(&frame[..buffer.len()]);
+                    ops.push(Op::Read(buffer.len()));
+                }
+            }
+        }
+        self.transactions.push(ops);
+        Ok(())
+    }
+}
+
+/// The MockDelay struct is a mock delay that returns immediately,
+/// recording each requested delay.
+#[derive(Debug, Default)]
+pub struct MockDelay {
+    /// Every requested delay in nanoseconds, in order
+    pub delays_ns: Vec<u32>,
+}
+
+impl DelayNs for MockDelay {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.delays_ns.push(ns);
+    }
+}
+
+/// Drives a future that is expected to complete without waiting,
+/// which holds for everything run against the mock bus and delay.
+///
+/// # Panics
+///
+/// If the future does not complete on its first poll.
+///
+/// # Examples
+///
+/// ```
+/// let mut i2c = MockI2c::new(&[frame]);
+/// let sensor_data = block_on(fetch_data(&mut i2c)).unwrap();
+/// ```
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    match future.as_mut().poll(&mut context) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("future did not complete"),
+    }
+}
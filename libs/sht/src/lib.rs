@@ -0,0 +1,305 @@
+//! SHT library
+//!
+//! This library provides temperature and relative humidity measurement
+//! with the Sensirion SHT3x and SHT4x sensors. Both families share the
+//! default I2C address and measurement frame format, differing only in
+//! the measurement command and humidity conversion. Data is read over any
+//! I2C bus implementing the `embedded-hal-async` I2C trait, so the sensor
+//! can share a bus with other devices by borrowing it for each read.
+
+#![no_std]
+
+#[cfg(feature = "std")]
+extern crate std;
+
+use core::fmt;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::i2c::{Error as _, ErrorKind, I2c};
+
+/// Default I2C address of both the SHT3x and SHT4x, with the SHT3x
+/// ADDR pin tied low.
+pub const SENSOR_I2C_ADDR: u8 = 0x44;
+/// Length of a measurement frame: temperature and humidity words, each
+/// followed by a CRC byte.
+pub const FRAME_LEN: usize = 6;
+/// Time to wait for a high repeatability measurement to complete. This
+/// covers the worst case of both families, 15.5ms for the SHT3x and
+/// 8.3ms for the SHT4x.
+pub const MEASUREMENT_TIME_MS: u32 = 16;
+
+const CRC_POLYNOMIAL: u8 = 0x31;
+const CRC_INIT: u8 = 0xFF;
+
+/// Model enum provides the supported sensor families.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    Sht3x,
+    Sht4x,
+}
+
+impl Model {
+    /// Gets the command starting a high repeatability measurement. The
+    /// SHT3x command is the single shot variant without clock stretching.
+    const fn measure_command(self) -> &'static [u8] {
+        match self {
+            Model::Sht3x => &[0x24, 0x00],
+            Model::Sht4x => &[0xFD],
+        }
+    }
+
+    /// Converts a raw humidity word to percent relative humidity. The
+    /// SHT4x formula can fall slightly outside 0-100%, so the result is
+    /// clamped, as recommended by the datasheet.
+    fn humidity(self, raw: u16) -> f32 {
+        let fraction = raw as f32 / 65535.0;
+        let humidity = match self {
+            Model::Sht3x => 100.0 * fraction,
+            Model::Sht4x => -6.0 + 125.0 * fraction,
+        };
+        humidity.clamp(0.0, 100.0)
+    }
+}
+
+/// The Measurement struct holds a single reading from the sensor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    /// Temperature in degrees Celsius
+    pub temperature_c: f32,
+    /// Relative humidity in percent
+    pub humidity: f32,
+}
+
+/// ShtError enum provides the reasons a measurement may fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShtError {
+    /// A word did not match the CRC byte following it
+    InvalidCrc,
+    /// Communication with the sensor failed
+    I2c(ErrorKind),
+}
+
+impl ShtError {
+    /// Whether the error indicates that no sensor is connected, i.e.
+    /// nothing acknowledged its address. Since the humidity sensor is
+    /// optional, callers can use this to skip humidity quietly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// match sht::measure(&mut i2c, &mut Delay, Model::Sht4x).await {
+    ///     Ok(measurement) => Some(measurement.humidity),
+    ///     Err(e) if e.is_absent() => None,
+    ///     Err(e) => {
+    ///         hprintln!("Error reading humidity: {}", e);
+    ///         None
+    ///     }
+    /// }
+    /// ```
+    pub fn is_absent(&self) -> bool {
+        matches!(self, ShtError::I2c(ErrorKind::NoAcknowledge(_)))
+    }
+}
+
+impl fmt::Display for ShtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShtError::InvalidCrc => f.write_str("CRC validation failed"),
+            ShtError::I2c(kind) => write!(f, "I2C error: {}", kind),
+        }
+    }
+}
+
+/// Calculates the CRC-8 used by Sensirion sensors, with polynomial 0x31
+/// and initial value 0xFF.
+///
+/// # Arguments
+///
+/// * `data` - Bytes covered by the CRC
+///
+/// # Returns
+///
+/// The calculated CRC byte.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(crc8(&[0xBE, 0xEF]), 0x92);
+/// ```
+pub fn crc8(data: &[u8]) -> u8 {
+    let mut crc = CRC_INIT;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ CRC_POLYNOMIAL
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Parses a measurement frame, validating the CRC of each word.
+///
+/// # Arguments
+///
+/// * `frame` - Bytes read from the sensor after a measurement
+/// * `model` - The sensor family which produced the frame
+///
+/// # Returns
+///
+/// Result of Ok(Measurement) or an Err of `ShtError::InvalidCrc`.
+///
+/// # Examples
+///
+/// ```
+/// let measurement = parse_measurement(&frame, Model::Sht4x)?;
+/// hprintln!("RH: {}%", measurement.humidity);
+/// ```
+pub fn parse_measurement(frame: &[u8; FRAME_LEN], model: Model) -> Result<Measurement, ShtError> {
+    let mut words = [0u16; 2];
+    for (word, chunk) in words.iter_mut().zip(frame.chunks_exact(3)) {
+        if crc8(&chunk[..2]) != chunk[2] {
+            return Err(ShtError::InvalidCrc);
+        }
+        *word = u16::from_be_bytes([chunk[0], chunk[1]]);
+    }
+
+    Ok(Measurement {
+        temperature_c: -45.0 + 175.0 * (words[0] as f32 / 65535.0),
+        humidity: model.humidity(words[1]),
+    })
+}
+
+/// Takes a high repeatability measurement, waiting for it to complete
+/// before reading the result. The bus is only borrowed for the duration
+/// of the call, so it may be shared with other devices.
+///
+/// # Arguments
+///
+/// * `i2c` - An async I2C instance, such as an Embassy Async I2C
+/// * `delay` - An async delay provider
+/// * `model` - The sensor family connected to the bus
+///
+/// # Returns
+///
+/// Result of Ok(Measurement) or an Err with the reason the measurement
+/// failed. A sensor which is not connected gives an I2C error of
+/// `ErrorKind::NoAcknowledge`.
+///
+/// # Examples
+///
+/// ```
+/// match sht::measure(&mut i2c, &mut Delay, Model::Sht4x).await {
+///     Ok(measurement) => hprintln!("RH: {}%", measurement.humidity),
+///     Err(e) => hprintln!("Error reading humidity: {}", e),
+/// }
+/// ```
+pub async fn measure<I: I2c, D: DelayNs>(
+    i2c: &mut I,
+    delay: &mut D,
+    model: Model,
+) -> Result<Measurement, ShtError> {
+    i2c.write(SENSOR_I2C_ADDR, model.measure_command())
+        .await
+        .map_err(|e| ShtError::I2c(e.kind()))?;
+    delay.delay_ms(MEASUREMENT_TIME_MS).await;
+
+    let mut frame = [0u8; FRAME_LEN];
+    i2c.read(SENSOR_I2C_ADDR, &mut frame)
+        .await
+        .map_err(|e| ShtError::I2c(e.kind()))?;
+    parse_measurement(&frame, model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_async::i2c::NoAcknowledgeSource;
+    use pmsa003i::mock::{block_on, MockDelay, MockI2c};
+
+    /// Builds a frame with valid CRCs from raw temperature and humidity words.
+    fn frame(temperature: u16, humidity: u16) -> [u8; FRAME_LEN] {
+        let mut frame = [0u8; FRAME_LEN];
+        for (chunk, word) in frame.chunks_exact_mut(3).zip([temperature, humidity]) {
+            chunk[..2].copy_from_slice(&word.to_be_bytes());
+            chunk[2] = crc8(&chunk[..2]);
+        }
+        frame
+    }
+
+    #[test]
+    fn test_crc8() {
+        // Example from the SHT3x and SHT4x datasheets
+        assert_eq!(crc8(&[0xBE, 0xEF]), 0x92);
+        assert_eq!(crc8(&[]), CRC_INIT);
+    }
+
+    #[test]
+    fn test_parse_measurement() {
+        // 0x6666 is 40% of full scale
+        let frame = frame(0x6666, 0x6666);
+        let sht4x = parse_measurement(&frame, Model::Sht4x).unwrap();
+        assert!((sht4x.temperature_c - 25.0).abs() < 0.01);
+        assert!((sht4x.humidity - 44.0).abs() < 0.01);
+
+        let sht3x = parse_measurement(&frame, Model::Sht3x).unwrap();
+        assert!((sht3x.temperature_c - 25.0).abs() < 0.01);
+        assert!((sht3x.humidity - 40.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_humidity_clamped() {
+        let dry = parse_measurement(&frame(0x6666, 0x0000), Model::Sht4x).unwrap();
+        assert_eq!(dry.humidity, 0.0);
+        let wet = parse_measurement(&frame(0x6666, 0xFFFF), Model::Sht4x).unwrap();
+        assert_eq!(wet.humidity, 100.0);
+    }
+
+    #[test]
+    fn test_invalid_crc() {
+        let mut bad_temperature = frame(0x6666, 0x6666);
+        bad_temperature[2] ^= 0x01;
+        assert_eq!(
+            parse_measurement(&bad_temperature, Model::Sht4x),
+            Err(ShtError::InvalidCrc)
+        );
+
+        let mut bad_humidity = frame(0x6666, 0x6666);
+        bad_humidity[5] ^= 0x01;
+        assert_eq!(
+            parse_measurement(&bad_humidity, Model::Sht4x),
+            Err(ShtError::InvalidCrc)
+        );
+    }
+
+    #[test]
+    fn test_measure() {
+        let mut i2c = MockI2c::new(&[frame(0x6666, 0x6666)]).with_address(SENSOR_I2C_ADDR);
+        let mut delay = MockDelay::default();
+        let measurement = block_on(measure(&mut i2c, &mut delay, Model::Sht3x)).unwrap();
+        assert!((measurement.humidity - 40.0).abs() < 0.01);
+        assert_eq!(i2c.written(), [[0x24, 0x00]]);
+        assert_eq!(delay.delays_ns, [MEASUREMENT_TIME_MS * 1_000_000]);
+
+        let measurement = block_on(measure(&mut i2c, &mut delay, Model::Sht4x)).unwrap();
+        assert!((measurement.humidity - 44.0).abs() < 0.01);
+        assert_eq!(i2c.written().last().unwrap(), &[0xFD]);
+    }
+
+    #[test]
+    fn test_measure_absent_sensor() {
+        let mut i2c = MockI2c::default().with_address(SENSOR_I2C_ADDR);
+        let result = block_on(measure(&mut i2c, &mut MockDelay::default(), Model::Sht4x));
+        assert!(result.is_err_and(|e| e.is_absent()));
+        assert_eq!(
+            result,
+            Err(ShtError::I2c(ErrorKind::NoAcknowledge(
+                NoAcknowledgeSource::Address
+            )))
+        );
+        assert!(!ShtError::InvalidCrc.is_absent());
+        assert!(!ShtError::I2c(ErrorKind::Bus).is_absent());
+    }
+}
@@ -0,0 +1,8 @@
+/* Memory layout of the STM32F303VC. The last 4K of the 256K flash is
+   left out of the image, as it is reserved for the `flash-log` feature's
+   circular log of readings (see LOG_START in src/flash_log.rs). */
+MEMORY
+{
+    FLASH : ORIGIN = 0x08000000, LENGTH = 252K
+    RAM   : ORIGIN = 0x20000000, LENGTH =  40K
+}
@@ -0,0 +1,198 @@
+//! Flash log
+//!
+//! Circular log of readings stored in the MCU's onboard flash, for
+//! unattended data collection without a host. Each reading is stored
+//! using the record format from `aqi::LogRecord`. Once the log region
+//! is full, the oldest page of records is erased to make room.
+
+use aqi::{LogRecord, ReadingReport, Verbosity, RECORD_SIZE};
+use core::fmt;
+use embassy_time::Instant;
+use embedded_storage::nor_flash::NorFlash;
+
+/// Start of the flash region reserved for the log, as an offset from
+/// the start of flash. The last 4KB, or two 2KB pages, of the 256KB
+/// flash on the STM32F303VC are used. `memory.x` limits the firmware
+/// image to the first 252KB, so the linker fails rather than letting
+/// the image grow into the log.
+const LOG_START: u32 = 0x3_F000;
+/// End of the flash region reserved for the log.
+const LOG_END: u32 = 0x4_0000;
+
+/// The FlashLog struct appends records to the log region of flash,
+/// resuming after the newest stored record.
+pub struct FlashLog<F: NorFlash> {
+    flash: F,
+    next_offset: u32,
+    next_sequence: u32,
+}
+
+impl<F: NorFlash> FlashLog<F> {
+    /// Open the log, scanning the stored records to find where
+    /// to resume writing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut log = FlashLog::new(Flash::new_blocking(p.FLASH))?;
+    /// ```
+    pub fn new(mut flash: F) -> Result<Self, F::Error> {
+        // Find the offset and sequence number of the newest record
+        let mut newest: Option<(u32, u32)> = None;
+        let mut offset = LOG_START;
+        while offset < LOG_END {
+            if let Some(record) = read_record(&mut flash, offset)? {
+                match newest {
+                    Some((_, sequence)) if sequence >= record.sequence => {}
+                    _ => newest = Some((offset, record.sequence)),
+                }
+            }
+            offset += RECORD_SIZE as u32;
+        }
+
+        let (mut next_offset, next_sequence) = match newest {
+            Some((offset, sequence)) => (advance(offset), sequence.wrapping_add(1)),
+            None => (LOG_START, 0),
+        };
+        // Skip past any partially written records following the newest
+        // one, as they cannot be written over until their page is erased
+        while !is_page_start::<F>(next_offset) && !is_erased(&mut flash, next_offset)? {
+            next_offset = advance(next_offset);
+        }
+
+        Ok(Self {
+            flash,
+            next_offset,
+            next_sequence,
+        })
+    }
+
+    /// Append a reading to the log, erasing the oldest page of
+    /// records first if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp_ms` - Time of the reading in milliseconds since boot
+    /// * `pm25` - The PM 2.5 value from the sensor
+    /// * `aqi` - The calculated AQI
+    pub fn append(&mut self, timestamp_ms: u32, pm25: u16, aqi: u16) -> Result<(), F::Error> {
+        if is_page_start::<F>(self.next_offset) {
+            self.flash
+                .erase(self.next_offset, self.next_offset + F::ERASE_SIZE as u32)?;
+        }
+        let record = LogRecord {
+            sequence: self.next_sequence,
+            timestamp_ms,
+            pm25,
+            aqi,
+        };
+        self.flash.write(self.next_offset, &record.encode())?;
+        self.next_offset = advance(self.next_offset);
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        Ok(())
+    }
+
+    /// Print every stored record, from oldest to newest. Each record
+    /// holds an AQI, so records are printed at `Verbosity::Quiet`.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - Where the records are printed
+    pub fn dump<O: aqi::Output>(&mut self, output: &mut O) -> Result<(), F::Error> {
+        output.log(Verbosity::Normal, format_args!("Stored readings:"));
+        let mut offset = self.next_offset;
+        loop {
+            if let Some(record) = read_record(&mut self.flash, offset)? {
+                output.log(
+                    Verbosity::Quiet,
+                    format_args!(
+                        "#{} at {} ms: PM2.5 concentration: {} µg/m³, AQI: {}",
+                        record.sequence, record.timestamp_ms, record.pm25, record.aqi
+                    ),
+                );
+            }
+            offset = advance(offset);
+            if offset == self.next_offset {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Wraps another Output, storing each reading to the flash
+/// log before passing it on. Without a log, e.g. if it could not be
+/// opened, readings are only passed on.
+pub struct FlashLogOutput<O: aqi::Output, F: NorFlash> {
+    output: O,
+    log: Option<FlashLog<F>>,
+}
+
+impl<O: aqi::Output, F: NorFlash> FlashLogOutput<O, F> {
+    /// Create a new FlashLogOutput.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut output = FlashLogOutput::new(output, Some(log));
+    /// ```
+    pub fn new(output: O, log: Option<FlashLog<F>>) -> Self {
+        Self { output, log }
+    }
+}
+
+impl<O: aqi::Output, F: NorFlash> aqi::Output for FlashLogOutput<O, F> {
+    fn verbosity(&self) -> Verbosity {
+        self.output.verbosity()
+    }
+
+    fn write_line(&mut self, args: fmt::Arguments) {
+        self.output.write_line(args);
+    }
+
+    fn log_reading(&mut self, reading: &ReadingReport) {
+        if let Some(log) = &mut self.log {
+            let timestamp_ms = Instant::now().as_millis() as u32;
+            if let Err(e) = log.append(timestamp_ms, reading.pm25, reading.aqi) {
+                self.output.log(
+                    Verbosity::Quiet,
+                    format_args!("Error writing reading to flash: {:?}", e),
+                );
+            }
+        }
+        self.output.log_reading(reading);
+    }
+
+    fn log_hold_summary(&mut self, readings: usize, average_aqi: Option<u16>) {
+        self.output.log_hold_summary(readings, average_aqi);
+    }
+}
+
+/// Read and decode the record at the given offset.
+fn read_record<F: NorFlash>(flash: &mut F, offset: u32) -> Result<Option<LogRecord>, F::Error> {
+    let mut bytes = [0u8; RECORD_SIZE];
+    flash.read(offset, &mut bytes)?;
+    Ok(LogRecord::decode(&bytes))
+}
+
+/// Whether the record slot at the given offset is erased.
+fn is_erased<F: NorFlash>(flash: &mut F, offset: u32) -> Result<bool, F::Error> {
+    let mut bytes = [0u8; RECORD_SIZE];
+    flash.read(offset, &mut bytes)?;
+    Ok(bytes.iter().all(|&byte| byte == 0xFF))
+}
+
+/// Whether the given offset is the start of an erasable page.
+fn is_page_start<F: NorFlash>(offset: u32) -> bool {
+    (offset - LOG_START).is_multiple_of(F::ERASE_SIZE as u32)
+}
+
+/// The offset of the record slot following the given one,
+/// wrapping back to the start of the log region.
+fn advance(offset: u32) -> u32 {
+    let next = offset + RECORD_SIZE as u32;
+    if next >= LOG_END {
+        LOG_START
+    } else {
+        next
+    }
+}
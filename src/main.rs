@@ -14,6 +14,45 @@
 //! measurement, as defined by the EPA, in addition to an exact AQI calculation
 //! printed to a serial debug output. Individual AQI measurements may be
 //! triggered by pressing the onboard user button on the Discovery board.
+//! Holding the button takes a new reading roughly every second and reports
+//! the average AQI across the hold.
+//!
+//! Holding the button through boot for a few seconds prints a dump of the
+//! sensor registers for field debugging.
+//!
+//! With the `demo` feature enabled, sensor readings are replaced with a
+//! synthetic reading for each AQI range in turn, advancing on each button
+//! press. This allows all LED states to be shown without a working sensor.
+//!
+//! With the `continuous` feature enabled, the sensor is instead read
+//! continuously without the button, and readings are only reported and
+//! shown on the LEDs when the AQI changes significantly.
+//!
+//! With the `stability` feature enabled, each button press instead takes a
+//! fixed number of readings and reports their mean and variance.
+//!
+//! With the `flash-log` feature enabled, each reading is also stored to a
+//! circular log in the onboard flash, and the stored log is printed on boot.
+//!
+//! With the `humidity` feature enabled, an SHT3x or SHT4x sensor on the same
+//! I2C bus is read alongside the PMSA003I, and the PM2.5 concentration is
+//! corrected for relative humidity. If no humidity sensor responds, readings
+//! are reported uncorrected.
+//!
+//! With the `pm10` feature enabled, the LEDs show the overall AQI, the worse
+//! of the PM2.5 and PM10 AQI, rather than the PM2.5 AQI alone.
+//!
+//! With the `panic-sos` feature enabled, a panic flashes SOS on the LEDs
+//! until the board is power cycled, rather than being reported to a debug
+//! probe.
+//!
+//! Readings, status messages and errors all go through the `aqi::Output`
+//! trait. By default they are printed over semihosting. With the `rtt`
+//! feature enabled, they are printed as plain text on RTT channel 0, and
+//! with the `defmt` feature enabled, they are logged through defmt. If
+//! both are enabled, `rtt` takes precedence. With the `usb` feature
+//! enabled, they are printed over USB serial, so that no debug probe is
+//! needed.
 //!
 //!
 //! # Examples
@@ -22,29 +61,479 @@
 //! $ cargo build && cargo run
 //! Attempting to ping device at address 0x12
 //! Device responded to ping
-//! PM2.5 concentration: 41 µg/m³
-//! Calculated AQI: 115, Color: Orange
+//! #0 PM2.5 concentration: 41 µg/m³
+//! Calculated AQI: 115 (+0 since last), Color: Orange
+//!
+//! #1 PM2.5 concentration: 33 µg/m³
+//! Calculated AQI: 96 (-19 since last), Color: Yellow
 //!
-//! PM2.5 concentration: 33 µg/m³
-//! Calculated AQI: 96, Color: Yellow
+//! #2 PM2.5 concentration: 33 µg/m³
+//! Calculated AQI: 96 (+0 since last), Color: Yellow
+//! #3 PM2.5 concentration: 35 µg/m³
+//! Calculated AQI: 97 (+1 since last), Color: Yellow
+//! Average AQI over 2 readings: 97
 //! ```
 
 #![no_std]
 #![no_main]
 
-mod pmsa003i;
+#[cfg(feature = "flash-log")]
+mod flash_log;
+#[cfg(feature = "usb")]
+mod usb_output;
+
+#[cfg(all(feature = "usb", any(feature = "rtt", feature = "defmt")))]
+compile_error!("The `usb` feature cannot be combined with the `rtt` or `defmt` features");
+
+#[cfg(all(feature = "remote", feature = "demo"))]
+compile_error!("The `remote` feature cannot be combined with the `demo` feature");
 
-use crate::pmsa003i::Pmsa003iData;
 use aqi::*;
+// The GPIO Output shadows the glob import, so the trait is brought into
+// scope separately for its methods
+use aqi::Output as _;
+#[cfg(not(any(feature = "rtt", feature = "defmt", feature = "usb")))]
 use cortex_m_semihosting::hprintln;
 use embassy_executor::Spawner;
+use embassy_futures::select::{select, Either};
 use embassy_stm32::bind_interrupts;
+#[cfg(all(feature = "continuous", not(feature = "remote")))]
 use embassy_stm32::exti::ExtiInput;
-use embassy_stm32::gpio::{Level, Output, Pull, Speed};
-use embassy_stm32::i2c::{Config, I2c};
-use embassy_stm32::peripherals::{I2C2, PE10, PE11, PE12, PE13, PE14, PE15, PE8, PE9};
-use embassy_stm32::time::Hertz;
-use {defmt_rtt as _, panic_probe as _};
+#[cfg(feature = "flash-log")]
+use embassy_stm32::flash::Flash;
+use embassy_stm32::gpio::{Level, Output, OutputOpenDrain, Speed};
+use embassy_stm32::i2c::{Error, I2c};
+use embassy_stm32::mode::Async;
+use embassy_stm32::peripherals::{I2C2, PA10, PA9, PE10, PE11, PE12, PE13, PE14, PE15, PE8, PE9};
+use embassy_time::{Delay, Timer};
+use embedded_hal::i2c::Error as _;
+// With the `panic-sos` feature, panics flash SOS on the LEDs instead
+#[cfg(not(feature = "panic-sos"))]
+use panic_probe as _;
+use pmsa003i::{
+    Pmsa003iData, Pmsa003iError, PresenceTracker, SensorPresence, TransferMode, TOTAL_REGISTERS,
+};
+// With the `usb` feature, all messages are printed over USB, as
+// semihosting halts the core when no debugger is attached
+#[cfg(feature = "usb")]
+use usb_output::usb_println as hprintln;
+// Likewise with `rtt` or `defmt`, status messages and errors go to the
+// same place as readings rather than to semihosting
+#[cfg(all(feature = "defmt", not(feature = "rtt")))]
+use defmt_println as hprintln;
+#[cfg(feature = "rtt")]
+use rtt_target::rprintln as hprintln;
+
+// With the `rtt` feature, rtt-target provides the defmt logger instead
+#[cfg(not(feature = "rtt"))]
+use defmt_rtt as _;
+
+/// Logs a line through defmt, in place of `hprintln!` when the `defmt`
+/// feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// defmt_println!("Device responded to ping");
+/// ```
+#[cfg(all(feature = "defmt", not(feature = "rtt")))]
+macro_rules! defmt_println {
+    ($($arg:tt)*) => {
+        defmt::info!("{}", defmt::Display2Format(&format_args!($($arg)*)))
+    };
+}
+#[cfg(all(feature = "defmt", not(feature = "rtt")))]
+pub(crate) use defmt_println;
+
+/// Creates the user button input from the board peripherals.
+///
+/// The Discovery board wires the button to PA0, which is read using
+/// EXTI line 0, with an external pull-down so the pin reads high while
+/// pressed. To port to a board with the button wired elsewhere, this is
+/// the only place which needs to change. Update the pin, the EXTI line
+/// (whose number must match the pin number, e.g. PC13 with EXTI13),
+/// and the pull to suit the button's wiring.
+///
+/// # Examples
+///
+/// ```
+/// let p = embassy_stm32::init(Default::default());
+/// let mut button = init_button!(p);
+/// ```
+macro_rules! init_button {
+    ($p:ident) => {
+        embassy_stm32::exti::ExtiInput::new($p.PA0, $p.EXTI0, embassy_stm32::gpio::Pull::Down)
+    };
+}
+
+/// Creates the ReadTrigger for the given TriggerSource from the board
+/// peripherals, taking only the pins the selected source needs. The
+/// user button is also returned when the trigger does not use it, so
+/// that it is free for switching the display mode.
+///
+/// The data-ready line is read on PD1, using EXTI line 1, with a
+/// pull-down so that an unconnected line never triggers a read. As
+/// with `init_button`, update the pin and EXTI line together to move it.
+///
+/// # Examples
+///
+/// ```
+/// let p = embassy_stm32::init(Default::default());
+/// let (mut trigger, mut mode_button) = init_read_trigger!(p, READ_TRIGGER);
+/// ```
+#[cfg(all(feature = "continuous", not(feature = "remote")))]
+macro_rules! init_read_trigger {
+    ($p:ident, $source:expr) => {
+        match $source {
+            TriggerSource::Button => (ReadTrigger::Button(init_button!($p)), None),
+            TriggerSource::Timer => (ReadTrigger::Timer, Some(init_button!($p))),
+            TriggerSource::DataReady => (
+                ReadTrigger::DataReady(ExtiInput::new(
+                    $p.PD1,
+                    $p.EXTI1,
+                    embassy_stm32::gpio::Pull::Down,
+                )),
+                Some(init_button!($p)),
+            ),
+        }
+    };
+}
+
+/// Maximum number of readings averaged while the button is held.
+/// Once exceeded, the oldest readings are dropped from the average.
+const HOLD_SAMPLES: usize = 30;
+
+/// Number of readings in the rolling average which is reported. As a
+/// remote display, concentrations are taken as already averaged by
+/// their source, so the average is bypassed and each one is reported
+/// as it arrives.
+const AVERAGE_SAMPLES: usize = if cfg!(feature = "remote") {
+    1
+} else {
+    HOLD_SAMPLES
+};
+
+/// Delay between readings while the button is held. The PMSA003I
+/// updates its output roughly once per second, so reading faster
+/// than this would mostly return repeated frames.
+const SAMPLE_INTERVAL_MS: u64 = 1000;
+
+/// What triggers each sensor read when reading continuously. Use
+/// `TriggerSource::DataReady` if the sensor's data-ready line is wired
+/// to PD1, so each read gets a fresh frame without delay.
+#[cfg(all(feature = "continuous", not(feature = "remote")))]
+const READ_TRIGGER: TriggerSource = TriggerSource::Timer;
+
+/// Change in AQI which must be exceeded for a reading to be reported
+/// when reading continuously. Crossing into a different category is
+/// always reported, regardless of this threshold.
+#[cfg(feature = "continuous")]
+const REPORT_MIN_DELTA: u16 = 5;
+
+/// Shortest time over which the rate of change in AQI is measured
+/// when reading continuously, for the warning when it rises rapidly.
+#[cfg(feature = "continuous")]
+const RATE_WINDOW_MS: u32 = 60_000;
+
+/// LEDs blinked to show the display mode after the button switches
+/// it when reading continuously: the four diagonal LEDs of the compass.
+#[cfg(feature = "continuous")]
+const MODE_CUE_LEDS: &[LedRole] = &[
+    LedRole::Orange1,
+    LedRole::Blue2,
+    LedRole::Orange2,
+    LedRole::Blue1,
+];
+
+/// How long the LEDs stay on, and then off, for each blink of the
+/// display mode cue.
+#[cfg(feature = "continuous")]
+const MODE_CUE_BLINK_MS: u64 = 150;
+
+/// AQI at or above which the alarm is raised. Once raised, the alarm
+/// LEDs stay lit between readings, even if the AQI drops, until the
+/// alarm is acknowledged with the next button press. The default is
+/// the bottom of the Very Unhealthy range.
+#[cfg(not(feature = "continuous"))]
+const ALARM_AQI: u16 = 201;
+
+/// Longest time between two button presses for them to count as a
+/// double press, which resets the session statistics. The first press
+/// still takes a reading, and the button is watched for the second
+/// press while waiting between readings.
+#[cfg(not(feature = "continuous"))]
+const DOUBLE_PRESS_MS: u32 = 1500;
+
+/// How long the button must be held to park: LEDs off and sensor
+/// asleep until the next press. Readings stop once this is reached, so
+/// it also limits how long a hold can average over. The sensor's SET
+/// pin must be wired to PD9 for it to sleep.
+#[cfg(not(feature = "continuous"))]
+const PARK_HOLD_MS: u32 = 10_000;
+
+/// Number of the most recent notable events, such as category changes
+/// and alarms, kept for printing on a double press.
+#[cfg(not(feature = "continuous"))]
+const EVENT_LOG_SIZE: usize = 32;
+
+/// How long the reset LEDs flash to confirm the session statistics
+/// were reset.
+#[cfg(not(feature = "continuous"))]
+const RESET_FLASH_MS: u64 = 200;
+
+/// LEDs flashed to confirm the session statistics were reset: the
+/// eastern half of the compass, from the north LED to the south LED.
+#[cfg(not(feature = "continuous"))]
+const RESET_FLASH_LEDS: &[LedRole] = &[
+    LedRole::Red1,
+    LedRole::Orange1,
+    LedRole::Green1,
+    LedRole::Blue2,
+    LedRole::Red2,
+];
+
+/// How long the last PM2.5 concentration is shown in binary after a
+/// press with the `binary` feature enabled, unless the button is
+/// pressed again sooner.
+#[cfg(not(feature = "continuous"))]
+const BINARY_DISPLAY_MS: u64 = 3000;
+
+/// LEDs alternated in place of the binary display when the PM2.5
+/// concentration is above 255: the four cardinal and then the four
+/// diagonal LEDs of the compass. Every fixed set of lit LEDs shows some
+/// value up to 255, so an overflow is shown by the alternation.
+#[cfg(not(feature = "continuous"))]
+const BINARY_OVERFLOW_LEDS: [&[LedRole]; 2] = [
+    &[
+        LedRole::Red1,
+        LedRole::Green1,
+        LedRole::Red2,
+        LedRole::Green2,
+    ],
+    &[
+        LedRole::Orange1,
+        LedRole::Blue2,
+        LedRole::Orange2,
+        LedRole::Blue1,
+    ],
+];
+
+/// How long each half of the binary overflow pattern stays lit.
+#[cfg(not(feature = "continuous"))]
+const BINARY_OVERFLOW_STEP_MS: u64 = 250;
+
+/// LEDs lit while the alarm is raised: the four cardinal LEDs of the
+/// compass.
+#[cfg(not(feature = "continuous"))]
+const ALARM_LEDS: &[LedRole] = &[
+    LedRole::Red1,
+    LedRole::Green1,
+    LedRole::Red2,
+    LedRole::Green2,
+];
+
+/// LEDs flashing SOS after a panic with the `panic-sos` feature. This
+/// is the only signal using every LED.
+#[cfg(feature = "panic-sos")]
+const PANIC_LEDS: &[LedRole] = &LedRole::ALL;
+
+/// Core clock frequency, for timing busy-wait delays after a panic,
+/// when the executor and timers can no longer be relied on. USB needs
+/// the 72MHz clock set by `usb_output::clock_config`, and otherwise the
+/// core runs from the 8MHz internal oscillator.
+#[cfg(feature = "panic-sos")]
+const CORE_CLOCK_HZ: u32 = if cfg!(feature = "usb") {
+    72_000_000
+} else {
+    8_000_000
+};
+
+/// Timeout for a single I2C transaction. The PMSA003I may stretch the
+/// clock while preparing a frame, so this is kept well above the ~3ms
+/// needed to transfer a 32 byte frame at 100kHz. If a transaction does
+/// exceed it, the read path reports a timeout rather than a NACK.
+#[cfg(not(any(all(feature = "continuous", feature = "demo"), feature = "remote")))]
+const I2C_TIMEOUT_MS: u64 = 1000;
+
+/// How long the button must be held through boot to print a dump of
+/// the sensor registers before readings start.
+#[cfg(not(any(feature = "continuous", feature = "demo")))]
+const DIAGNOSTICS_PRESS_MS: u32 = 3000;
+
+/// Delay between checks of the button while timing a long press.
+#[cfg(not(any(feature = "continuous", feature = "demo")))]
+const BUTTON_POLL_MS: u64 = 50;
+
+/// Delay between pings while waiting for a missing sensor to appear.
+#[cfg_attr(any(feature = "demo", feature = "remote"), allow(dead_code))]
+const SENSOR_RETRY_MS: u64 = 5000;
+
+/// LEDs lit while the sensor is missing: both blue LEDs, which no AQI
+/// range lights on their own.
+#[cfg_attr(any(feature = "demo", feature = "remote"), allow(dead_code))]
+const SENSOR_MISSING_LEDS: &[LedRole] = &[LedRole::Blue1, LedRole::Blue2];
+
+/// I2C address pinged to check that the sensor is present. Behind an
+/// I2C multiplexer, this can be pointed at the multiplexer instead,
+/// since the sensor only answers once its channel has been selected.
+/// The ping is address-only, so it leaves the multiplexer's channel
+/// selection as it was.
+#[cfg_attr(any(feature = "demo", feature = "remote"), allow(dead_code))]
+const PING_ADDR: u8 = pmsa003i::SENSOR_I2C_ADDR;
+
+/// Whether to ping the sensor at startup, waiting for it to appear
+/// before taking readings. Set to false on setups where the sensor
+/// cannot answer yet at startup, such as behind a multiplexer whose
+/// channel must be selected first. The main loop then starts
+/// immediately, and the ping is deferred to the check made before each
+/// reading rather than skipped altogether.
+#[cfg_attr(any(feature = "demo", feature = "remote"), allow(dead_code))]
+const STARTUP_PING: bool = true;
+
+/// Whether to take and display an initial reading at boot, once the
+/// sensor has warmed up, rather than waiting for the first reading.
+/// This holds up the first press by up to `WARMUP_MS`, so is off by
+/// default.
+#[cfg_attr(any(feature = "demo", feature = "remote"), allow(dead_code))]
+const BOOT_READING: bool = false;
+
+/// Time after power-on before the sensor's readings are stable. The
+/// datasheet recommends letting the fan run for at least 30 seconds.
+#[cfg_attr(any(feature = "demo", feature = "remote"), allow(dead_code))]
+const WARMUP_MS: u32 = pmsa003i::WARMUP_MS;
+
+/// Time each LED of the measuring spinner stays lit during warm-up.
+#[cfg_attr(any(feature = "demo", feature = "remote"), allow(dead_code))]
+const MEASURING_STEP_MS: u32 = 125;
+
+/// Whether to use the standard (CF=1) PM2.5 value when the sensor
+/// reports an environmental value of zero alongside a nonzero
+/// standard value, rather than reporting a falsely "Good" reading.
+const PM25_STANDARD_FALLBACK: bool = true;
+
+/// How frames are requested from the sensor. The default single
+/// transaction with a repeated start works on a direct connection,
+/// but some I2C multiplexers mishandle repeated starts. On those
+/// buses, switch to `TransferMode::WriteThenRead`.
+const SENSOR_TRANSFER_MODE: TransferMode = TransferMode::RepeatedStart;
+
+/// How much is printed: only the AQI with `Verbosity::Quiet`, the full
+/// reading and status messages with `Verbosity::Normal`, or also a dump
+/// of the sensor registers with `Verbosity::Verbose`. Warnings and
+/// errors are printed at every level.
+const VERBOSITY: Verbosity = Verbosity::Normal;
+
+/// Whether to wait for the sensor to update when a read returns the
+/// same frame as the previous read, as happens when polling faster than
+/// the sensor's roughly one second update. Otherwise, the repeated
+/// frame is skipped as not updated, rather than reported as new.
+const WAIT_FOR_NEW_FRAME: bool = true;
+
+/// Family of the optional humidity sensor sharing the I2C bus with the
+/// PMSA003I when the `humidity` feature is enabled. Use `sht::Model::Sht3x`
+/// for an SHT3x with its ADDR pin tied low.
+#[cfg(feature = "humidity")]
+const HUMIDITY_SENSOR: sht::Model = sht::Model::Sht4x;
+
+/// Second AQI standard to print alongside the US AQI, e.g.
+/// `Some(AqiStandard::India)` to print "US AQI 115 / India AQI 68".
+/// The LEDs always follow the US AQI.
+const SECONDARY_STANDARD: Option<AqiStandard> = None;
+
+/// Number of samples taken on each button press with the `stability`
+/// feature enabled.
+const STABILITY_SAMPLES: usize = 10;
+
+/// Variance in PM2.5, in (µg/m³)², above which a stability measurement
+/// is reported as too noisy to rely on.
+const STABILITY_MAX_VARIANCE: f32 = 4.0;
+
+/// PM2.5 concentrations used in place of sensor readings when the
+/// `demo` feature is enabled, chosen to give one AQI value in each
+/// EPA range: 22 (Green), 75 (Yellow), 124 (Orange), 175 (Red),
+/// 250 (Purple), and 391 (Dark Purple).
+#[cfg(feature = "demo")]
+const DEMO_PM25_VALUES: [u16; 6] = [4, 22, 45, 90, 175, 350];
+
+/// Sources which can trigger a sensor read when reading continuously,
+/// selected at init with `READ_TRIGGER`.
+#[cfg(all(feature = "continuous", not(feature = "remote")))]
+#[allow(dead_code)]
+enum TriggerSource {
+    /// Read on each press of the user button
+    Button,
+    /// Read every `SAMPLE_INTERVAL_MS`
+    Timer,
+    /// Read each time the sensor signals a new frame is ready, on
+    /// the rising edge of the data-ready line
+    DataReady,
+}
+
+/// The ReadTrigger enum holds whatever is needed to wait for the
+/// selected TriggerSource.
+#[cfg(all(feature = "continuous", not(feature = "remote")))]
+enum ReadTrigger {
+    Button(ExtiInput<'static>),
+    Timer,
+    DataReady(ExtiInput<'static>),
+}
+
+#[cfg(all(feature = "continuous", not(feature = "remote")))]
+impl ReadTrigger {
+    /// Wait until the next sensor read should be taken.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// trigger.wait().await;
+    /// let reading = read_pm(&mut i2c, &mut last_frame).await.ok().flatten();
+    /// ```
+    async fn wait(&mut self) {
+        match self {
+            ReadTrigger::Button(input) | ReadTrigger::DataReady(input) => {
+                input.wait_for_rising_edge().await
+            }
+            ReadTrigger::Timer => Timer::after_millis(SAMPLE_INTERVAL_MS).await,
+        }
+    }
+}
+
+/// Prints reading output as plain text through `hprintln!`, which goes
+/// to semihosting, RTT, defmt or USB depending on the enabled features.
+struct TextOutput {
+    verbosity: Verbosity,
+}
+
+impl aqi::Output for TextOutput {
+    fn verbosity(&self) -> Verbosity {
+        self.verbosity
+    }
+
+    fn write_line(&mut self, args: core::fmt::Arguments) {
+        hprintln!("{}", args);
+    }
+}
+
+/// Sets up the RTT channels. Channel 0 carries plain text output, and
+/// channel 1 carries defmt logs so that the two do not interleave.
+/// This must only be called once, before anything is printed.
+#[cfg(feature = "rtt")]
+fn init_rtt() {
+    let channels = rtt_target::rtt_init! {
+        up: {
+            0: {
+                size: 1024,
+                name: "Terminal"
+            }
+            1: {
+                size: 1024,
+                name: "defmt"
+            }
+        }
+    };
+    rtt_target::set_print_channel(channels.up.0);
+    rtt_target::set_defmt_channel(channels.up.1);
+}
 
 /// The LedController struct maps human-readable LED
 /// names to their corresponding pin name for the
@@ -61,6 +550,8 @@ pub struct LedController {
     led_red2: Output<'static>,
     led_orange2: Output<'static>,
     led_green2: Output<'static>,
+    // The LEDs currently on
+    state: LedState,
 }
 
 impl LedController {
@@ -92,15 +583,12 @@ impl LedController {
             led_red2: Output::new(pe13, Level::Low, Speed::Low),
             led_orange2: Output::new(pe14, Level::Low, Speed::Low),
             led_green2: Output::new(pe15, Level::Low, Speed::Low),
+            state: LedState::new(),
         }
     }
 
-    /// Turn on desired LEDs based on the specified Color value.
-    ///
-    /// The Discovery board does not have LEDs with colors directly
-    /// matching the EPA AQI ranges, so some approximations are made.
-    /// For example, the represent a dark purple color, both blue LEDS
-    /// along with a red LED are set high.
+    /// Turn on desired LEDs based on the specified Color value,
+    /// as given by `aqi::color_to_led_roles`.
     ///
     /// # Examples
     ///
@@ -109,57 +597,108 @@ impl LedController {
     /// led_controller.set_color(Color::Orange);
     /// ```
     pub fn set_color(&mut self, color: Color) {
-        // Turn off all LEDs first
-        self.all_off();
+        self.set_roles(color_to_led_roles(color));
+    }
 
-        // Set LEDs matching on color
-        match color {
-            Color::Green => {
-                self.led_green1.set_high();
-                self.led_green2.set_high();
-            }
-            Color::Yellow => {
-                self.led_green1.set_high();
-                self.led_orange1.set_high();
-            }
-            Color::Orange => {
-                self.led_orange1.set_high();
-                self.led_orange2.set_high();
-            }
-            Color::Red => {
-                self.led_red1.set_high();
-                self.led_red2.set_high();
-            }
-            Color::Purple => {
-                self.led_red1.set_high();
-                self.led_blue1.set_high();
-            }
-            Color::DarkPurple => {
-                self.led_red2.set_high();
-                self.led_blue1.set_high();
-                self.led_blue2.set_high();
-            }
+    /// Turn on exactly the given LEDs, turning off all others.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// led_controller.set_roles(&[LedRole::Blue1, LedRole::Blue2]);
+    /// ```
+    pub fn set_roles(&mut self, roles: &[LedRole]) {
+        self.set_mask(led_roles_mask(roles));
+    }
+
+    /// Turn on exactly the LEDs whose bits are set in the mask, turning
+    /// off all others. Each bit maps to one LED, as given by
+    /// `aqi::LedRole::mask`:
+    ///
+    /// | Bit | LED     | Pin  |
+    /// |-----|---------|------|
+    /// | 0   | Blue1   | PE8  |
+    /// | 1   | Red1    | PE9  |
+    /// | 2   | Orange1 | PE10 |
+    /// | 3   | Green1  | PE11 |
+    /// | 4   | Blue2   | PE12 |
+    /// | 5   | Red2    | PE13 |
+    /// | 6   | Orange2 | PE14 |
+    /// | 7   | Green2  | PE15 |
+    ///
+    /// Each pin is written exactly once, so LEDs which stay on do not
+    /// flicker off between updates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Alternate blue and red around the compass
+    /// led_controller.set_mask(0b0011_0011);
+    /// ```
+    pub fn set_mask(&mut self, mask: u8) {
+        for role in LedRole::ALL {
+            self.led(role).set_level(Level::from(role.is_lit(mask)));
         }
+        self.state.set_mask(mask);
     }
 
-    /// Turn off all LEDs. This is used as a reset prior
-    /// to setting desired LEDs high in set_color above.
+    /// Get the LEDs which are currently on, e.g. to mirror the LED
+    /// state on another display.
+    ///
+    /// # Returns
+    ///
+    /// A mask with one bit set for each LED which is on, as given by
+    /// `aqi::LedRole::mask`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// led_controller.set_color(Color::DarkPurple);
+    /// assert_eq!(led_controller.active_leds(), 0b0011_0001);
+    /// ```
+    pub fn active_leds(&self) -> u8 {
+        self.state.active_leds()
+    }
+
+    /// Get the GPIO output for a logical LED.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// led_controller.led(LedRole::Green1).set_high();
+    /// ```
+    fn led(&mut self, role: LedRole) -> &mut Output<'static> {
+        match role {
+            LedRole::Blue1 => &mut self.led_blue1,
+            LedRole::Red1 => &mut self.led_red1,
+            LedRole::Orange1 => &mut self.led_orange1,
+            LedRole::Green1 => &mut self.led_green1,
+            LedRole::Blue2 => &mut self.led_blue2,
+            LedRole::Red2 => &mut self.led_red2,
+            LedRole::Orange2 => &mut self.led_orange2,
+            LedRole::Green2 => &mut self.led_green2,
+        }
+    }
+
+    /// Turn off all LEDs, leaving them in a known-off state, e.g. before
+    /// the sensor is put to sleep. In debug builds, each pin's output
+    /// register is read back to verify that it was driven low, along
+    /// with the tracked state.
     ///
     /// # Examples
     ///
     /// ```
     /// let mut led_controller = LedController::new(p.PE8, p.PE9, p.PE10, p.PE11, p.PE12, p.PE13, p.PE14, p.PE15);
-    /// led_controller.all_off();
+    /// led_controller.off();
+    /// assert_eq!(led_controller.active_leds(), 0);
     /// ```
-    fn all_off(&mut self) {
-        self.led_orange1.set_low();
-        self.led_green1.set_low();
-        self.led_red1.set_low();
-        self.led_blue1.set_low();
-        self.led_orange2.set_low();
-        self.led_green2.set_low();
-        self.led_red2.set_low();
-        self.led_blue2.set_low();
+    pub fn off(&mut self) {
+        for role in LedRole::ALL {
+            self.led(role).set_low();
+        }
+        self.state.off();
+        debug_assert!(self.state.is_off());
+        debug_assert!(LedRole::ALL.iter().all(|&role| self.led(role).is_set_low()));
     }
 }
 
@@ -171,84 +710,1272 @@ bind_interrupts!(struct Irqs {
 });
 
 #[embassy_executor::main]
-async fn main(_spawner: Spawner) {
+async fn main(spawner: Spawner) {
+    #[cfg(feature = "rtt")]
+    init_rtt();
+
+    // USB needs a 48MHz clock, which the default clock configuration
+    // does not provide
+    #[cfg(feature = "usb")]
+    let p = embassy_stm32::init(usb_output::clock_config());
+    #[cfg(not(feature = "usb"))]
     let p = embassy_stm32::init(Default::default());
-    let mut button = ExtiInput::new(p.PA0, p.EXTI0, Pull::Down);
-
-    // Assign I2C pins
-    let scl = p.PA9;
-    let sda = p.PA10;
-
-    // Initialize I2C2 with 100kHz speed
-    let mut i2c = I2c::new(
-        p.I2C2,
-        scl,
-        sda,
-        Irqs,
-        p.DMA1_CH4,
-        p.DMA1_CH5,
-        Hertz(100_000),
-        Config::default(),
-    );
+
+    #[cfg(feature = "usb")]
+    usb_output::init(spawner, p.USB, p.PA12, p.PA11);
+    #[cfg(not(feature = "usb"))]
+    let _ = spawner;
+
+    let mut output = TextOutput {
+        verbosity: VERBOSITY,
+    };
+
+    // Store readings to flash in addition to the selected output,
+    // printing any readings stored before this boot. If the log cannot
+    // be opened, readings are still reported, just not stored.
+    #[cfg(feature = "flash-log")]
+    let mut output = {
+        let log = match flash_log::FlashLog::new(Flash::new_blocking(p.FLASH)) {
+            Ok(mut log) => {
+                if let Err(e) = log.dump(&mut output) {
+                    output.log(
+                        Verbosity::Quiet,
+                        format_args!("Error reading log from flash: {:?}", e),
+                    );
+                }
+                Some(log)
+            }
+            Err(e) => {
+                output.log(
+                    Verbosity::Quiet,
+                    format_args!(
+                        "Error opening log in flash, readings will not be stored: {:?}",
+                        e
+                    ),
+                );
+                None
+            }
+        };
+        flash_log::FlashLogOutput::new(output, log)
+    };
+
+    #[cfg(not(feature = "continuous"))]
+    let mut button = init_button!(p);
+    // The sensor's SET pin, held high to keep it awake until parked
+    #[cfg(not(feature = "continuous"))]
+    let mut sensor_set = Output::new(p.PD9, Level::High, Speed::Low);
+    #[cfg(all(feature = "continuous", not(feature = "remote")))]
+    let (mut trigger, mut mode_button) = init_read_trigger!(p, READ_TRIGGER);
+    // As a remote display, reads wait on USB rather than a trigger, so
+    // the button is only for switching the display mode
+    #[cfg(feature = "remote")]
+    let mut mode_button = init_button!(p);
+
+    // The sensor is never read in continuous demo mode or as a remote
+    // display, so the I2C bus is only set up when it is
+    #[cfg(not(any(all(feature = "continuous", feature = "demo"), feature = "remote")))]
+    let mut i2c = {
+        // Assign I2C pins
+        let scl = p.PA9;
+        let sda = p.PA10;
+
+        // Set an explicit timeout to allow for clock stretching by the sensor
+        let mut i2c_config = embassy_stm32::i2c::Config::default();
+        i2c_config.timeout = embassy_time::Duration::from_millis(I2C_TIMEOUT_MS);
+
+        // Initialize I2C2 with 100kHz speed
+        I2c::new(
+            p.I2C2,
+            scl,
+            sda,
+            Irqs,
+            p.DMA1_CH4,
+            p.DMA1_CH5,
+            embassy_stm32::time::Hertz(100_000),
+            i2c_config,
+        )
+    };
 
     // Create our LED controller
     let mut led_controller =
         LedController::new(p.PE8, p.PE9, p.PE10, p.PE11, p.PE12, p.PE13, p.PE14, p.PE15);
 
-    // Ping check the device
-    hprintln!(
-        "Attempting to ping device at address 0x{:02X}",
-        pmsa003i::SENSOR_I2C_ADDR
+    // Ping check the device, waiting for it if it is missing
+    #[cfg(not(any(feature = "demo", feature = "remote")))]
+    let mut presence = PresenceTracker::new();
+    // The last frame read, for spotting frames the sensor has not updated
+    #[cfg(not(any(feature = "demo", feature = "remote")))]
+    let mut last_frame = None;
+    #[cfg(not(any(feature = "demo", feature = "remote")))]
+    if STARTUP_PING {
+        output.log(
+            Verbosity::Normal,
+            format_args!("Attempting to ping device at address 0x{:02X}", PING_ADDR),
+        );
+        wait_for_sensor(&mut i2c, &mut led_controller, &mut presence, &mut output).await;
+    } else {
+        output.log(Verbosity::Normal, format_args!("Startup ping skipped"));
+    }
+
+    // Holding the button through boot dumps the sensor registers
+    // for field debugging
+    #[cfg(not(any(feature = "continuous", feature = "demo")))]
+    if wait_for_long_press(&mut button).await {
+        dump_registers(&mut i2c, &mut output).await;
+    }
+
+    // Give immediate feedback on power-up with an initial reading
+    #[cfg(not(any(feature = "demo", feature = "remote")))]
+    if BOOT_READING {
+        warm_up_and_read(&mut i2c, &mut led_controller, &mut output).await;
+    }
+
+    let mut monitor: Monitor<AVERAGE_SAMPLES> =
+        Monitor::new().with_secondary_standard(SECONDARY_STANDARD);
+
+    #[cfg(feature = "demo")]
+    output.log(
+        Verbosity::Normal,
+        format_args!("Demo mode enabled, cycling through synthetic readings"),
     );
+    #[cfg(feature = "remote")]
+    output.log(
+        Verbosity::Normal,
+        format_args!("Remote display enabled, waiting for PM2.5 concentrations over USB"),
+    );
+    #[cfg(feature = "demo")]
+    let mut demo_readings = DEMO_PM25_VALUES.iter().cycle();
+
+    #[cfg(feature = "continuous")]
+    let mut display_mode = DisplayMode::default();
+    // Rate of change across reports, for spotting rapid deterioration
+    #[cfg(feature = "continuous")]
+    let mut rate_window = RateWindow::new(RATE_WINDOW_MS);
 
-    match i2c.write(pmsa003i::SENSOR_I2C_ADDR, &[0x00]).await {
-        Ok(()) => hprintln!("Device responded to ping"),
-        Err(e) => hprintln!("Device did not respond to ping: {:?}", e),
+    // Read continuously, reporting the running average whenever it
+    // changes significantly.
+    #[cfg(feature = "continuous")]
+    loop {
+        // Unless the button triggers reads, a press switches the display
+        // mode instead, blinking the LEDs to show the new mode
+        #[cfg(not(feature = "remote"))]
+        if let Some(button) = mode_button.as_mut() {
+            if let Either::Second(()) = select(trigger.wait(), button.wait_for_rising_edge()).await
+            {
+                display_mode =
+                    switch_display_mode(display_mode, &mut led_controller, &mut output).await;
+                continue;
+            }
+        } else {
+            trigger.wait().await;
+        }
+        // As a remote display, the next concentration to arrive over USB is
+        // read instead, while still watching for a press
+        #[cfg(feature = "remote")]
+        let received = match select(
+            usb_output::read_concentration(),
+            mode_button.wait_for_rising_edge(),
+        )
+        .await
+        {
+            Either::First(pm) => pm,
+            Either::Second(()) => {
+                display_mode =
+                    switch_display_mode(display_mode, &mut led_controller, &mut output).await;
+                continue;
+            }
+        };
+
+        #[cfg(not(any(feature = "demo", feature = "remote")))]
+        wait_for_sensor(&mut i2c, &mut led_controller, &mut presence, &mut output).await;
+        #[cfg(not(any(feature = "demo", feature = "remote")))]
+        let reading = read_pm(&mut i2c, &mut last_frame, &mut output)
+            .await
+            .ok()
+            .flatten();
+        #[cfg(feature = "demo")]
+        let reading = demo_readings.next().map(|&pm| (pm, pm));
+        // Only PM2.5 is received, so it stands in for PM10 as in demo mode
+        #[cfg(feature = "remote")]
+        let reading = Some((received, received));
+
+        if let Some((pm25_concentration, pm10_concentration)) = reading {
+            let report =
+                monitor.record_significant(pm25_concentration, REPORT_MIN_DELTA, &mut output);
+            if let Some(report) = &report {
+                let mut buf = [0u8; 45];
+                let prev_aqi = (report.aqi as i32 - report.delta) as u16;
+                let trend = Trend::from_change(prev_aqi, report.aqi, REPORT_MIN_DELTA);
+                output.log(
+                    Verbosity::Normal,
+                    format_args!("{}", status_sentence(report.aqi, trend, &mut buf)),
+                );
+
+                let now_ms = embassy_time::Instant::now().as_millis() as u32;
+                if let Some((from, rate)) = rate_window.update(now_ms, report.aqi) {
+                    if rate == ChangeRate::Rapid && report.aqi > from {
+                        output.log(
+                            Verbosity::Quiet,
+                            format_args!(
+                                "Warning: AQI rising rapidly, from {} to {}",
+                                from, report.aqi
+                            ),
+                        );
+                    }
+                }
+            }
+            if let Some(aqi) = display_mode.led_aqi(pm25_concentration, report.as_ref()) {
+                led_controller.set_color(led_color(aqi, pm10_concentration));
+            }
+        }
     }
 
-    let mut aqi = 0;
+    #[cfg(not(feature = "continuous"))]
+    let mut alarm = AlarmLatch::new(ALARM_AQI);
+    #[cfg(not(feature = "continuous"))]
+    let mut stats = AqiStats::new();
+    #[cfg(not(feature = "continuous"))]
+    let mut double_press = DoublePress::new(DOUBLE_PRESS_MS);
+    #[cfg(not(feature = "continuous"))]
+    let mut events: EventLog<EVENT_LOG_SIZE> = EventLog::new();
+    #[cfg(not(feature = "continuous"))]
+    let mut last_category: Option<Category> = None;
+    // Whether the last reading failed, so a fault is only logged once
+    // until the sensor recovers
+    #[cfg(not(feature = "continuous"))]
+    let mut sensor_faulted = false;
+    // Time of a press seen while busy with the previous one, to be
+    // handled as though it had been waited for
+    #[cfg(not(feature = "continuous"))]
+    let mut pending_press_ms: Option<u32> = None;
 
+    #[cfg(not(feature = "continuous"))]
     loop {
-        button.wait_for_any_edge().await;
-        if button.is_high() {
-            match pmsa003i::fetch_data(&mut i2c).await {
-                Ok(sensor_data) => {
-                    // If validations fail, skip data parsing and try again on the next iteration
-                    if let Err(e) = pmsa003i::validate_header(&sensor_data[0..2]) {
-                        hprintln!("Error validating header: {}", e);
-                        continue;
+        let pressed_ms = match pending_press_ms.take() {
+            Some(pressed_ms) => pressed_ms,
+            None => {
+                button.wait_for_rising_edge().await;
+                embassy_time::Instant::now().as_millis() as u32
+            }
+        };
+
+        // A press while the alarm is raised acknowledges it
+        if alarm.is_latched() {
+            alarm.acknowledge();
+            events.push(pressed_ms, Event::AlarmAcknowledged);
+            output.log(Verbosity::Normal, format_args!("Alarm acknowledged"));
+        }
+
+        // A double press prints the session's events and resets the
+        // session statistics instead of taking a reading, flashing the
+        // reset LEDs to confirm
+        if double_press.press(pressed_ms) {
+            output.log(Verbosity::Normal, format_args!("Session events:"));
+            for (timestamp_ms, event) in events.iter() {
+                output.log(
+                    Verbosity::Normal,
+                    format_args!("  {} ms: {}", timestamp_ms, event),
+                );
+            }
+            events.clear();
+            stats.reset();
+            output.log(Verbosity::Normal, format_args!("Session statistics reset"));
+            led_controller.set_roles(RESET_FLASH_LEDS);
+            Timer::after_millis(RESET_FLASH_MS).await;
+            led_controller.off();
+            continue;
+        }
+
+        // If the sensor has gone missing, wait for it to come back
+        #[cfg(not(any(feature = "demo", feature = "remote")))]
+        wait_for_sensor(&mut i2c, &mut led_controller, &mut presence, &mut output).await;
+
+        // With the `stability` feature, each press instead takes a fixed
+        // number of samples and reports how much they vary
+        if cfg!(all(feature = "stability", not(feature = "demo"))) {
+            report_stability(&mut i2c, &mut output).await;
+            continue;
+        }
+
+        // In demo mode, each press uses the next synthetic reading in place of the sensor
+        #[cfg(feature = "demo")]
+        let demo_reading = demo_readings.next().map(|&pm| (pm, pm));
+
+        // Collect readings for as long as the button is held, reporting
+        // the running average so the displayed AQI steadies over time.
+        // A very long hold parks instead.
+        let mut parked = false;
+        let mut last_pm25 = None;
+        while button.is_high() {
+            let held_ms =
+                (embassy_time::Instant::now().as_millis() as u32).wrapping_sub(pressed_ms);
+            if hold_action(held_ms, PARK_HOLD_MS) == HoldAction::Park {
+                parked = true;
+                break;
+            }
+
+            #[cfg(not(any(feature = "demo", feature = "remote")))]
+            let reading = read_pm(&mut i2c, &mut last_frame, &mut output).await;
+            #[cfg(feature = "demo")]
+            let reading = Ok::<_, ()>(demo_reading);
+
+            let reading_ms = embassy_time::Instant::now().as_millis() as u32;
+
+            // Skip setting the LEDs until a valid reading is available
+            if let Ok(Some((pm25_concentration, pm10_concentration))) = reading {
+                if sensor_faulted {
+                    events.push(reading_ms, Event::SensorRecovered);
+                    sensor_faulted = false;
+                }
+
+                let report = monitor.record(pm25_concentration, &mut output);
+                led_controller.set_color(led_color(report.aqi, pm10_concentration));
+                last_pm25 = Some(pm25_concentration);
+
+                stats.update(report.aqi);
+
+                let category = get_aqi_category(report.aqi);
+                if let Some(from) = last_category.filter(|&from| from != category) {
+                    events.push(reading_ms, Event::CategoryChanged { from, to: category });
+                }
+                last_category = Some(category);
+
+                let was_latched = alarm.is_latched();
+                if alarm.update(report.aqi) && !was_latched {
+                    events.push(reading_ms, Event::AlarmRaised { aqi: report.aqi });
+                    output.log(
+                        Verbosity::Quiet,
+                        format_args!("Alarm: AQI {} reached {}", report.aqi, ALARM_AQI),
+                    );
+                }
+            } else if reading.is_err() && !sensor_faulted {
+                // A frame the sensor has not yet updated is not a fault
+                events.push(reading_ms, Event::SensorFault);
+                sensor_faulted = true;
+            }
+
+            // Watch for the button being released and pressed again while
+            // waiting, so that the second press of a double press is seen
+            // when it happens rather than after the wait
+            let repress = async {
+                button.wait_for_low().await;
+                button.wait_for_rising_edge().await;
+            };
+            if let Either::Second(()) =
+                select(Timer::after_millis(SAMPLE_INTERVAL_MS), repress).await
+            {
+                pending_press_ms = Some(embassy_time::Instant::now().as_millis() as u32);
+                break;
+            }
+        }
+
+        monitor.finish_hold(&mut output);
+        if let (Some(min), Some(max), Some(average)) = (stats.min(), stats.max(), stats.average()) {
+            output.log(
+                Verbosity::Normal,
+                format_args!(
+                    "Session AQI over {} readings: min {}, max {}, average {}",
+                    stats.len(),
+                    min,
+                    max,
+                    average
+                ),
+            );
+        }
+
+        // With the `binary` feature, briefly show the raw PM2.5 value,
+        // unless parking or the next press has already come
+        if cfg!(feature = "binary") && !parked && pending_press_ms.is_none() {
+            if let Some(pm25_concentration) = last_pm25 {
+                output.log(
+                    Verbosity::Normal,
+                    format_args!("Showing PM2.5 of {} µg/m³ in binary", pm25_concentration),
+                );
+                // A press ends the display early, and is handled as the next press
+                let display = show_binary(&mut led_controller, pm25_concentration);
+                if let Either::Second(()) = select(display, button.wait_for_rising_edge()).await {
+                    pending_press_ms = Some(embassy_time::Instant::now().as_millis() as u32);
+                }
+            }
+        }
+
+        if parked {
+            park(
+                &mut button,
+                &mut led_controller,
+                &mut i2c,
+                &mut sensor_set,
+                &mut output,
+            )
+            .await;
+        }
+
+        // Keep showing the alarm until it is acknowledged
+        if alarm.is_latched() {
+            led_controller.set_roles(ALARM_LEDS);
+        } else {
+            led_controller.off();
+        }
+    }
+}
+
+/// Shows a value in binary on the LEDs for `BINARY_DISPLAY_MS`, as given
+/// by `binary_led_mask`. A value too large to show alternates between
+/// the two halves of `BINARY_OVERFLOW_LEDS` instead.
+///
+/// # Arguments
+///
+/// * `led_controller` - The LEDs to show the value on
+/// * `value` - The value to show, such as a PM2.5 concentration
+///
+/// # Examples
+///
+/// ```
+/// show_binary(&mut led_controller, pm25_concentration).await;
+/// ```
+#[cfg(not(feature = "continuous"))]
+async fn show_binary(led_controller: &mut LedController, value: u16) {
+    match binary_led_mask(value) {
+        Some(mask) => {
+            led_controller.set_mask(mask);
+            Timer::after_millis(BINARY_DISPLAY_MS).await;
+        }
+        None => {
+            for roles in BINARY_OVERFLOW_LEDS
+                .iter()
+                .cycle()
+                .take((BINARY_DISPLAY_MS / BINARY_OVERFLOW_STEP_MS) as usize)
+            {
+                led_controller.set_roles(roles);
+                Timer::after_millis(BINARY_OVERFLOW_STEP_MS).await;
+            }
+        }
+    }
+}
+
+/// Parks after a very long hold to save power, turning the LEDs off
+/// and putting the sensor to sleep until the button is pressed again.
+/// On resume, the sensor is woken and polled until it returns a valid
+/// frame, then given `WARMUP_MS` for its fan to settle with the
+/// measuring spinner shown, so readings are stable as soon as this
+/// returns.
+///
+/// # Arguments
+///
+/// * `button` - The user button, still held from the hold which parked
+/// * `led_controller` - The LEDs to turn off, and to show the spinner
+///   on resume
+/// * `i2c` - An Embassy Async I2C instance
+/// * `sensor_set` - The GPIO output connected to the sensor's SET pin
+/// * `output` - Where status messages and errors are printed
+///
+/// # Examples
+///
+/// ```
+/// if hold_action(held_ms, PARK_HOLD_MS) == HoldAction::Park {
+///     park(&mut button, &mut led_controller, &mut i2c, &mut sensor_set, &mut output).await;
+/// }
+/// ```
+#[cfg(not(feature = "continuous"))]
+#[cfg_attr(any(feature = "demo", feature = "remote"), allow(unused_variables))]
+async fn park<O: aqi::Output>(
+    button: &mut embassy_stm32::exti::ExtiInput<'_>,
+    led_controller: &mut LedController,
+    i2c: &mut I2c<'_, Async>,
+    sensor_set: &mut Output<'_>,
+    output: &mut O,
+) {
+    output.log(
+        Verbosity::Normal,
+        format_args!("Parked, press the button to resume"),
+    );
+    led_controller.off();
+    #[cfg(not(any(feature = "demo", feature = "remote")))]
+    if let Err(e) = pmsa003i::sleep(sensor_set) {
+        output.log(
+            Verbosity::Quiet,
+            format_args!("Error putting sensor to sleep: {}", e),
+        );
+    }
+
+    // Wait for the hold which parked to end before waiting for a new press
+    button.wait_for_low().await;
+    button.wait_for_rising_edge().await;
+
+    output.log(
+        Verbosity::Normal,
+        format_args!("Resuming, waiting for the sensor to wake"),
+    );
+    #[cfg(not(any(feature = "demo", feature = "remote")))]
+    {
+        if let Err(e) = pmsa003i::wake(i2c, sensor_set, &mut Delay, SENSOR_TRANSFER_MODE).await {
+            output.log(Verbosity::Quiet, format_args!("Error waking sensor: {}", e));
+        }
+        // The fan restarted on waking, so needs to settle as after power-on
+        warm_up(led_controller, 0, output).await;
+    }
+
+    // Wait for the resume press to end, so it does not take a reading
+    button.wait_for_low().await;
+}
+
+/// Pings the sensor until it responds, retrying every `SENSOR_RETRY_MS`.
+/// While the sensor is missing, the LEDs show `SENSOR_MISSING_LEDS`, and
+/// a single message is printed when it is lost and when it is found.
+///
+/// # Arguments
+///
+/// * `i2c` - An Embassy Async I2C instance
+/// * `led_controller` - The LEDs used to indicate the sensor is missing
+/// * `presence` - Tracks whether the sensor was present at the last ping
+/// * `output` - Where status messages and errors are printed
+///
+/// # Examples
+///
+/// ```
+/// let mut presence = PresenceTracker::new();
+/// wait_for_sensor(&mut i2c, &mut led_controller, &mut presence, &mut output).await;
+/// ```
+#[cfg_attr(any(feature = "demo", feature = "remote"), allow(dead_code))]
+async fn wait_for_sensor<O: aqi::Output>(
+    i2c: &mut I2c<'_, Async>,
+    led_controller: &mut LedController,
+    presence: &mut PresenceTracker,
+    output: &mut O,
+) {
+    loop {
+        let result = pmsa003i::ping(i2c, PING_ADDR).await;
+        match (presence.update(result.is_ok()), result) {
+            (Some(SensorPresence::Present), _) => {
+                output.log(Verbosity::Normal, format_args!("Device responded to ping"));
+                led_controller.off();
+
+                // Log the sensor revision to help with field debugging
+                match pmsa003i::read_version(i2c, SENSOR_TRANSFER_MODE).await {
+                    Ok(version) => output.log(
+                        Verbosity::Normal,
+                        format_args!("Sensor version: 0x{:02X}", version),
+                    ),
+                    Err(e) => output.log(
+                        Verbosity::Quiet,
+                        format_args!("Error reading sensor version: {}", e),
+                    ),
+                }
+            }
+            (Some(SensorPresence::Absent), Err(e)) => {
+                output.log(
+                    Verbosity::Quiet,
+                    format_args!(
+                        "Sensor not found: {} ({:?}), retrying every {} seconds",
+                        i2c_error_message(&e),
+                        e,
+                        SENSOR_RETRY_MS / 1000
+                    ),
+                );
+                led_controller.set_roles(SENSOR_MISSING_LEDS);
+            }
+            _ => {}
+        }
+
+        if presence.is_present() {
+            return;
+        }
+        Timer::after_millis(SENSOR_RETRY_MS).await;
+    }
+}
+
+/// Waits for the sensor to warm up, showing a measuring spinner on the
+/// LEDs, then takes an initial reading and shows its AQI. Warm-up is
+/// timed from boot, as the sensor is powered along with the board, so
+/// any time already spent waiting for the sensor counts towards it.
+///
+/// # Arguments
+///
+/// * `i2c` - An Embassy Async I2C instance
+/// * `led_controller` - The LED controller, used for the spinner and
+///   the reading
+/// * `output` - Where the reading and any errors are printed
+///
+/// # Examples
+///
+/// ```
+/// if BOOT_READING {
+///     warm_up_and_read(&mut i2c, &mut led_controller, &mut output).await;
+/// }
+/// ```
+#[cfg(not(any(feature = "demo", feature = "remote")))]
+async fn warm_up_and_read<O: aqi::Output>(
+    i2c: &mut I2c<'_, Async>,
+    led_controller: &mut LedController,
+    output: &mut O,
+) {
+    let elapsed_ms = embassy_time::Instant::now().as_millis() as u32;
+    warm_up(led_controller, elapsed_ms, output).await;
+
+    if let Ok(Some((pm25_concentration, pm10_concentration))) =
+        read_pm(i2c, &mut None, output).await
+    {
+        let aqi = aqi::calculate_aqi(pm25_concentration as f32);
+        output.log(
+            Verbosity::Quiet,
+            format_args!(
+                "Initial reading: PM2.5 {} µg/m³, AQI {}",
+                pm25_concentration, aqi
+            ),
+        );
+        led_controller.set_color(led_color(aqi, pm10_concentration));
+    }
+}
+
+/// Waits until the sensor has run for `WARMUP_MS`, showing a measuring
+/// spinner on the LEDs, which are turned off once it is done.
+///
+/// # Arguments
+///
+/// * `led_controller` - The LED controller, used for the spinner
+/// * `elapsed_ms` - Time the sensor has already been running
+/// * `output` - Where the warm-up message is printed
+///
+/// # Examples
+///
+/// ```
+/// // The sensor has just been woken, so its fan is starting afresh
+/// warm_up(&mut led_controller, 0, &mut output).await;
+/// ```
+#[cfg(not(any(feature = "demo", feature = "remote")))]
+async fn warm_up<O: aqi::Output>(
+    led_controller: &mut LedController,
+    elapsed_ms: u32,
+    output: &mut O,
+) {
+    pmsa003i::wait_for_warmup(
+        &mut Delay,
+        elapsed_ms,
+        WARMUP_MS,
+        MEASURING_STEP_MS,
+        |step_ms| {
+            // Only called while warming up, first with the starting time
+            if step_ms == elapsed_ms {
+                output.log(
+                    Verbosity::Normal,
+                    format_args!("Measuring, waiting for the sensor to warm up"),
+                );
+            }
+            led_controller.set_roles(&[measuring_led(step_ms, MEASURING_STEP_MS)]);
+        },
+    )
+    .await;
+    led_controller.off();
+}
+
+/// Waits while the button is held, checking whether it is held for
+/// at least `DIAGNOSTICS_PRESS_MS`. Returns immediately if the button
+/// is not pressed.
+///
+/// # Arguments
+///
+/// * `button` - The user button
+///
+/// # Returns
+///
+/// True if the button was held long enough to request diagnostics.
+///
+/// # Examples
+///
+/// ```
+/// if wait_for_long_press(&mut button).await {
+///     dump_registers(&mut i2c, &mut output).await;
+/// }
+/// ```
+#[cfg(not(any(feature = "continuous", feature = "demo")))]
+async fn wait_for_long_press(button: &mut embassy_stm32::exti::ExtiInput<'_>) -> bool {
+    let mut long_press = LongPress::new(DIAGNOSTICS_PRESS_MS);
+    while button.is_high() {
+        let timestamp_ms = embassy_time::Instant::now().as_millis() as u32;
+        if long_press.update(true, timestamp_ms) {
+            return true;
+        }
+        Timer::after_millis(BUTTON_POLL_MS).await;
+    }
+    false
+}
+
+/// Reads a single frame from the sensor and prints every register,
+/// without validating or parsing the frame, for field debugging. The
+/// dump was asked for, so it is printed unless the output is quiet.
+///
+/// # Arguments
+///
+/// * `i2c` - An Embassy Async I2C instance
+/// * `output` - Where the registers and any errors are printed
+///
+/// # Examples
+///
+/// ```
+/// dump_registers(&mut i2c, &mut output).await;
+/// ```
+#[cfg(not(any(feature = "continuous", feature = "demo")))]
+async fn dump_registers<O: aqi::Output>(i2c: &mut I2c<'_, Async>, output: &mut O) {
+    output.log(
+        Verbosity::Normal,
+        format_args!("Diagnostics requested, dumping sensor registers"),
+    );
+    match pmsa003i::fetch_data_with_mode(i2c, SENSOR_TRANSFER_MODE).await {
+        Ok(sensor_data) => print_all_regs(&sensor_data, Verbosity::Normal, output),
+        Err(e) => output.log(
+            Verbosity::Quiet,
+            format_args!(
+                "Error reading registers: {} ({:?})",
+                i2c_error_message(&e),
+                e
+            ),
+        ),
+    }
+}
+
+/// Takes `STABILITY_SAMPLES` readings from the sensor and prints their
+/// mean and variance, along with a warning if the variance is above
+/// `STABILITY_MAX_VARIANCE`.
+///
+/// # Arguments
+///
+/// * `i2c` - An Embassy Async I2C instance
+/// * `output` - Where the results and any errors are printed
+///
+/// # Examples
+///
+/// ```
+/// button.wait_for_rising_edge().await;
+/// report_stability(&mut i2c, &mut output).await;
+/// ```
+#[cfg_attr(feature = "continuous", allow(dead_code))]
+async fn report_stability<O: aqi::Output>(i2c: &mut I2c<'_, Async>, output: &mut O) {
+    let mut samples = [0.0; STABILITY_SAMPLES];
+    let mut count = 0;
+    let mut last_frame = None;
+    for _ in 0..STABILITY_SAMPLES {
+        if let Ok(Some((pm25_concentration, _))) = read_pm(i2c, &mut last_frame, output).await {
+            samples[count] = pm25_concentration as f32;
+            count += 1;
+        }
+        Timer::after_millis(SAMPLE_INTERVAL_MS).await;
+    }
+
+    if count == 0 {
+        output.log(
+            Verbosity::Quiet,
+            format_args!("No valid readings taken, unable to measure stability"),
+        );
+        return;
+    }
+    let (mean, variance) = mean_variance(&samples[..count]);
+    output.log(
+        Verbosity::Normal,
+        format_args!(
+            "PM2.5 over {} samples: mean {:.1} µg/m³, variance {:.1}, confidence {}%",
+            count,
+            mean,
+            variance,
+            confidence(&samples[..count])
+        ),
+    );
+    if variance > STABILITY_MAX_VARIANCE {
+        output.log(
+            Verbosity::Quiet,
+            format_args!("Warning: reading is noisy, consider retaking it"),
+        );
+    }
+    // Newline to separate output between readings
+    output.log(Verbosity::Normal, format_args!(""));
+}
+
+/// Switches the display mode after a press of the button, printing the
+/// new mode and blinking the LEDs to show it.
+///
+/// # Arguments
+///
+/// * `display_mode` - The display mode before the press
+/// * `led_controller` - The LED controller, used for the cue
+/// * `output` - Where the new mode is printed
+///
+/// # Returns
+///
+/// The new display mode.
+///
+/// # Examples
+///
+/// ```
+/// display_mode = switch_display_mode(display_mode, &mut led_controller, &mut output).await;
+/// ```
+#[cfg(feature = "continuous")]
+async fn switch_display_mode<O: aqi::Output>(
+    display_mode: DisplayMode,
+    led_controller: &mut LedController,
+    output: &mut O,
+) -> DisplayMode {
+    let display_mode = display_mode.toggle();
+    output.log(
+        Verbosity::Normal,
+        format_args!("Display mode: {}", display_mode),
+    );
+    show_mode_cue(led_controller, display_mode).await;
+    display_mode
+}
+
+/// Blinks the LEDs to indicate the display mode just switched to, once
+/// for instantaneous and twice for averaged, then restores whatever the
+/// LEDs were showing.
+///
+/// # Arguments
+///
+/// * `led_controller` - The LEDs to blink
+/// * `display_mode` - The display mode switched to
+///
+/// # Examples
+///
+/// ```
+/// display_mode = display_mode.toggle();
+/// show_mode_cue(&mut led_controller, display_mode).await;
+/// ```
+#[cfg(feature = "continuous")]
+async fn show_mode_cue(led_controller: &mut LedController, display_mode: DisplayMode) {
+    let lit = led_controller.active_leds();
+    for _ in 0..display_mode.cue_blinks() {
+        led_controller.set_roles(MODE_CUE_LEDS);
+        Timer::after_millis(MODE_CUE_BLINK_MS).await;
+        led_controller.off();
+        Timer::after_millis(MODE_CUE_BLINK_MS).await;
+    }
+    led_controller.set_mask(lit);
+}
+
+/// Selects the Color shown on the LEDs. By default this is from the
+/// PM2.5 AQI alone. With the `pm10` feature, it is from the overall
+/// AQI, which is the worse of the PM2.5 and PM10 AQI.
+///
+/// # Arguments
+///
+/// * `pm25_aqi` - The reported PM2.5 AQI
+/// * `pm10_concentration` - The PM10 concentration in environmental units
+///
+/// # Examples
+///
+/// ```
+/// let report = monitor.record(pm25_concentration, &mut output);
+/// led_controller.set_color(led_color(report.aqi, pm10_concentration));
+/// ```
+#[cfg_attr(not(feature = "pm10"), allow(unused_variables))]
+fn led_color(pm25_aqi: u16, pm10_concentration: u16) -> Color {
+    #[cfg(feature = "pm10")]
+    let pm10_aqi = Some(aqi::calculate_aqi_pm10(pm10_concentration as f32));
+    #[cfg(not(feature = "pm10"))]
+    let pm10_aqi = None;
+
+    aqi::get_aqi_color(aqi::overall_aqi(pm25_aqi, pm10_aqi))
+}
+
+/// Fetches, validates, and parses a single frame from the sensor.
+/// Any errors along the way are printed to the output. Failed
+/// reads are retried by `pmsa003i::fetch_data_retry`, except errors
+/// which can wedge the bus, such as lost arbitration, after which the
+/// bus is recovered and the read retried once. A frame repeating the
+/// previous read is waited out or skipped, per `WAIT_FOR_NEW_FRAME`.
+///
+/// # Arguments
+///
+/// * `i2c` - An Embassy Async I2C instance
+/// * `last_frame` - The frame from the previous read, updated with
+///   the frame read
+/// * `output` - Where errors and details of the reading are printed
+///
+/// # Returns
+///
+/// The PM2.5 and PM10 concentrations in environmental units, or None
+/// if the frame was not updated since the previous read. An Err is
+/// returned if the frame could not be read or failed validation.
+///
+/// # Examples
+///
+/// ```
+/// let mut last_frame = None;
+/// if let Ok(Some((pm25_concentration, _))) = read_pm(&mut i2c, &mut last_frame, &mut output).await {
+///     let aqi = aqi::calculate_aqi(pm25_concentration as f32);
+/// }
+/// ```
+#[cfg_attr(any(feature = "demo", feature = "remote"), allow(dead_code))]
+async fn read_pm<O: aqi::Output>(
+    i2c: &mut I2c<'_, Async>,
+    last_frame: &mut Option<[u8; TOTAL_REGISTERS]>,
+    output: &mut O,
+) -> Result<Option<(u16, u16)>, ()> {
+    let mut result = pmsa003i::fetch_data_retry(i2c, &mut Delay, SENSOR_TRANSFER_MODE).await;
+    if let Err(e) = &result {
+        if pmsa003i::needs_bus_recovery(e.kind()) {
+            output.log(
+                Verbosity::Quiet,
+                format_args!(
+                    "Error reading registers: {} ({:?}), recovering bus and retrying",
+                    i2c_error_message(e),
+                    e
+                ),
+            );
+            recover_i2c_bus(output).await;
+            result = pmsa003i::fetch_data_with_mode(i2c, SENSOR_TRANSFER_MODE).await;
+        }
+    }
+
+    match result {
+        Ok(mut sensor_data) => {
+            // Don't report a frame the sensor has not updated as new
+            if last_frame
+                .is_some_and(|previous| pmsa003i::is_repeated_frame(&previous, &sensor_data))
+            {
+                let new_frame = if WAIT_FOR_NEW_FRAME {
+                    pmsa003i::wait_for_new_frame(
+                        i2c,
+                        &mut Delay,
+                        SENSOR_TRANSFER_MODE,
+                        &sensor_data,
+                    )
+                    .await
+                } else {
+                    Ok(None)
+                };
+                match new_frame {
+                    Ok(Some(new_data)) => sensor_data = new_data,
+                    Ok(None) => {
+                        output.log(
+                            Verbosity::Normal,
+                            format_args!("Sensor frame not updated, skipping reading"),
+                        );
+                        return Ok(None);
                     }
-                    if let Err(e) = pmsa003i::validate_checksum(&sensor_data[0..=31]) {
-                        hprintln!("Error validating checksum: {}", e);
-                        continue;
+                    Err(e) => {
+                        output.log(
+                            Verbosity::Quiet,
+                            format_args!(
+                                "Error reading registers: {} ({:?})",
+                                i2c_error_message(&e),
+                                e
+                            ),
+                        );
+                        return Err(());
                     }
+                }
+            }
+            *last_frame = Some(sensor_data);
+            print_all_regs(&sensor_data, Verbosity::Verbose, output);
 
-                    // Parse data
-                    let data = pmsa003i::parse_data(&sensor_data).unwrap_or_else(|err| {
-                        hprintln!("Error parsing data: {}", err);
-                        Pmsa003iData::default()
-                    });
+            // If validations fail, skip data parsing
+            if let Err(e) = pmsa003i::validate_frame(&sensor_data) {
+                if e == Pmsa003iError::InvalidHeader {
+                    output.log(
+                        Verbosity::Quiet,
+                        format_args!(
+                            "Error validating frame: {}, got 0x{:02X}{:02X}",
+                            e, sensor_data[0], sensor_data[1]
+                        ),
+                    );
+                } else {
+                    output.log(
+                        Verbosity::Quiet,
+                        format_args!("Error validating frame: {}", e),
+                    );
+                }
+                return Err(());
+            }
 
-                    // Get PM2.5 concentration
-                    let pm25_concentration = data.pm2_5_env;
+            // Parse data
+            let data = pmsa003i::parse_data(&sensor_data).unwrap_or_else(|err| {
+                output.log(
+                    Verbosity::Quiet,
+                    format_args!("Error parsing data: {}", err),
+                );
+                Pmsa003iData::default()
+            });
+            if let Err(e) = pmsa003i::check_pm_consistency(&data) {
+                output.log(
+                    Verbosity::Quiet,
+                    format_args!("Error validating data: {}", e),
+                );
+                return Err(());
+            }
+            if let Ok(health) = pmsa003i::parse_health(&sensor_data) {
+                if !health.is_healthy() {
+                    output.log(
+                        Verbosity::Quiet,
+                        format_args!("Warning: sensor reported {}", health),
+                    );
+                }
+            }
 
-                    // Convert concentration to AQI
-                    aqi = aqi::calculate_aqi(pm25_concentration as f32);
-                    hprintln!("PM2.5 concentration: {} µg/m³", pm25_concentration);
+            // Get PM2.5 and PM10 concentrations
+            let (_, mut pm2_5, pm10) = data.mass_concentrations();
+            if PM25_STANDARD_FALLBACK {
+                if let Some(standard) = pmsa003i::pm25_fallback(pm2_5, data.pm2_5_standard()) {
+                    output.log(
+                        Verbosity::Normal,
+                        format_args!(
+                            "PM2.5 environmental value is zero, using standard value of {} µg/m³",
+                            standard
+                        ),
+                    );
+                    pm2_5 = standard;
                 }
-                Err(e) => hprintln!("Error reading registers: {:?}", e),
             }
-            // Get color name from AQI value
-            let color = aqi::get_aqi_color(aqi);
+            #[cfg(feature = "humidity")]
+            if let Some(rh) = read_humidity(i2c, output).await {
+                let corrected = aqi::correct_pm25(data.pm2_5_standard() as f32, rh);
+                output.log(
+                    Verbosity::Normal,
+                    format_args!(
+                        "RH: {:.1}%, humidity corrected PM2.5: {:.1} µg/m³",
+                        rh, corrected
+                    ),
+                );
+                pm2_5 = libm::roundf(corrected) as u16;
+            }
+            Ok(Some((pm2_5, pm10)))
+        }
+        Err(e) => {
+            output.log(
+                Verbosity::Quiet,
+                format_args!(
+                    "Error reading registers: {} ({:?})",
+                    i2c_error_message(&e),
+                    e
+                ),
+            );
+            Err(())
+        }
+    }
+}
 
-            // Set the LED color
-            led_controller.set_color(color);
+/// Reads relative humidity from the optional humidity sensor, which
+/// shares the I2C bus with the PMSA003I. If no sensor acknowledges,
+/// humidity is skipped without printing an error, so the same firmware
+/// runs with or without the sensor fitted.
+///
+/// # Arguments
+///
+/// * `i2c` - The I2C bus shared with the PMSA003I
+/// * `output` - Where errors are printed
+///
+/// # Returns
+///
+/// The relative humidity in percent, or None if the sensor is absent
+/// or the measurement failed.
+///
+/// # Examples
+///
+/// ```
+/// if let Some(rh) = read_humidity(i2c, output).await {
+///     pm2_5 = libm::roundf(aqi::correct_pm25(data.pm2_5_standard() as f32, rh)) as u16;
+/// }
+/// ```
+#[cfg(feature = "humidity")]
+async fn read_humidity<O: aqi::Output>(i2c: &mut I2c<'_, Async>, output: &mut O) -> Option<f32> {
+    match sht::measure(i2c, &mut Delay, HUMIDITY_SENSOR).await {
+        Ok(measurement) => Some(measurement.humidity),
+        Err(e) if e.is_absent() => None,
+        Err(e) => {
+            output.log(
+                Verbosity::Quiet,
+                format_args!("Error reading humidity: {}", e),
+            );
+            None
+        }
+    }
+}
 
-            hprintln!("Calculated AQI: {}, Color: {:?}", aqi, color);
-            // Newline to separate output between readings
-            hprintln!("");
-        } else {
-            led_controller.all_off();
+/// Frees the I2C bus after an error which can leave a device holding
+/// SDA low, without a reboot. The I2C pins are briefly driven as GPIO
+/// to clock the bus free, then handed back to the I2C peripheral with
+/// their original pin configuration, and the peripheral is reset to
+/// clear its busy state.
+///
+/// # Arguments
+///
+/// * `output` - Where errors are printed
+///
+/// # Examples
+///
+/// ```
+/// if pmsa003i::needs_bus_recovery(e.kind()) {
+///     recover_i2c_bus(&mut output).await;
+/// }
+/// ```
+#[cfg_attr(any(feature = "demo", feature = "remote"), allow(dead_code))]
+async fn recover_i2c_bus<O: aqi::Output>(output: &mut O) {
+    const PINS: [usize; 2] = [9, 10];
+    let gpio = stm32_metapac::GPIOA;
+
+    // Driving the pins as GPIO changes their mode, output type, speed
+    // and pull, so the I2C configuration is saved to be restored below
+    let (moder, otyper, ospeedr, pupdr, afr) = (
+        gpio.moder().read(),
+        gpio.otyper().read(),
+        gpio.ospeedr().read(),
+        gpio.pupdr().read(),
+        gpio.afr(1).read(),
+    );
+
+    // SAFETY: PA9 and PA10 are owned by the I2C driver, which is idle
+    // between transfers while the caller holds it, so the pins can be
+    // borrowed here and are returned to it below.
+    let (mut scl, mut sda) = unsafe {
+        (
+            OutputOpenDrain::new(PA9::steal(), Level::High, Speed::Low),
+            OutputOpenDrain::new(PA10::steal(), Level::High, Speed::Low),
+        )
+    };
+    if let Err(e) = pmsa003i::recover_bus(&mut scl, &mut sda, &mut Delay).await {
+        output.log(
+            Verbosity::Quiet,
+            format_args!("Error recovering bus: {}", e),
+        );
+    }
+
+    // Dropping the GPIO pins would disconnect them, so instead they are
+    // forgotten and switched back to their saved I2C configuration. The
+    // registers are shared with the other pins on the port, so each
+    // read-modify-write is done in a critical section.
+    core::mem::forget(scl);
+    core::mem::forget(sda);
+    critical_section::with(|_| {
+        gpio.afr(1).modify(|w| {
+            for pin in PINS {
+                w.set_afr(pin - 8, afr.afr(pin - 8));
+            }
+        });
+        gpio.otyper().modify(|w| {
+            for pin in PINS {
+                w.set_ot(pin, otyper.ot(pin));
+            }
+        });
+        gpio.ospeedr().modify(|w| {
+            for pin in PINS {
+                w.set_ospeedr(pin, ospeedr.ospeedr(pin));
+            }
+        });
+        gpio.pupdr().modify(|w| {
+            for pin in PINS {
+                w.set_pupdr(pin, pupdr.pupdr(pin));
+            }
+        });
+        gpio.moder().modify(|w| {
+            for pin in PINS {
+                w.set_moder(pin, moder.moder(pin));
+            }
+        });
+    });
+
+    // Clearing PE resets the peripheral state machine, including the
+    // busy flag, while keeping its configuration. PE must read back as
+    // cleared before it is set again for the reset to take effect.
+    let i2c = stm32_metapac::I2C2;
+    critical_section::with(|_| i2c.cr1().modify(|w| w.set_pe(false)));
+    while i2c.cr1().read().pe() {}
+    critical_section::with(|_| i2c.cr1().modify(|w| w.set_pe(true)));
+}
+
+/// Provides a human-readable description of an I2C error
+/// returned while communicating with the sensor. In particular,
+/// this distinguishes a timeout (e.g. from excessive clock
+/// stretching) from the sensor failing to acknowledge.
+///
+/// # Arguments
+///
+/// * `error` - An Embassy I2C error
+///
+/// # Returns
+///
+/// A static message describing the likely cause of the error.
+///
+/// # Examples
+///
+/// ```
+/// match pmsa003i::fetch_data(&mut i2c).await {
+///     Ok(sensor_data) => print_all_regs(&sensor_data, Verbosity::Normal, &mut output),
+///     Err(e) => output.log(
+///         Verbosity::Quiet,
+///         format_args!("Error reading registers: {}", i2c_error_message(&e)),
+///     ),
+/// }
+/// ```
+fn i2c_error_message(error: &Error) -> &'static str {
+    match error {
+        Error::Timeout => "Timed out waiting for sensor, the bus may be stretched or stuck",
+        Error::Nack => "Sensor did not acknowledge, check wiring and address",
+        Error::Bus => "Bus error, check wiring and pull-up resistors",
+        Error::Arbitration => "Arbitration lost to another bus controller",
+        Error::Overrun => "Data overrun while reading from sensor",
+        _ => "Unexpected I2C error",
+    }
+}
+
+/// Debugging helper function to print all data from
+/// the PMSA003I sensor. Simply iterates over all data
+/// and prints the register address and corresponding data.
+///
+/// # Arguments
+///
+/// * `buffer` - Entire array of u8 data from the sensor
+/// * `level` - The level the registers are printed at
+/// * `output` - Where the registers are printed
+///
+/// # Examples
+///
+/// ```
+/// match pmsa003i::fetch_data(&mut i2c).await {
+///     Ok(sensor_data) => {
+///         print_all_regs(&sensor_data, Verbosity::Verbose, &mut output);
+///     }
+///     Err(e) => output.log(Verbosity::Quiet, format_args!("Error reading registers: {:?}", e)),
+/// }
+/// ```
+#[cfg_attr(any(feature = "demo", feature = "remote"), allow(dead_code))]
+fn print_all_regs<O: aqi::Output>(buffer: &[u8], level: Verbosity, output: &mut O) {
+    for (i, &value) in buffer.iter().enumerate() {
+        output.log(level, format_args!("Register 0x{:02X}: 0x{:02X}", i, value));
+    }
+}
+
+/// Flashes SOS on the LEDs forever after a panic, selected with the
+/// `panic-sos` feature in place of `panic_probe`. Field units usually
+/// have no debug probe to report the panic to, so this tells the user
+/// the device has faulted and needs to be power cycled.
+///
+/// Interrupts are disabled, so nothing else runs, and the pattern is
+/// timed with busy-wait delays rather than the executor. A panic before
+/// the peripherals are initialized leaves the LEDs dark, as their GPIO
+/// port is not yet clocked.
+#[cfg(feature = "panic-sos")]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    cortex_m::interrupt::disable();
+
+    // SAFETY: With interrupts disabled, nothing else runs after the
+    // panic, so the LED pins can be taken over from the LedController
+    // which owned them.
+    let mut led_controller = unsafe {
+        LedController::new(
+            PE8::steal(),
+            PE9::steal(),
+            PE10::steal(),
+            PE11::steal(),
+            PE12::steal(),
+            PE13::steal(),
+            PE14::steal(),
+            PE15::steal(),
+        )
+    };
+
+    let mask = led_roles_mask(PANIC_LEDS);
+    loop {
+        for (lit, duration_ms) in BlinkPattern::SOS.steps() {
+            led_controller.set_mask(if lit { mask } else { 0 });
+            cortex_m::asm::delay(duration_ms * (CORE_CLOCK_HZ / 1000));
         }
     }
 }
@@ -0,0 +1,251 @@
+//! USB serial output
+//!
+//! Reading output over USB CDC-ACM, so that the board shows up as a
+//! serial port on any computer and works without a debug probe. Output
+//! is formatted into a buffer and sent to the host by a background task,
+//! so the measurement loop never waits on the host. If the buffer fills
+//! up, such as while no terminal has the port open, further output is
+//! dropped until there is room again.
+//!
+//! With the `remote` feature, PM2.5 concentrations sent by the host, one
+//! per line, are also received, for use as a remote display.
+
+#[cfg(feature = "remote")]
+use aqi::LineBuffer;
+use core::fmt::{self, Write};
+use embassy_executor::Spawner;
+use embassy_stm32::gpio::{Level, Output, Speed};
+use embassy_stm32::peripherals::{PA11, PA12, USB};
+use embassy_stm32::time::Hertz;
+use embassy_stm32::usb::Driver;
+use embassy_stm32::{bind_interrupts, usb};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::pipe::Pipe;
+#[cfg(feature = "remote")]
+use embassy_sync::signal::Signal;
+use embassy_time::{block_for, Duration};
+#[cfg(feature = "remote")]
+use embassy_usb::class::cdc_acm::Receiver;
+use embassy_usb::class::cdc_acm::{CdcAcmClass, Sender, State};
+use embassy_usb::driver::EndpointError;
+use embassy_usb::{Builder, UsbDevice};
+use static_cell::StaticCell;
+
+/// USB vendor ID. This is a placeholder suitable for development only,
+/// and must be replaced with an assigned ID before distributing devices.
+const USB_VID: u16 = 0xc0de;
+/// USB product ID, also a development placeholder.
+const USB_PID: u16 = 0xcafe;
+/// Largest packet sent to the host at once. 64 bytes is the maximum
+/// for a full speed bulk endpoint.
+const MAX_PACKET_SIZE: u16 = 64;
+/// Bytes of formatted output held while waiting for the host to read
+/// them, enough for several readings.
+const BUFFER_SIZE: usize = 1024;
+
+/// Formatted output waiting to be sent to the host.
+static BUFFER: Pipe<CriticalSectionRawMutex, BUFFER_SIZE> = Pipe::new();
+
+/// Longest line accepted from the host, ample for a concentration.
+#[cfg(feature = "remote")]
+const INPUT_LINE_SIZE: usize = 32;
+
+/// The latest concentration received from the host and not yet read.
+/// Only the latest is kept, as older values are stale for a display.
+#[cfg(feature = "remote")]
+static CONCENTRATION: Signal<CriticalSectionRawMutex, u16> = Signal::new();
+
+bind_interrupts!(struct UsbIrqs {
+    USB_LP_CAN_RX0 => usb::InterruptHandler<USB>;
+});
+
+/// Provides the clock configuration needed for USB. The USB peripheral
+/// requires a 48MHz clock, which is derived from the 8MHz clock the
+/// on-board ST-LINK supplies to the HSE input, multiplied to a 72MHz
+/// system clock. Embassy divides a 72MHz PLL output by 1.5 for USB
+/// automatically.
+///
+/// # Examples
+///
+/// ```
+/// let p = embassy_stm32::init(usb_output::clock_config());
+/// ```
+pub fn clock_config() -> embassy_stm32::Config {
+    use embassy_stm32::rcc::*;
+
+    let mut config = embassy_stm32::Config::default();
+    config.rcc.hse = Some(Hse {
+        freq: Hertz(8_000_000),
+        mode: HseMode::Bypass,
+    });
+    config.rcc.pll = Some(Pll {
+        src: PllSource::HSE,
+        prediv: PllPreDiv::DIV1,
+        mul: PllMul::MUL9,
+    });
+    config.rcc.sys = Sysclk::PLL1_P;
+    config.rcc.ahb_pre = AHBPrescaler::DIV1;
+    config.rcc.apb1_pre = APBPrescaler::DIV2;
+    config.rcc.apb2_pre = APBPrescaler::DIV1;
+    config
+}
+
+/// Prints a line over USB, in place of `hprintln!` when the `usb`
+/// feature is enabled, since semihosting requires a debugger.
+///
+/// # Examples
+///
+/// ```
+/// usb_println!("Device responded to ping");
+/// ```
+macro_rules! usb_println {
+    ($($arg:tt)*) => {
+        $crate::usb_output::print_line(format_args!($($arg)*))
+    };
+}
+pub(crate) use usb_println;
+
+/// Writes a line to the output buffer, ending it with CRLF for
+/// serial terminals. Used by `usb_println!`.
+pub fn print_line(args: fmt::Arguments) {
+    // BufferWriter never fails, it only drops output which does not fit
+    let _ = BufferWriter.write_fmt(args);
+    let _ = BufferWriter.write_str("\r\n");
+}
+
+/// Writes formatted output into BUFFER, dropping whatever does not fit.
+struct BufferWriter;
+
+impl Write for BufferWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut bytes = s.as_bytes();
+        while !bytes.is_empty() {
+            match BUFFER.try_write(bytes) {
+                Ok(written) => bytes = &bytes[written..],
+                Err(_) => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sets up the USB peripheral as a CDC-ACM serial port and spawns
+/// the tasks which run it. The clocks must have been configured with
+/// `clock_config`. This must only be called once.
+///
+/// The board enumerates using the USB USER connector as a serial
+/// port, e.g. `/dev/ttyACM0` on Linux or a COM port on Windows.
+/// The baud rate and other line settings chosen on the host are
+/// ignored.
+///
+/// # Arguments
+///
+/// * `spawner` - Spawner for the USB tasks
+/// * `usb` - The USB peripheral
+/// * `dp` - The USB D+ pin
+/// * `dm` - The USB D- pin
+///
+/// # Examples
+///
+/// ```
+/// usb_output::init(spawner, p.USB, p.PA12, p.PA11);
+/// ```
+pub fn init(spawner: Spawner, usb: USB, mut dp: PA12, dm: PA11) {
+    // Pull D+ low briefly, so that the host sees a disconnect and
+    // enumerates the device again after it is reset or reflashed
+    {
+        let _dp = Output::new(&mut dp, Level::Low, Speed::Low);
+        block_for(Duration::from_millis(10));
+    }
+
+    let driver = Driver::new(usb, UsbIrqs, dp, dm);
+
+    let mut config = embassy_usb::Config::new(USB_VID, USB_PID);
+    config.manufacturer = Some("quick_aqi");
+    config.product = Some("Quick AQI");
+    config.max_power = 100;
+    config.max_packet_size_0 = 64;
+
+    static CONFIG_DESCRIPTOR: StaticCell<[u8; 256]> = StaticCell::new();
+    static BOS_DESCRIPTOR: StaticCell<[u8; 256]> = StaticCell::new();
+    static CONTROL_BUF: StaticCell<[u8; 64]> = StaticCell::new();
+    static STATE: StaticCell<State> = StaticCell::new();
+
+    let mut builder = Builder::new(
+        driver,
+        config,
+        CONFIG_DESCRIPTOR.init([0; 256]),
+        BOS_DESCRIPTOR.init([0; 256]),
+        &mut [],
+        CONTROL_BUF.init([0; 64]),
+    );
+    let class = CdcAcmClass::new(&mut builder, STATE.init(State::new()), MAX_PACKET_SIZE);
+    let device = builder.build();
+
+    let (sender, receiver) = class.split();
+    spawner.must_spawn(usb_task(device));
+    spawner.must_spawn(serial_task(sender));
+    #[cfg(feature = "remote")]
+    spawner.must_spawn(input_task(receiver));
+    #[cfg(not(feature = "remote"))]
+    let _ = receiver;
+}
+
+/// Waits for the next PM2.5 concentration sent by the host, in µg/m³.
+/// The host sends one decimal concentration per line, e.g. `41.2`,
+/// which is rounded to the nearest whole µg/m³. If several arrive
+/// before this is called, only the latest is returned.
+///
+/// # Examples
+///
+/// ```
+/// let pm25_concentration = usb_output::read_concentration().await;
+/// ```
+#[cfg(feature = "remote")]
+pub async fn read_concentration() -> u16 {
+    CONCENTRATION.wait().await
+}
+
+/// Runs the USB device, handling enumeration and control requests.
+#[embassy_executor::task]
+async fn usb_task(mut device: UsbDevice<'static, Driver<'static, USB>>) -> ! {
+    device.run().await
+}
+
+/// Sends buffered output to the host whenever a terminal is connected.
+#[embassy_executor::task]
+async fn serial_task(mut sender: Sender<'static, Driver<'static, USB>>) -> ! {
+    let mut packet = [0; MAX_PACKET_SIZE as usize];
+    loop {
+        sender.wait_connection().await;
+        loop {
+            let len = BUFFER.read(&mut packet).await;
+            if let Err(EndpointError::Disabled) = sender.write_packet(&packet[..len]).await {
+                // The host disconnected, so wait for it to connect again
+                break;
+            }
+        }
+    }
+}
+
+/// Receives concentrations sent by the host, one per line, printing an
+/// error for any line which is not a valid concentration.
+#[cfg(feature = "remote")]
+#[embassy_executor::task]
+async fn input_task(mut receiver: Receiver<'static, Driver<'static, USB>>) -> ! {
+    let mut packet = [0; MAX_PACKET_SIZE as usize];
+    let mut line: LineBuffer<INPUT_LINE_SIZE> = LineBuffer::new();
+    loop {
+        receiver.wait_connection().await;
+        // Read until the host disconnects, then wait for it to connect again
+        while let Ok(len) = receiver.read_packet(&mut packet).await {
+            for &byte in &packet[..len] {
+                match line.push(byte) {
+                    Some(Ok(pm25)) => CONCENTRATION.signal(pm25),
+                    Some(Err(e)) => usb_println!("Error parsing input: {}", e),
+                    None => {}
+                }
+            }
+        }
+    }
+}